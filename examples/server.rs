@@ -0,0 +1,142 @@
+//! Minimal ranking microservice over a warm graph.
+//!
+//! Reads a tab separated edge list (`source\ttarget`) from stdin,
+//! calculates the PageRank once and then answers queries over HTTP:
+//!
+//! - `GET /score/:node`
+//! - `GET /top?k=10`
+//! - `GET /related/:node?k=10`
+//!
+//! ```text
+//! cat edges.tsv | cargo run --example server -- 127.0.0.1:3000
+//! ```
+use simple_pagerank::{ImportOptions, Pagerank, ParseMode};
+use std::env;
+use std::io::{self, BufRead, BufReader, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::Arc;
+use std::thread;
+
+fn main() -> io::Result<()> {
+    let addr = env::args()
+        .nth(1)
+        .unwrap_or_else(|| "127.0.0.1:3000".to_string());
+
+    let mut pr = Pagerank::<String>::new();
+    let options = ImportOptions {
+        mode: ParseMode::Lenient,
+        ..ImportOptions::default()
+    };
+    pr.import_tsv(io::stdin().lock(), &options)
+        .map_err(io::Error::other)?;
+    pr.calculate();
+
+    // The graph is only read from now on, so it can be shared by every
+    // connection thread without any lock.
+    let pr = Arc::new(pr);
+
+    println!("Serving {} nodes on http://{}", pr.len(), addr);
+
+    for stream in TcpListener::bind(addr)?.incoming() {
+        let stream = stream?;
+        let pr = Arc::clone(&pr);
+        thread::spawn(move || {
+            if let Err(err) = handle(&pr, stream) {
+                eprintln!("connection error: {}", err);
+            }
+        });
+    }
+
+    Ok(())
+}
+
+fn handle(
+    pr: &Pagerank<String>,
+    mut stream: TcpStream,
+) -> io::Result<()> {
+    let mut request = String::new();
+    BufReader::new(&stream).read_line(&mut request)?;
+
+    let path = request.split(' ').nth(1).unwrap_or("/");
+    let (path, query) = match path.find('?') {
+        Some(pos) => (&path[..pos], &path[pos + 1..]),
+        None => (path, ""),
+    };
+    let k = query
+        .split('&')
+        .filter_map(|pair| pair.strip_prefix("k="))
+        .find_map(|k| k.parse().ok())
+        .unwrap_or(10);
+
+    let (status, body) = if let Some(node) =
+        path.strip_prefix("/score/")
+    {
+        match pr.get_score(node.to_string()) {
+            Some(score) => (
+                "200 OK",
+                format!(
+                    "{{\"node\":{},\"score\":{}}}",
+                    quote(node),
+                    score
+                ),
+            ),
+            None => (
+                "404 Not Found",
+                "{\"error\":\"unknown node\"}".into(),
+            ),
+        }
+    } else if path == "/top" {
+        ("200 OK", to_json(pr.nodes().into_iter().take(k)))
+    } else if let Some(node) = path.strip_prefix("/related/") {
+        (
+            "200 OK",
+            to_json(
+                pr.similar_by_cocitation(node.to_string(), k)
+                    .into_iter(),
+            ),
+        )
+    } else {
+        ("404 Not Found", "{\"error\":\"unknown endpoint\"}".into())
+    };
+
+    write!(
+        stream,
+        "HTTP/1.1 {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        body.len(),
+        body
+    )
+}
+
+fn to_json<'a>(
+    nodes: impl Iterator<Item = (&'a String, f64)>,
+) -> String {
+    let items = nodes
+        .map(|(node, score)| {
+            format!(
+                "{{\"node\":{},\"score\":{}}}",
+                quote(node),
+                score
+            )
+        })
+        .collect::<Vec<String>>();
+
+    format!("[{}]", items.join(","))
+}
+
+fn quote(value: &str) -> String {
+    let mut quoted = String::with_capacity(value.len() + 2);
+    quoted.push('"');
+    for c in value.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            c if (c as u32) < 0x20 => {
+                quoted.push_str(&format!("\\u{:04x}", c as u32))
+            }
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
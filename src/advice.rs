@@ -0,0 +1,141 @@
+//! Parameter suggestions from the shape of the graph
+#[cfg(not(feature = "std"))]
+use crate::compat::prelude::*;
+use crate::Pagerank;
+use core::hash::{BuildHasher, Hash};
+
+/// Per node change in score the suggested tolerance aims for
+const TARGET_CHANGE: f64 = 1e-4;
+
+/// Calculation parameters suggested by `Pagerank::suggest_parameters`
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParameterSuggestion {
+    /// Damping factor, as expected by `set_damping_factor`
+    pub damping: u8,
+    /// Convergence for `calculate_with_convergence`
+    pub tolerance: f64,
+    /// Number of iterations the calculation should converge within. It
+    /// is an upper bound to check against, or to pass to
+    /// `calculate_linear`.
+    pub max_iterations: usize,
+    /// Why each value was picked, in plain words
+    pub rationale: Vec<String>,
+}
+
+impl<T, S> Pagerank<T, S>
+where
+    T: Eq + Hash + Clone,
+    S: BuildHasher,
+{
+    /// Inspects the size of the graph, the fraction of nodes without out
+    /// edges (dangling nodes) and how skewed the in degrees are, and
+    /// suggests a damping factor, a tolerance and a number of iterations,
+    /// each with the reason it was picked.
+    ///
+    /// The suggestion is a starting point for users who don't want to
+    /// tune the calculation themselves; nothing is changed in the graph.
+    pub fn suggest_parameters(&self) -> ParameterSuggestion {
+        let mut rationale = Vec::new();
+        let len = self.nodes.len();
+        if len == 0 {
+            rationale.push(
+                "the graph is empty, so the defaults are kept"
+                    .to_owned(),
+            );
+            return ParameterSuggestion {
+                damping: 85,
+                tolerance: 0.01,
+                max_iterations: 100,
+                rationale,
+            };
+        }
+
+        let dangling =
+            self.nodes.iter().filter(|n| n.out_edges == 0).count()
+                as f64
+                / len as f64;
+        let average = self.edges as f64 / len as f64;
+        let max_in_degree =
+            self.nodes.iter().map(|n| n.in_degree).max().unwrap_or(0);
+        let skew = if average > 0f64 {
+            max_in_degree as f64 / average
+        } else {
+            0f64
+        };
+
+        let damping = if dangling > 0.5 {
+            rationale.push(format!(
+                "{:.0}% of the nodes have no out edges and pass their score to no one, so a damping of 0.75 keeps the teleport term from being drowned by that loss",
+                dangling * 100f64
+            ));
+            75
+        } else if skew > 1000f64 {
+            rationale.push(format!(
+                "the most linked node has {:.0} times the average in degree, so a damping of 0.80 limits how much score piles up on hubs and speeds up convergence",
+                skew
+            ));
+            80
+        } else {
+            rationale.push(
+                "the graph has no unusual shape, so the standard damping of 0.85 is kept"
+                    .to_owned(),
+            );
+            85
+        };
+
+        // The convergence is the l2 norm of the change over the nodes
+        // with in edges, so a change of TARGET_CHANGE on every node
+        // gives TARGET_CHANGE / sqrt(len)
+        let tolerance = TARGET_CHANGE / (len as f64).sqrt();
+        rationale.push(format!(
+            "a tolerance of {:.1e} stops once scores change by about {:.0e} per node, for {} nodes",
+            tolerance, TARGET_CHANGE, len
+        ));
+
+        // The error shrinks by about the damping factor every iteration
+        let d = damping as f64 / 100f64;
+        let max_iterations =
+            (tolerance.ln() / d.ln()).ceil() as usize + 10;
+        rationale.push(format!(
+            "the error shrinks by about {:.2} per iteration, so {} iterations should be enough",
+            d, max_iterations
+        ));
+
+        ParameterSuggestion {
+            damping,
+            tolerance,
+            max_iterations,
+            rationale,
+        }
+    }
+}
+
+#[cfg(all(test, feature = "generators"))]
+mod tests {
+    use crate::generators;
+    use crate::Pagerank;
+
+    #[test]
+    fn test_suggest_parameters() {
+        let suggestion = Pagerank::<u32>::new().suggest_parameters();
+        assert_eq!(85, suggestion.damping);
+        assert_eq!(1, suggestion.rationale.len());
+
+        let mut pr = generators::ring(100);
+        let suggestion = pr.suggest_parameters();
+        assert_eq!(85, suggestion.damping);
+        assert!((suggestion.tolerance - 1e-5).abs() < 1e-12);
+        assert_eq!(3, suggestion.rationale.len());
+
+        pr.set_damping_factor(suggestion.damping).unwrap();
+        let result =
+            pr.calculate_with_convergence(suggestion.tolerance);
+        assert!(result.iterations <= suggestion.max_iterations);
+
+        let mut fan_out = Pagerank::<u32>::new();
+        for leaf in 1..10 {
+            fan_out.add_edge(0, leaf);
+        }
+        assert_eq!(75, fan_out.suggest_parameters().damping);
+    }
+}
@@ -0,0 +1,226 @@
+//! Graph metrics besides PageRank, as baselines to compare it against
+#[cfg(not(feature = "std"))]
+use crate::compat::prelude::*;
+use crate::compat::HashMap;
+use crate::graph::{cmp_scores, Node};
+use crate::Pagerank;
+use alloc::collections::VecDeque;
+use core::hash::{BuildHasher, Hash};
+
+impl<T, S> Pagerank<T, S>
+where
+    T: Eq + Hash + Clone,
+    S: BuildHasher,
+{
+    /// Returns up to `k` nodes which are most frequently co-cited with
+    /// the given node, that is, nodes linked from the same sources.
+    ///
+    /// Similarity is the weighted Jaccard index of both in-edge lists,
+    /// where repeated edges from the same source count as weight.
+    pub fn similar_by_cocitation(
+        &self,
+        node: T,
+        k: usize,
+    ) -> Vec<(&T, f64)> {
+        let id = match self.node_positions.get(&node) {
+            Some(id) => *id,
+            None => return Vec::new(),
+        };
+
+        let mut citers = HashMap::new();
+        for source in self.in_edges(id).iter() {
+            *citers.entry(*source).or_insert(0usize) += 1;
+        }
+
+        let total = self.len_in_edges(id);
+        let mut similar = self
+            .nodes
+            .iter()
+            .enumerate()
+            .filter(|(other, _)| *other != id)
+            .filter_map(|(other, n)| {
+                let sources = self.in_edges(other);
+                let mut common = HashMap::new();
+                for source in sources.iter() {
+                    if citers.contains_key(source) {
+                        *common.entry(*source).or_insert(0usize) += 1;
+                    }
+                }
+
+                let shared = common
+                    .iter()
+                    .map(|(source, count)| {
+                        *count.min(&citers[source])
+                    })
+                    .sum::<usize>();
+
+                if shared == 0 {
+                    return None;
+                }
+
+                let union = total + sources.len() - shared;
+                Some((&n.node, shared as f64 / union as f64))
+            })
+            .collect::<Vec<(&T, f64)>>();
+
+        similar.sort_by(|a, b| cmp_scores(b.1, a.1));
+        similar.truncate(k);
+
+        similar
+    }
+
+    /// Return all nodes, sorted by their number of in edges. Useful as a
+    /// cheap baseline to compare against the PageRank.
+    pub fn rank_by_in_degree(&self) -> Vec<(&T, f64)> {
+        self.rank_by(|n| n.in_degree as f64)
+    }
+
+    /// Return all nodes, sorted by their number of out edges
+    pub fn rank_by_out_degree(&self) -> Vec<(&T, f64)> {
+        self.rank_by(|n| n.out_edges as f64)
+    }
+
+    /// Ranks every node by a value computed from the node itself
+    fn rank_by<F>(&self, value: F) -> Vec<(&T, f64)>
+    where
+        F: Fn(&Node<T>) -> f64,
+    {
+        let mut ranked = self
+            .nodes
+            .iter()
+            .map(|n| (&n.node, value(n)))
+            .collect::<Vec<(&T, f64)>>();
+
+        ranked.sort_by(|a, b| cmp_scores(b.1, a.1));
+
+        ranked
+    }
+
+    /// Return all nodes, sorted by their harmonic centrality.
+    ///
+    /// The harmonic centrality of a node is the sum of the inverse
+    /// distances from every other node to it, following the edges.
+    /// Nodes which cannot reach it contribute nothing.
+    pub fn harmonic_centrality(&self) -> Vec<(&T, f64)> {
+        self.rank_by_distances(|distances| {
+            distances.iter().map(|d| 1f64 / *d as f64).sum()
+        })
+    }
+
+    /// Return all nodes, sorted by their closeness centrality.
+    ///
+    /// The closeness of a node is the inverse of the average distance
+    /// from the nodes which can reach it, scaled by the fraction of the
+    /// graph that can reach it (Wasserman and Faust) so nodes in small
+    /// components don't get an inflated value.
+    pub fn closeness_centrality(&self) -> Vec<(&T, f64)> {
+        let others = self.nodes.len().saturating_sub(1) as f64;
+        self.rank_by_distances(|distances| {
+            let total = distances.iter().sum::<usize>();
+            if total == 0 {
+                return 0f64;
+            }
+
+            let reachable = distances.len() as f64;
+            (reachable / total as f64) * (reachable / others)
+        })
+    }
+
+    /// Ranks every node by a value computed from the distances of all
+    /// the nodes which can reach it. Distances are found with a breadth
+    /// first search over the in edges.
+    fn rank_by_distances<F>(&self, value: F) -> Vec<(&T, f64)>
+    where
+        F: Fn(&[usize]) -> f64,
+    {
+        let mut distance = vec![usize::MAX; self.nodes.len()];
+        let mut queue = VecDeque::new();
+        let mut distances = Vec::new();
+
+        let mut ranked = self
+            .nodes
+            .iter()
+            .enumerate()
+            .map(|(id, n)| {
+                distance.iter_mut().for_each(|d| *d = usize::MAX);
+                distances.clear();
+                distance[id] = 0;
+                queue.push_back(id);
+
+                while let Some(current) = queue.pop_front() {
+                    for source in self.in_edges(current).iter() {
+                        if distance[*source] == usize::MAX {
+                            distance[*source] = distance[current] + 1;
+                            distances.push(distance[*source]);
+                            queue.push_back(*source);
+                        }
+                    }
+                }
+
+                (&n.node, value(&distances))
+            })
+            .collect::<Vec<(&T, f64)>>();
+
+        ranked.sort_by(|a, b| cmp_scores(b.1, a.1));
+
+        ranked
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Pagerank;
+
+    #[test]
+    fn test_similar_by_cocitation() {
+        let mut pr = Pagerank::<&str>::new();
+        pr.add_edge("a", "x");
+        pr.add_edge("a", "y");
+        pr.add_edge("b", "x");
+        pr.add_edge("b", "y");
+        pr.add_edge("c", "x");
+        pr.add_edge("c", "z");
+
+        let similar = pr.similar_by_cocitation("x", 5);
+        assert_eq!(
+            vec!["y", "z"],
+            similar.iter().map(|(n, _)| **n).collect::<Vec<&str>>()
+        );
+        assert_eq!(2.0 / 3.0, similar[0].1);
+        assert_eq!(1, pr.similar_by_cocitation("x", 1).len());
+        assert!(pr.similar_by_cocitation("unknown", 5).is_empty());
+    }
+
+    #[test]
+    fn test_harmonic_and_closeness() {
+        let mut pr = Pagerank::<&str>::new();
+        pr.add_edge("a", "b");
+        pr.add_edge("b", "c");
+        pr.add_edge("d", "c");
+
+        let harmonic = pr.harmonic_centrality();
+        assert_eq!(("c", 2.5), (*harmonic[0].0, harmonic[0].1));
+        assert_eq!(("b", 1.0), (*harmonic[1].0, harmonic[1].1));
+
+        let closeness = pr.closeness_centrality();
+        assert_eq!("c", *closeness[0].0);
+        assert_eq!((3.0 / 4.0) * (3.0 / 3.0), closeness[0].1);
+        assert_eq!(0.0, closeness[3].1);
+    }
+
+    #[test]
+    fn test_rank_by_degree() {
+        let mut pr = Pagerank::<&str>::new();
+        pr.add_edge("a", "b");
+        pr.add_edge("a", "c");
+        pr.add_edge("c", "b");
+
+        let in_degree = pr.rank_by_in_degree();
+        assert_eq!(("b", 2.0), (*in_degree[0].0, in_degree[0].1));
+        assert_eq!(("c", 1.0), (*in_degree[1].0, in_degree[1].1));
+
+        let out_degree = pr.rank_by_out_degree();
+        assert_eq!(("a", 2.0), (*out_degree[0].0, out_degree[0].1));
+        assert_eq!(0.0, out_degree[2].1);
+    }
+}
@@ -0,0 +1,251 @@
+//! Transactional batches of edge insertions
+#[cfg(not(feature = "std"))]
+use crate::compat::prelude::*;
+use crate::Pagerank;
+use core::hash::{BuildHasher, Hash};
+
+/// How an edge added during a batch was stored in its target in edges
+pub(crate) enum StoredEdge {
+    /// Appended at the end of the list
+    Pushed,
+    /// Replaced the source and weight at the given slot, by reservoir
+    /// sampling
+    Replaced(usize, usize, f64),
+    /// Only counted, by reservoir sampling
+    Skipped,
+    /// Removed from the given slot, with its weight, and from the given
+    /// position of the edge log with its sequence number if it was
+    /// logged
+    Removed(usize, f64, Option<(usize, u64)>),
+}
+
+/// Journal of the changes made since `begin_batch`, to undo them
+pub(crate) struct Batch {
+    /// Number of nodes when the batch started
+    nodes: usize,
+    /// State of the random generator when the batch started
+    rng: u64,
+    /// Personalization vector when the batch started
+    personalization: Vec<(usize, f64)>,
+    /// Length of the edge log when the batch started
+    edge_log: usize,
+    /// Edges added or removed during the batch, as source, target,
+    /// storage and the out weight of the source before the change
+    edges: Vec<(usize, usize, StoredEdge, f64)>,
+}
+
+impl Batch {
+    /// Records an edge added or removed during the batch
+    pub(crate) fn record_edge(
+        &mut self,
+        source: usize,
+        target: usize,
+        stored: StoredEdge,
+        out_weight: f64,
+    ) {
+        self.edges.push((source, target, stored, out_weight));
+    }
+}
+
+impl<T, S> Pagerank<T, S>
+where
+    T: Eq + Hash + Clone,
+    S: BuildHasher,
+{
+    /// Starts a batch of edge insertions, which can later be committed
+    /// or rolled back as a whole. Useful to cleanly discard a chunk of
+    /// input which fails to parse midway.
+    ///
+    /// Batches don't nest: if a batch is already open it is kept, and a
+    /// single commit or rollback ends it.
+    pub fn begin_batch(&mut self) {
+        if self.batch.is_none() {
+            self.batch = Some(Batch {
+                nodes: self.nodes.len(),
+                rng: self.rng,
+                personalization: self.personalization.clone(),
+                edge_log: self.edge_log.as_ref().map_or(0, Vec::len),
+                edges: Vec::new(),
+            });
+        }
+    }
+
+    /// Keeps every change made since `begin_batch`, then removes nodes
+    /// if the graph is over its memory budget, which is not enforced
+    /// while the batch is open. Returns false if no batch was open.
+    pub fn commit_batch(&mut self) -> bool {
+        let batch = match self.batch.take() {
+            Some(batch) => batch,
+            None => return false,
+        };
+        let keep = batch
+            .edges
+            .last()
+            .map_or([usize::MAX; 2], |(source, target, _, _)| {
+                [*source, *target]
+            });
+        self.enforce_max_memory(keep);
+        true
+    }
+
+    /// Undoes every edge and node added since `begin_batch`, leaving the
+    /// graph as it was when the batch started. Returns false if no batch
+    /// was open.
+    pub fn rollback_batch(&mut self) -> bool {
+        let batch = match self.batch.take() {
+            Some(batch) => batch,
+            None => return false,
+        };
+        self.thaw();
+
+        for (source, target, stored, out_weight) in
+            batch.edges.into_iter().rev()
+        {
+            let node = &mut self.nodes[target];
+            match stored {
+                StoredEdge::Pushed => {
+                    node.in_edges.pop();
+                    node.in_weights.truncate(node.in_edges.len());
+                }
+                StoredEdge::Replaced(slot, previous, weight) => {
+                    node.in_edges[slot] = previous;
+                    node.set_edge_weight(slot, weight);
+                }
+                StoredEdge::Skipped => {}
+                StoredEdge::Removed(slot, weight, logged) => {
+                    node.in_edges.insert(slot, source);
+                    if !node.in_weights.is_empty() {
+                        node.in_weights.insert(slot, weight);
+                    } else {
+                        node.set_edge_weight(slot, weight);
+                    }
+                    node.in_degree += 1;
+                    if let Some(unique) = self.unique_edges.as_mut() {
+                        unique.insert((source, target));
+                    }
+                    self.nodes[source].out_edges += 1;
+                    self.nodes[source].out_weight = out_weight;
+                    self.edges += 1;
+                    if let (Some(log), Some((at, sequence))) =
+                        (self.edge_log.as_mut(), logged)
+                    {
+                        log.insert(
+                            at,
+                            (source, target, sequence, weight),
+                        );
+                    }
+                    continue;
+                }
+            }
+            node.in_degree -= 1;
+            if let Some(unique) = self.unique_edges.as_mut() {
+                unique.remove(&(source, target));
+            }
+            self.nodes[source].out_edges -= 1;
+            self.nodes[source].out_weight = out_weight;
+            self.edges -= 1;
+        }
+
+        self.edge_weights = None;
+        self.sweep = None;
+        self.components = None;
+        self.node_types.truncate(batch.nodes);
+        let nodes = batch.nodes;
+        self.pinned.retain(|(id, _)| *id < nodes);
+        for node in self.nodes.drain(batch.nodes..) {
+            self.node_positions.remove(&node.node);
+        }
+
+        if let Some(log) = self.edge_log.as_mut() {
+            log.truncate(batch.edge_log);
+        }
+
+        self.rng = batch.rng;
+        self.personalization = batch.personalization;
+        self.nodes_with_in_edges = None;
+
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Pagerank;
+
+    #[test]
+    fn test_rollback_batch() {
+        let mut pr = Pagerank::<u32>::new();
+        pr.set_in_edges_cap(2);
+        pr.add_edge(1, 0);
+        pr.add_edge(2, 0);
+
+        let degrees = pr
+            .degrees()
+            .into_iter()
+            .map(|(n, i, o)| (*n, i, o))
+            .collect::<Vec<(u32, usize, usize)>>();
+        let in_edges = pr.nodes[1].in_edges.clone();
+        let out_weight = pr.nodes[0].out_weight;
+
+        pr.begin_batch();
+        for source in 3..20 {
+            pr.add_edge_weighted(source, 0, 0.1 * source as f64)
+                .unwrap();
+            pr.add_edge(0, source);
+            pr.add_edge_weighted(1, source, 0.3).unwrap();
+        }
+        pr.add_edge(1, 2);
+        assert!(pr.rollback_batch());
+        assert!(!pr.rollback_batch());
+
+        assert_eq!(
+            degrees,
+            pr.degrees()
+                .into_iter()
+                .map(|(n, i, o)| (*n, i, o))
+                .collect::<Vec<(u32, usize, usize)>>()
+        );
+        assert_eq!(in_edges, pr.nodes[1].in_edges);
+        // Weights may stay materialized, but only as unit weights
+        assert!(pr.nodes[1].in_weights.iter().all(|w| *w == 1.0));
+        assert_eq!(out_weight, pr.nodes[0].out_weight);
+        assert_eq!(2, pr.len_node());
+        assert_eq!(None, pr.get_score(3));
+        assert_eq!(3, pr.insert_node(3));
+    }
+
+    #[test]
+    fn test_commit_batch() {
+        let mut pr = Pagerank::<&str>::new();
+        pr.begin_batch();
+        pr.add_edge("foo", "bar");
+        assert!(pr.commit_batch());
+        assert!(!pr.rollback_batch());
+        assert_eq!(2, pr.len());
+        assert_eq!(1, pr.len_node());
+    }
+
+    #[test]
+    fn test_rollback_removed_edges() {
+        let mut pr = Pagerank::<&str>::new();
+        pr.record_edge_order();
+        pr.add_edge("foo", "bar");
+        pr.add_edge_weighted("xxx", "bar", 2.0).unwrap();
+        pr.add_edge("bar", "foo");
+        let in_edges = pr.nodes[1].in_edges.clone();
+        let log = pr.edge_log.clone();
+
+        pr.begin_batch();
+        assert!(pr.remove_edge(&"foo", &"bar"));
+        pr.add_edge("foo", "xxx");
+        assert!(pr.remove_edge(&"xxx", &"bar"));
+        assert!(pr.rollback_batch());
+
+        assert_eq!(in_edges, pr.nodes[1].in_edges);
+        assert_eq!(2.0, pr.nodes[1].edge_weight(1));
+        assert_eq!(2.0, pr.nodes[2].out_weight);
+        assert_eq!(log, pr.edge_log);
+        assert_eq!(3, pr.len_node());
+        assert_eq!(Some(2), pr.get_in_edges("bar"));
+    }
+}
@@ -1,9 +1,85 @@
-use simple_pagerank::Pagerank;
-use std::io::{self, BufRead};
-use std::time::Instant;
+use simple_pagerank::{
+    ImportError, ImportOptions, Pagerank, ParseMode,
+};
+use std::env;
+use std::fs::{self, File};
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+/// Number of random nodes shown by `--preview`
+const PREVIEW_NODES: usize = 5;
+
+const USAGE: &str = "Usage: wikilink [--strict] [--preview [EDGES]] < links.tsv
+       wikilink [--strict] --follow INPUT --output OUTPUT [--interval SECS]";
 
 fn main() -> io::Result<()> {
+    let mut args = env::args().skip(1);
+    let mut preview = None;
+    let mut follow = None;
+    let mut output = None;
+    let mut interval = Duration::from_secs(60);
+    let mut options = ImportOptions {
+        source_column: 1,
+        target_column: 3,
+        skip_header: true,
+        mode: ParseMode::Lenient,
+        ..ImportOptions::default()
+    };
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--preview" => {
+                let edges = match args.next() {
+                    Some(n) => n.parse().map_err(|_| {
+                        io::Error::new(
+                            io::ErrorKind::InvalidInput,
+                            "--preview expects a number of edges",
+                        )
+                    })?,
+                    None => 10,
+                };
+                preview = Some(edges);
+            }
+            "--strict" => options.mode = ParseMode::Strict,
+            "--follow" => {
+                follow = Some(PathBuf::from(value(&mut args)?))
+            }
+            "--output" => {
+                output = Some(PathBuf::from(value(&mut args)?))
+            }
+            "--interval" => {
+                let secs =
+                    value(&mut args)?.parse().map_err(|_| {
+                        io::Error::new(
+                            io::ErrorKind::InvalidInput,
+                            "--interval expects a number of seconds",
+                        )
+                    })?;
+                interval = Duration::from_secs(secs);
+            }
+            _ => {
+                eprintln!("{}", USAGE);
+                return Ok(());
+            }
+        }
+    }
+
+    match (follow, output) {
+        (Some(input), Some(output)) => {
+            return run_follow(&input, &output, interval, options);
+        }
+        (None, None) => {}
+        _ => {
+            eprintln!("{}", USAGE);
+            return Ok(());
+        }
+    }
+
     let mut pr = Pagerank::<String>::new();
+    if preview.is_some() {
+        pr.record_edge_order();
+    }
 
     let stdin = io::stdin();
 
@@ -11,28 +87,68 @@ fn main() -> io::Result<()> {
 
     println!("Reading file and creating link graph");
 
-    for (i, line) in stdin.lock().lines().enumerate() {
-        let line = line.unwrap();
-        let words: Vec<String> = line
-            .trim()
-            .split('\t')
-            .map(|s| s.parse().unwrap())
-            .collect();
+    let report =
+        pr.import_tsv(stdin.lock(), &options).map_err(|err| {
+            io::Error::new(io::ErrorKind::InvalidData, err)
+        })?;
 
-        if words.len() != 4 {
-            continue;
-        }
+    println!("Ready in {} secs", now.elapsed().as_secs());
 
-        if i > 0 {
-            pr.add_edge(words[1].clone(), words[3].clone());
-        }
+    println!("Graph size: {}", pr.len());
+
+    if report.skipped > 0 {
+        println!(
+            "Skipped {} malformed lines, the first one at line {}",
+            report.skipped,
+            report.first_skipped_line.unwrap_or(0)
+        );
+    }
+    for anomaly in report.anomalies.iter() {
+        println!("  {}", anomaly);
+    }
+    if report.suppressed_anomalies > 0 {
+        println!(
+            "  and {} more anomalies",
+            report.suppressed_anomalies
+        );
     }
 
-    println!("Ready in {} secs", now.elapsed().as_secs());
+    if let Some(edges) = preview {
+        println!("Edges: {}", report.edges);
+        for (i, (source, target, _)) in pr
+            .edge_sequence()
+            .unwrap_or_default()
+            .into_iter()
+            .take(edges)
+            .enumerate()
+        {
+            println!("Edge {}: {} -> {}", i, source, target);
+        }
 
-    println!("Graph size: {}", pr.len());
+        let mut degrees = pr.degrees();
+        let mut state = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(1, |d| d.as_nanos() as u64)
+            | 1;
+        for i in 0..PREVIEW_NODES.min(degrees.len()) {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            let pick =
+                i + (state % (degrees.len() - i) as u64) as usize;
+            degrees.swap(i, pick);
+            let (node, in_degree, out_degree) = degrees[i];
+            println!(
+                "Node {}: {} in edges, {} out edges",
+                node, in_degree, out_degree
+            );
+        }
+
+        return Ok(());
+    }
 
     let mut times = 0;
+    pr.freeze();
 
     loop {
         let t = Instant::now();
@@ -56,3 +172,92 @@ fn main() -> io::Result<()> {
 
     Ok(())
 }
+
+/// Returns the value following a flag
+fn value(
+    args: &mut impl Iterator<Item = String>,
+) -> io::Result<String> {
+    args.next().ok_or_else(|| {
+        io::Error::new(io::ErrorKind::InvalidInput, USAGE)
+    })
+}
+
+/// Tails `input` for new edges, like `tail -f`, and every `interval`
+/// recomputes the scores if there are new edges and rewrites `output`.
+///
+/// The scores are kept between runs, so each calculation starts from the
+/// previous ranking and takes few iterations. The output is written to a
+/// temporary file which is then renamed, so readers never see a partial
+/// ranking. It runs until interrupted.
+fn run_follow(
+    input: &Path,
+    output: &Path,
+    interval: Duration,
+    mut options: ImportOptions,
+) -> io::Result<()> {
+    let mut pr = Pagerank::<String>::new();
+    let mut reader = BufReader::new(File::open(input)?);
+    let mut pending = Vec::new();
+    let mut lines = 0;
+    let mut skipped = 0;
+
+    loop {
+        let started = Instant::now();
+        let mut chunk = Vec::new();
+        while reader.read_until(b'\n', &mut pending)? > 0 {
+            // A partial line is completed on a later read
+            if pending.ends_with(b"\n") {
+                chunk.append(&mut pending);
+            }
+        }
+
+        if !chunk.is_empty() {
+            let report = pr
+                .import_tsv(&chunk[..], &options)
+                .map_err(|err| {
+                    let err = match err {
+                        ImportError::Malformed { line } => {
+                            ImportError::Malformed {
+                                line: lines + line,
+                            }
+                        }
+                        err => err,
+                    };
+                    io::Error::new(io::ErrorKind::InvalidData, err)
+                })?;
+            lines += chunk.iter().filter(|b| **b == b'\n').count();
+            skipped += report.skipped;
+            options.skip_header = false;
+
+            pr.freeze();
+            let result = pr.calculate();
+            write_ranking(&pr, output)?;
+            println!(
+                "{} nodes, {} edges, {} skipped lines, {} iterations",
+                pr.len(),
+                pr.len_node(),
+                skipped,
+                result.iterations
+            );
+        }
+
+        thread::sleep(interval.saturating_sub(started.elapsed()));
+    }
+}
+
+/// Atomically replaces `output` with the current ranking
+fn write_ranking(
+    pr: &Pagerank<String>,
+    output: &Path,
+) -> io::Result<()> {
+    let mut tmp = output.as_os_str().to_owned();
+    tmp.push(".tmp");
+
+    let mut file = BufWriter::new(File::create(&tmp)?);
+    for (node, score) in pr.nodes() {
+        writeln!(file, "{}\t{}", node, score)?;
+    }
+    file.into_inner()?.sync_all()?;
+
+    fs::rename(&tmp, output)
+}
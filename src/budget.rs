@@ -0,0 +1,176 @@
+//! Memory budget for graphs fed by unbounded streams
+#[cfg(not(feature = "std"))]
+use crate::compat::prelude::*;
+use crate::graph::Node;
+use crate::Pagerank;
+use core::hash::{BuildHasher, Hash};
+use core::mem;
+
+/// Fraction of the budget the graph is brought down to once it is
+/// exceeded, so evictions don't happen on every new edge
+const EVICT_TO: f64 = 0.75;
+
+/// Approximate hash map overhead per node, in bytes
+const MAP_OVERHEAD: usize = 8;
+
+/// Estimated bytes taken by a node and its position index entry
+fn node_bytes<T>() -> usize
+where
+    T: Eq + Hash + Clone,
+{
+    mem::size_of::<Node<T>>()
+        + mem::size_of::<T>()
+        + mem::size_of::<usize>()
+        + MAP_OVERHEAD
+}
+
+impl<T, S> Pagerank<T, S>
+where
+    T: Eq + Hash + Clone,
+    S: BuildHasher,
+{
+    /// Caps the memory used by the graph to about `bytes`, so ingesting
+    /// an unbounded stream of edges can't exhaust it.
+    ///
+    /// Whenever an added edge takes the graph over the budget, the nodes
+    /// with the lowest degree are removed with their edges, ties going to
+    /// the oldest ones, until it is back to three quarters of the budget.
+    /// The two nodes of the edge being added are always kept. While a
+    /// batch is open no node is removed, so it can still be rolled back:
+    /// the budget is enforced when the batch is committed. Pair it with
+    /// `set_in_edges_cap` to also bound the in edges of hubs.
+    ///
+    /// The memory is estimated with `estimated_memory`.
+    pub fn set_max_memory(&mut self, bytes: usize) {
+        self.max_memory = Some(bytes);
+    }
+
+    /// Lifts the memory budget
+    pub fn clear_max_memory(&mut self) {
+        self.max_memory = None;
+    }
+
+    /// Returns the number of nodes removed so far to honor the memory
+    /// budget
+    pub fn evicted_nodes(&self) -> u64 {
+        self.evicted_nodes
+    }
+
+    /// Rough estimate of the memory used by the nodes, their position
+    /// index and their edges, in bytes.
+    ///
+    /// Every added edge is counted, even those dropped by the in edges
+    /// cap, and the heap memory owned by the keys themselves, such as the
+    /// bytes of a `String`, is not. For such keys, lower the budget
+    /// accordingly, or store them as hashes with `KeyHasher`.
+    pub fn estimated_memory(&self) -> usize {
+        self.nodes.len() * node_bytes::<T>()
+            + self.edges as usize * mem::size_of::<usize>()
+    }
+
+    /// Removes the lowest degree nodes, except `keep`, if the graph is
+    /// over its memory budget and no batch is open
+    pub(crate) fn enforce_max_memory(&mut self, keep: [usize; 2]) {
+        let budget = match self.max_memory {
+            Some(_) if self.batch.is_some() => return,
+            Some(budget) if self.estimated_memory() > budget => {
+                budget
+            }
+            _ => return,
+        };
+
+        let mut excess = self
+            .estimated_memory()
+            .saturating_sub((budget as f64 * EVICT_TO) as usize);
+
+        let mut ids = (0..self.nodes.len())
+            .filter(|id| !keep.contains(id))
+            .collect::<Vec<usize>>();
+        ids.sort_by_key(|id| {
+            self.nodes[*id].in_degree + self.nodes[*id].out_edges
+        });
+
+        let mut removed = vec![false; self.nodes.len()];
+        let mut evicted = 0;
+        for id in ids {
+            if excess == 0 {
+                break;
+            }
+            let n = &self.nodes[id];
+            let freed = node_bytes::<T>()
+                + (n.in_degree + n.out_edges)
+                    * mem::size_of::<usize>();
+            excess = excess.saturating_sub(freed);
+            removed[id] = true;
+            evicted += 1;
+        }
+
+        if evicted > 0 {
+            self.remove_ids(&removed);
+            self.evicted_nodes += evicted;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Pagerank;
+
+    #[test]
+    fn test_max_memory() {
+        let mut pr = Pagerank::<u64>::new();
+        let empty = pr.estimated_memory();
+        pr.add_edge(1, 2);
+        let per_edge = pr.estimated_memory() - empty;
+
+        let budget = per_edge * 100;
+        pr.set_max_memory(budget);
+        for i in 0..10_000u64 {
+            pr.add_edge(i % 7, i);
+            assert!(pr.estimated_memory() <= budget);
+        }
+
+        assert!(pr.evicted_nodes() > 0);
+        assert!(pr.len() < 200);
+        // The hubs have the highest degree and are never evicted
+        for hub in 0..7 {
+            assert!(pr.contains(&hub));
+        }
+        assert!(pr.contains(&9_999));
+
+        pr.clear_max_memory();
+        let len = pr.len();
+        pr.add_edge(20_000, 20_001);
+        assert_eq!(len + 2, pr.len());
+    }
+
+    #[test]
+    fn test_max_memory_batch() {
+        let mut pr = Pagerank::<u64>::new();
+        pr.add_edge(1, 2);
+        let budget = pr.estimated_memory() * 20;
+        pr.set_max_memory(budget);
+
+        // Nothing is evicted while the batch can be rolled back
+        pr.begin_batch();
+        for i in 0..100u64 {
+            pr.add_edge(i % 7, i + 10);
+        }
+        assert!(pr.estimated_memory() > budget);
+        assert_eq!(0, pr.evicted_nodes());
+        assert!(pr.rollback_batch());
+        assert_eq!(2, pr.len());
+        assert_eq!(1, pr.len_node());
+
+        // The budget is enforced on commit
+        pr.begin_batch();
+        for i in 0..100u64 {
+            pr.add_edge(i % 7, i + 10);
+        }
+        assert!(pr.commit_batch());
+        assert!(pr.evicted_nodes() > 0);
+        assert!(pr.estimated_memory() <= budget);
+        assert!(pr.contains(&6));
+        assert!(pr.contains(&109));
+    }
+}
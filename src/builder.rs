@@ -0,0 +1,170 @@
+//! Graphs configured up front
+use crate::compat::HashMap;
+use crate::solver::DEFAULT_CONVERGENCE;
+use crate::{DanglingPolicy, Pagerank, PagerankError};
+use core::hash::Hash;
+
+/// Builds a `Pagerank` with all its settings given up front, before any
+/// node is added, so settings which affect new nodes, such as the
+/// damping factor for their initial score, apply to every node.
+///
+/// ```
+/// use simple_pagerank::PagerankBuilder;
+///
+/// let mut pr = PagerankBuilder::new()
+///     .damping(80)
+///     .convergence(1e-6)
+///     .max_iterations(100)
+///     .capacity(2)
+///     .build()
+///     .unwrap();
+/// pr.add_edge("a", "b");
+/// assert!(pr.calculate().converged);
+/// ```
+#[derive(Debug, Clone)]
+pub struct PagerankBuilder<T> {
+    /// Damping factor, as given to `set_damping_factor`
+    damping: u8,
+    /// Convergence used by `calculate`
+    convergence: f64,
+    /// Iteration limit used by `calculate`
+    max_iterations: Option<usize>,
+    /// What happens to the score of nodes without out edges
+    dangling_policy: DanglingPolicy,
+    /// Score of nodes when they are added
+    initial_scores: HashMap<T, f64>,
+    /// Number of nodes to reserve room for
+    capacity: usize,
+}
+
+impl<T> PagerankBuilder<T>
+where
+    T: Eq + Hash + Clone,
+{
+    /// Creates a builder with the defaults of `Pagerank::new`
+    pub fn new() -> PagerankBuilder<T> {
+        PagerankBuilder {
+            damping: 85,
+            convergence: DEFAULT_CONVERGENCE,
+            max_iterations: None,
+            dangling_policy: DanglingPolicy::Ignore,
+            initial_scores: HashMap::new(),
+            capacity: 0,
+        }
+    }
+
+    /// Sets the damping factor, see `Pagerank::set_damping_factor`
+    pub fn damping(mut self, factor: u8) -> Self {
+        self.damping = factor;
+        self
+    }
+
+    /// Sets the convergence `calculate` stops at, 0.01 by default
+    pub fn convergence(mut self, convergence: f64) -> Self {
+        self.convergence = convergence;
+        self
+    }
+
+    /// Sets the number of iterations after which `calculate` gives up,
+    /// see `Pagerank::calculate_with_limits`. There is no limit by
+    /// default.
+    pub fn max_iterations(mut self, max_iterations: usize) -> Self {
+        self.max_iterations = Some(max_iterations);
+        self
+    }
+
+    /// Sets what happens to the score of nodes without out edges, see
+    /// `Pagerank::set_dangling_policy`
+    pub fn dangling_policy(mut self, policy: DanglingPolicy) -> Self {
+        self.dangling_policy = policy;
+        self
+    }
+
+    /// Sets the score nodes start with when they are added, instead of
+    /// `1 - d`, for instance the scores of the previous run, so the
+    /// first calculation starts near the solution. Scores which are not
+    /// finite, non negative numbers are ignored. See
+    /// `Pagerank::set_initial_scores`.
+    pub fn initial_scores<I>(mut self, scores: I) -> Self
+    where
+        I: IntoIterator<Item = (T, f64)>,
+    {
+        self.initial_scores = scores
+            .into_iter()
+            .filter(|(_, score)| *score >= 0f64 && score.is_finite())
+            .collect();
+        self
+    }
+
+    /// Reserves room for `nodes` nodes, see `Pagerank::with_capacity`
+    pub fn capacity(mut self, nodes: usize) -> Self {
+        self.capacity = nodes;
+        self
+    }
+
+    /// Builds the graph, failing if the damping factor is not below 100
+    /// or the convergence is not a positive, finite number
+    pub fn build(self) -> Result<Pagerank<T>, PagerankError> {
+        if !self.convergence.is_finite() || self.convergence <= 0f64 {
+            return Err(PagerankError::InvalidConvergence(
+                self.convergence,
+            ));
+        }
+
+        let mut pr = Pagerank::with_capacity(self.capacity);
+        pr.set_damping_factor(self.damping)?;
+        pr.set_dangling_policy(self.dangling_policy);
+        pr.convergence = self.convergence;
+        pr.max_iterations = self.max_iterations;
+        pr.pending_scores = self.initial_scores;
+        Ok(pr)
+    }
+}
+
+impl<T> Default for PagerankBuilder<T>
+where
+    T: Eq + Hash + Clone,
+{
+    fn default() -> Self {
+        PagerankBuilder::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{DanglingPolicy, PagerankBuilder, PagerankError};
+
+    #[test]
+    fn test_builder() {
+        let mut pr = PagerankBuilder::new()
+            .damping(50)
+            .dangling_policy(DanglingPolicy::Uniform)
+            .initial_scores(vec![("a", 2.0), ("b", f64::NAN)])
+            .max_iterations(2)
+            .convergence(1e-12)
+            .build()
+            .unwrap();
+        pr.add_edge("a", "b");
+        pr.add_edge("c", "b");
+        assert_eq!(Some(2.0), pr.get_score("a"));
+        assert_eq!(Some(0.5), pr.get_score("b"));
+        assert_eq!(DanglingPolicy::Uniform, pr.dangling_policy());
+
+        let result = pr.calculate();
+        assert!(!result.converged);
+        assert_eq!(2, result.iterations);
+        assert_eq!(0.5, result.damping);
+        assert_eq!(1e-12, result.tolerance);
+
+        match PagerankBuilder::<u32>::new().damping(100).build() {
+            Err(PagerankError::InvalidDamping(100)) => {}
+            _ => panic!("invalid damping expected"),
+        }
+        for convergence in [0.0, -1.0, f64::NAN, f64::INFINITY] {
+            assert!(PagerankBuilder::<u32>::new()
+                .convergence(convergence)
+                .build()
+                .is_err());
+        }
+    }
+}
@@ -0,0 +1,209 @@
+//! Reproducibility bundles of a PageRank run
+use crate::io::SnapshotKey;
+use crate::Pagerank;
+use std::fmt::Display;
+use std::fs::File;
+use std::hash::{BuildHasher, Hash};
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Size of a tar block
+const BLOCK: usize = 512;
+
+impl<T, S> Pagerank<T, S>
+where
+    T: Eq + Hash + Clone + SnapshotKey + Display,
+    S: BuildHasher,
+{
+    /// Writes a run bundle to the file at `path`. See `write_run_bundle`.
+    pub fn export_run_bundle<P: AsRef<Path>>(
+        &self,
+        path: P,
+    ) -> io::Result<()> {
+        let mut file = BufWriter::new(File::create(path)?);
+        self.write_run_bundle(&mut file)?;
+        file.flush()
+    }
+
+    /// Writes a self-contained bundle of the current run, so a ranking
+    /// can be audited and reproduced later.
+    ///
+    /// The bundle is a plain tar archive with three entries:
+    ///
+    /// - `parameters.txt`: crate version, creation time and parameters.
+    /// - `graph.snapshot`: binary snapshot of the graph and its scores,
+    ///   readable with `Pagerank::read_snapshot`.
+    /// - `scores.tsv`: every node and its score, sorted by score.
+    pub fn write_run_bundle<W: Write>(
+        &self,
+        mut writer: W,
+    ) -> io::Result<()> {
+        let created = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0);
+
+        let mut parameters = String::new();
+        parameters += &format!(
+            "crate = {} {}\n",
+            env!("CARGO_PKG_NAME"),
+            env!("CARGO_PKG_VERSION")
+        );
+        parameters += &format!("created = {}\n", created);
+        parameters += &format!("damping = {}\n", self.damping);
+        parameters += &format!("nodes = {}\n", self.nodes.len());
+        parameters += &format!("edges = {}\n", self.edges);
+        parameters += &format!(
+            "in_edges_cap = {}\n",
+            self.in_edges_cap
+                .map_or("none".to_string(), |cap| cap.to_string())
+        );
+        for (id, weight) in self.personalization.iter() {
+            parameters += &format!(
+                "personalization = {}\t{}\n",
+                self.nodes[*id].node, weight
+            );
+        }
+
+        let mut snapshot = Vec::new();
+        self.write_snapshot(&mut snapshot)?;
+
+        let mut scores = String::new();
+        for (node, score) in self.nodes() {
+            scores += &format!("{}\t{}\n", node, score);
+        }
+
+        write_tar_entry(
+            &mut writer,
+            "parameters.txt",
+            parameters.as_bytes(),
+            created,
+        )?;
+        write_tar_entry(
+            &mut writer,
+            "graph.snapshot",
+            &snapshot,
+            created,
+        )?;
+        write_tar_entry(
+            &mut writer,
+            "scores.tsv",
+            scores.as_bytes(),
+            created,
+        )?;
+        writer.write_all(&[0u8; BLOCK * 2])
+    }
+}
+
+/// Writes a regular file entry of an ustar archive
+fn write_tar_entry<W: Write>(
+    writer: &mut W,
+    name: &str,
+    content: &[u8],
+    mtime: u64,
+) -> io::Result<()> {
+    let mut header = [0u8; BLOCK];
+    header[..name.len()].copy_from_slice(name.as_bytes());
+    write_octal(&mut header[100..108], 0o644)?;
+    write_octal(&mut header[108..116], 0)?;
+    write_octal(&mut header[116..124], 0)?;
+    write_octal(&mut header[124..136], content.len() as u64)?;
+    write_octal(&mut header[136..148], mtime)?;
+    header[156] = b'0';
+    header[257..263].copy_from_slice(b"ustar\0");
+    header[263..265].copy_from_slice(b"00");
+
+    // The checksum is computed with its own field filled with spaces
+    header[148..156].copy_from_slice(b"        ");
+    let sum = header.iter().map(|b| *b as u64).sum::<u64>();
+    write_octal(&mut header[148..155], sum)?;
+    header[155] = b' ';
+
+    writer.write_all(&header)?;
+    writer.write_all(content)?;
+    let padding = (BLOCK - content.len() % BLOCK) % BLOCK;
+    writer.write_all(&[0u8; BLOCK][..padding])
+}
+
+/// Writes `value` as a zero padded, NUL terminated octal number
+fn write_octal(field: &mut [u8], value: u64) -> io::Result<()> {
+    let width = field.len() - 1;
+    let digits = format!("{:0width$o}", value, width = width);
+    if digits.len() > width {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "bundle entry is too large",
+        ));
+    }
+
+    field[..width].copy_from_slice(digits.as_bytes());
+    field[width] = 0;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn read_entries(buf: &[u8]) -> Vec<(String, Vec<u8>)> {
+        let mut entries = Vec::new();
+        let mut pos = 0;
+        while buf[pos] != 0 {
+            let header = &buf[pos..pos + BLOCK];
+            let name = String::from_utf8(
+                header[..100]
+                    .iter()
+                    .take_while(|b| **b != 0)
+                    .cloned()
+                    .collect(),
+            )
+            .unwrap();
+            let size =
+                std::str::from_utf8(&header[124..135]).unwrap();
+            let size = usize::from_str_radix(size, 8).unwrap();
+            let content =
+                buf[pos + BLOCK..pos + BLOCK + size].to_vec();
+            entries.push((name, content));
+            pos += BLOCK + size.div_ceil(BLOCK) * BLOCK;
+        }
+        entries
+    }
+
+    #[test]
+    fn test_run_bundle() {
+        let mut pr = Pagerank::<String>::new();
+        pr.add_edge("foo".to_string(), "bar".to_string());
+        pr.add_edge("bar".to_string(), "foo".to_string());
+        pr.add_edge("xxx".to_string(), "bar".to_string());
+        pr.calculate();
+
+        let mut buf = Vec::new();
+        pr.write_run_bundle(&mut buf).expect("write");
+        assert_eq!(0, buf.len() % BLOCK);
+
+        let entries = read_entries(&buf);
+        assert_eq!(
+            vec!["parameters.txt", "graph.snapshot", "scores.tsv"],
+            entries
+                .iter()
+                .map(|(n, _)| n.as_str())
+                .collect::<Vec<&str>>()
+        );
+
+        let parameters =
+            String::from_utf8(entries[0].1.clone()).unwrap();
+        assert!(parameters.contains("damping = 0.85\n"));
+
+        let loaded =
+            Pagerank::<String>::read_snapshot(&entries[1].1[..])
+                .expect("snapshot");
+        assert_eq!(
+            pr.get_score("bar".to_string()),
+            loaded.get_score("bar".to_string())
+        );
+
+        let scores = String::from_utf8(entries[2].1.clone()).unwrap();
+        assert!(scores.starts_with("bar\t"));
+    }
+}
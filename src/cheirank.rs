@@ -0,0 +1,147 @@
+//! CheiRank, the PageRank of the transposed graph
+#[cfg(not(feature = "std"))]
+use crate::compat::prelude::*;
+use crate::graph::Node;
+use crate::{Pagerank, ScoreSet};
+use core::hash::{BuildHasher, Hash};
+
+impl<T, S> Pagerank<T, S>
+where
+    T: Eq + Hash + Clone,
+    S: BuildHasher,
+{
+    /// Calculates the CheiRank of every node: its PageRank in the graph
+    /// with every edge reversed, which ranks nodes by how much they link
+    /// out rather than how much they are linked to.
+    ///
+    /// It is an extra calculation over a transposed copy of the stored
+    /// edges, with the same damping factor, teleport settings and edge
+    /// weights, which are computed first if needed. The scores of the
+    /// graph itself are left untouched.
+    pub fn cheirank(&mut self, convergence: f64) -> ScoreSet<T> {
+        self.finalize_weights();
+        let mut transposed = self.transposed();
+        transposed.calculate_with_convergence(convergence);
+        transposed.score_set()
+    }
+
+    /// Calculates both the PageRank and the CheiRank of every node, the
+    /// two coordinates of the PageRank–CheiRank plane used to analyze
+    /// directed networks. The PageRank is kept in the graph as with
+    /// `calculate_with_convergence`.
+    ///
+    /// Returns every node in insertion order with its PageRank and its
+    /// CheiRank.
+    pub fn calculate_with_cheirank(
+        &mut self,
+        convergence: f64,
+    ) -> Vec<(&T, f64, f64)> {
+        self.calculate_with_convergence(convergence);
+        let cheirank = self.cheirank(convergence);
+
+        self.nodes
+            .iter()
+            .map(|n| {
+                (&n.node, n.score, cheirank.get(&n.node).unwrap())
+            })
+            .collect()
+    }
+
+    /// Copy of the graph with every stored edge reversed. Edges keep
+    /// their weight, including the one given by the weight closure, and
+    /// are scaled by the weight of the stored in edges when they were
+    /// sampled.
+    fn transposed(&self) -> Pagerank<T> {
+        let mut in_edges = vec![Vec::new(); self.nodes.len()];
+        let mut in_weights = vec![Vec::new(); self.nodes.len()];
+        let mut out_weights = vec![0f64; self.nodes.len()];
+        for (target, n) in self.nodes.iter().enumerate() {
+            let scale = self.in_edges_weight(target);
+            for (slot, source) in
+                self.in_edges(target).iter().enumerate()
+            {
+                let weight = match &self.edge_weights {
+                    Some(weights) => weights.in_edges[target][slot],
+                    None => n.edge_weight(slot),
+                } * scale;
+                in_edges[*source].push(target);
+                in_weights[*source].push(weight);
+                out_weights[target] += weight;
+            }
+        }
+
+        let mut pr = Pagerank::new();
+        pr.damping = self.damping;
+        pr.personalization = self.personalization.clone();
+        pr.node_types = self.node_types.clone();
+        pr.teleport_shares = self.teleport_shares.clone();
+        pr.node_positions = self
+            .node_positions
+            .iter()
+            .map(|(node, id)| (node.clone(), *id))
+            .collect();
+        pr.nodes = self
+            .nodes
+            .iter()
+            .zip(in_edges.into_iter().zip(in_weights))
+            .zip(out_weights)
+            .enumerate()
+            .map(|(id, ((n, (in_edges, in_weights)), out_weight))| {
+                let unweighted =
+                    in_weights.iter().all(|w| *w == 1f64);
+                Node {
+                    node: n.node.clone(),
+                    in_degree: in_edges.len(),
+                    out_edges: self.len_in_edges(id),
+                    in_edges,
+                    in_weights: if unweighted {
+                        Vec::new()
+                    } else {
+                        in_weights
+                    },
+                    out_weight,
+                    score: 1f64 - self.damping,
+                }
+            })
+            .collect();
+        pr.edges = pr.nodes.iter().map(|n| n.out_edges as u64).sum();
+
+        pr
+    }
+}
+
+#[cfg(all(test, feature = "generators"))]
+mod tests {
+    use crate::generators;
+    use crate::Pagerank;
+
+    #[test]
+    fn test_cheirank() {
+        let mut pr = Pagerank::<&str>::new();
+        pr.add_edge("hub", "a");
+        pr.add_edge("hub", "b");
+        pr.add_edge("hub", "c");
+        pr.add_edge("a", "b");
+        pr.add_edge("c", "hub");
+
+        let scores = pr.calculate_with_cheirank(1e-9);
+        assert_eq!(4, scores.len());
+        let best_chei = scores
+            .iter()
+            .max_by(|a, b| a.2.partial_cmp(&b.2).unwrap())
+            .unwrap();
+        assert_eq!("hub", *best_chei.0);
+        assert_eq!(("b", 1.0 - 0.85), (*scores[2].0, scores[2].2));
+
+        // A symmetric graph is its own transpose
+        let mut ring = generators::ring(5);
+        for i in 0..5 {
+            ring.add_edge((i + 1) % 5, i);
+        }
+        for (_, pagerank, cheirank) in
+            ring.calculate_with_cheirank(1e-9)
+        {
+            assert!((pagerank - cheirank).abs() < 1e-9);
+        }
+    }
+}
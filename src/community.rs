@@ -0,0 +1,137 @@
+//! Community detection
+#[cfg(not(feature = "std"))]
+use crate::compat::prelude::*;
+use crate::compat::HashMap;
+use crate::graph::next_random;
+use crate::Pagerank;
+use core::hash::{BuildHasher, Hash};
+
+impl<T, S> Pagerank<T, S>
+where
+    T: Eq + Hash + Clone,
+    S: BuildHasher,
+{
+    /// Detects communities with label propagation.
+    ///
+    /// Edges are treated as undirected. Every node starts in its own
+    /// community and, visiting the nodes in a random order on each
+    /// iteration, adopts the label shared by most of its neighbors, ties
+    /// broken at random. It stops once no label changes or after
+    /// `max_iterations`. The same `seed` always yields the same labels.
+    ///
+    /// Returns each node with its community label. Labels are numbered
+    /// from 0 in order of first appearance.
+    pub fn label_propagation(
+        &self,
+        max_iterations: usize,
+        seed: u64,
+    ) -> Vec<(&T, usize)> {
+        let len = self.nodes.len();
+        let mut neighbors = vec![Vec::new(); len];
+        for target in 0..len {
+            for source in self.in_edges(target).iter() {
+                if *source != target {
+                    neighbors[target].push(*source);
+                    neighbors[*source].push(target);
+                }
+            }
+        }
+
+        let mut rng = seed ^ 0x9e3779b97f4a7c15;
+        if rng == 0 {
+            rng = 0x9e3779b97f4a7c15;
+        }
+
+        let mut labels = (0..len).collect::<Vec<usize>>();
+        let mut order = (0..len).collect::<Vec<usize>>();
+        let mut counts = HashMap::new();
+        let mut candidates = Vec::new();
+
+        for _ in 0..max_iterations {
+            for i in (1..len).rev() {
+                let j =
+                    (next_random(&mut rng) % (i as u64 + 1)) as usize;
+                order.swap(i, j);
+            }
+
+            let mut changed = false;
+            for id in order.iter() {
+                if neighbors[*id].is_empty() {
+                    continue;
+                }
+
+                counts.clear();
+                for neighbor in neighbors[*id].iter() {
+                    *counts
+                        .entry(labels[*neighbor])
+                        .or_insert(0usize) += 1;
+                }
+
+                let max = *counts.values().max().unwrap_or(&0);
+                if counts.get(&labels[*id]) == Some(&max) {
+                    continue;
+                }
+
+                candidates.clear();
+                candidates.extend(
+                    counts
+                        .iter()
+                        .filter(|(_, count)| **count == max)
+                        .map(|(label, _)| *label),
+                );
+                candidates.sort_unstable();
+
+                let pick =
+                    next_random(&mut rng) % candidates.len() as u64;
+                labels[*id] = candidates[pick as usize];
+                changed = true;
+            }
+
+            if !changed {
+                break;
+            }
+        }
+
+        let mut renumbered = HashMap::new();
+        self.nodes
+            .iter()
+            .zip(labels)
+            .map(|(n, label)| {
+                let next = renumbered.len();
+                (&n.node, *renumbered.entry(label).or_insert(next))
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Pagerank;
+
+    #[test]
+    fn test_label_propagation() {
+        let mut pr = Pagerank::<&str>::new();
+        for (a, b) in [("a", "b"), ("b", "c"), ("c", "a")].iter() {
+            pr.add_edge(*a, *b);
+            pr.add_edge(*b, *a);
+        }
+        for (a, b) in [("x", "y"), ("y", "z"), ("z", "x")].iter() {
+            pr.add_edge(*a, *b);
+            pr.add_edge(*b, *a);
+        }
+        pr.add_edge("c", "x");
+
+        let labels = pr.label_propagation(100, 42);
+        let label = |node: &str| {
+            labels.iter().find(|(n, _)| **n == node).unwrap().1
+        };
+
+        assert_eq!(0, label("a"));
+        assert_eq!(label("a"), label("b"));
+        assert_eq!(label("a"), label("c"));
+        assert_eq!(label("x"), label("y"));
+        assert_eq!(label("x"), label("z"));
+        assert_ne!(label("a"), label("x"));
+        assert_eq!(labels, pr.label_propagation(100, 42));
+    }
+}
@@ -0,0 +1,82 @@
+//! What the crate needs from `std`, taken without it from `alloc`,
+//! `hashbrown` and `libm`
+#[cfg(not(feature = "std"))]
+use core::time::Duration;
+
+#[cfg(feature = "std")]
+pub(crate) use std::collections::hash_map::RandomState;
+#[cfg(feature = "std")]
+pub(crate) use std::collections::{HashMap, HashSet};
+#[cfg(feature = "std")]
+pub(crate) use std::time::Instant;
+
+#[cfg(not(feature = "std"))]
+pub(crate) use hashbrown::DefaultHashBuilder as RandomState;
+#[cfg(not(feature = "std"))]
+pub(crate) use hashbrown::{HashMap, HashSet};
+
+/// Types of the `std` prelude which `alloc` doesn't bring into scope
+#[cfg(not(feature = "std"))]
+pub(crate) mod prelude {
+    pub(crate) use super::Float;
+    pub(crate) use alloc::borrow::ToOwned;
+    pub(crate) use alloc::string::{String, ToString};
+    pub(crate) use alloc::vec::Vec;
+}
+
+/// Clock of the calculations without `std`, where there is none: no
+/// time ever elapses, so results report a zero `elapsed`
+#[cfg(not(feature = "std"))]
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Instant;
+
+#[cfg(not(feature = "std"))]
+impl Instant {
+    /// Returns the current instant
+    pub(crate) fn now() -> Instant {
+        Instant
+    }
+
+    /// Returns the time elapsed since this instant, always zero
+    pub(crate) fn elapsed(&self) -> Duration {
+        Duration::ZERO
+    }
+}
+
+/// Float methods of `std` which `core` lacks, computed with `libm`
+#[cfg(not(feature = "std"))]
+pub(crate) trait Float {
+    /// Square root
+    fn sqrt(self) -> Self;
+    /// Natural logarithm
+    fn ln(self) -> Self;
+    /// Raised to an integer power
+    fn powi(self, n: i32) -> Self;
+    /// Largest integer less than or equal to it
+    fn floor(self) -> Self;
+    /// Smallest integer greater than or equal to it
+    fn ceil(self) -> Self;
+}
+
+#[cfg(not(feature = "std"))]
+impl Float for f64 {
+    fn sqrt(self) -> f64 {
+        libm::sqrt(self)
+    }
+
+    fn ln(self) -> f64 {
+        libm::log(self)
+    }
+
+    fn powi(self, n: i32) -> f64 {
+        libm::pow(self, n as f64)
+    }
+
+    fn floor(self) -> f64 {
+        libm::floor(self)
+    }
+
+    fn ceil(self) -> f64 {
+        libm::ceil(self)
+    }
+}
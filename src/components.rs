@@ -0,0 +1,152 @@
+//! Weakly connected components kept up to date as edges are added
+#[cfg(not(feature = "std"))]
+use crate::compat::prelude::*;
+use crate::Pagerank;
+use core::hash::{BuildHasher, Hash};
+
+/// Disjoint sets of node ids (union-find), one set per weakly connected
+/// component
+pub(crate) struct Components {
+    /// Parent of each node, roots are their own parent
+    parents: Vec<usize>,
+    /// Number of nodes under each root
+    sizes: Vec<usize>,
+    /// Number of components
+    count: usize,
+}
+
+impl Components {
+    /// Adds a node in a component of its own
+    pub(crate) fn push(&mut self) {
+        self.parents.push(self.parents.len());
+        self.sizes.push(1);
+        self.count += 1;
+    }
+
+    /// Returns the root of the component of `id`, halving the path to it
+    fn find(&mut self, mut id: usize) -> usize {
+        while self.parents[id] != id {
+            let parent = self.parents[id];
+            self.parents[id] = self.parents[parent];
+            id = parent;
+        }
+        id
+    }
+
+    /// Merges the components of two nodes, the smaller under the larger
+    pub(crate) fn union(&mut self, a: usize, b: usize) {
+        let (a, b) = (self.find(a), self.find(b));
+        if a == b {
+            return;
+        }
+        let (root, child) = if self.sizes[a] >= self.sizes[b] {
+            (a, b)
+        } else {
+            (b, a)
+        };
+        self.parents[child] = root;
+        self.sizes[root] += self.sizes[child];
+        self.count -= 1;
+    }
+}
+
+impl<T, S> Pagerank<T, S>
+where
+    T: Eq + Hash + Clone,
+    S: BuildHasher,
+{
+    /// Whether there is a path between two nodes ignoring the direction
+    /// of the edges, that is, whether they are in the same weakly
+    /// connected component. Returns false if either node is missing.
+    ///
+    /// Components are built from the stored edges on the first query,
+    /// and then kept up to date as edges are added, so each query takes
+    /// nearly constant time. Removing edges or nodes, rolling back a
+    /// batch or dropping in edges builds them again on the next query.
+    pub fn connected(&mut self, a: &T, b: &T) -> bool {
+        match (self.node_id(a), self.node_id(b)) {
+            (Some(a), Some(b)) => {
+                let components = self.components();
+                components.find(a) == components.find(b)
+            }
+            _ => false,
+        }
+    }
+
+    /// Returns an id shared by every node in the weakly connected
+    /// component of `node`, or `None` if the node is missing. See
+    /// `connected`.
+    ///
+    /// The id is the internal id of one of the nodes of the component.
+    /// It is only stable until the graph changes, since adding an edge
+    /// may merge two components under either id.
+    pub fn component_id(&mut self, node: &T) -> Option<usize> {
+        let id = self.node_id(node)?;
+        Some(self.components().find(id))
+    }
+
+    /// Returns the number of weakly connected components. See
+    /// `connected`.
+    pub fn component_count(&mut self) -> usize {
+        self.components().count
+    }
+
+    /// Returns the components, building them from the stored edges if
+    /// needed
+    fn components(&mut self) -> &mut Components {
+        if self.components.is_none() {
+            let len = self.nodes.len();
+            let mut components = Components {
+                parents: (0..len).collect(),
+                sizes: vec![1; len],
+                count: len,
+            };
+            for target in 0..len {
+                for source in self.in_edges(target).iter() {
+                    components.union(*source, target);
+                }
+            }
+            self.components = Some(components);
+        }
+        self.components.as_mut().unwrap()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Pagerank;
+
+    #[test]
+    fn test_connected() {
+        let mut pr = Pagerank::<&str>::new();
+        pr.add_edge("a", "b");
+        pr.add_edge("c", "b");
+        pr.add_edge("x", "y");
+        pr.insert_node("z");
+
+        assert!(pr.connected(&"a", &"c"));
+        assert!(!pr.connected(&"a", &"x"));
+        assert!(!pr.connected(&"a", &"missing"));
+        assert_eq!(3, pr.component_count());
+        assert_eq!(pr.component_id(&"a"), pr.component_id(&"c"));
+        assert_ne!(pr.component_id(&"a"), pr.component_id(&"z"));
+        assert_eq!(None, pr.component_id(&"missing"));
+
+        // Kept up to date as edges are added
+        pr.add_edge("y", "c");
+        assert!(pr.connected(&"a", &"x"));
+        pr.add_edge("z", "new");
+        assert_eq!(2, pr.component_count());
+
+        // And built again once edges are removed
+        assert!(pr.remove_edge(&"y", &"c"));
+        assert!(!pr.connected(&"a", &"x"));
+        assert_eq!(3, pr.component_count());
+
+        pr.begin_batch();
+        pr.add_edge("a", "z");
+        assert!(pr.connected(&"b", &"new"));
+        pr.rollback_batch();
+        assert!(!pr.connected(&"b", &"new"));
+    }
+}
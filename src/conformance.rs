@@ -0,0 +1,182 @@
+//! Conformance checks for custom PageRank implementations.
+//!
+//! Users writing their own solver, for instance a GPU or distributed
+//! kernel reading the graph through a `GraphView`, can check it against
+//! the reference graphs of this crate, whose scores are known: the
+//! symmetric graphs of `generators` with their closed form scores, and
+//! the golden graph with the fully converged scores of the core
+//! algorithm.
+//!
+//! ```
+//! use simple_pagerank::conformance;
+//!
+//! conformance::check_solver(
+//!     |mut graph, damping| {
+//!         graph.set_damping_factor((damping * 100.0).round() as u8)
+//!             .unwrap();
+//!         graph.calculate_with_convergence(1e-12);
+//!         graph.iter_nodes().map(|n| n.score).collect()
+//!     },
+//!     1e-6,
+//! )
+//! .unwrap();
+//! ```
+#[cfg(not(feature = "std"))]
+use crate::compat::prelude::*;
+use crate::generators;
+use crate::Pagerank;
+use core::error::Error;
+use core::fmt;
+
+/// A reference graph with its expected scores
+pub struct Case {
+    /// Name of the case, reported on failures
+    pub name: &'static str,
+    /// The graph
+    pub graph: Pagerank<usize>,
+    /// Damping factor the scores were computed with
+    pub damping: f64,
+    /// Expected score of each node, indexed by node id
+    pub expected: Vec<f64>,
+}
+
+/// A score of a custom implementation which does not match the
+/// reference one
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConformanceError {
+    /// Name of the failing case
+    pub case: &'static str,
+    /// Id of the failing node
+    pub node: usize,
+    /// Expected score
+    pub expected: f64,
+    /// Score given by the implementation, `None` if it returned too few
+    pub actual: Option<f64>,
+}
+
+impl fmt::Display for ConformanceError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.actual {
+            Some(actual) => write!(
+                f,
+                "{}: score of node {} is {}, expected {}",
+                self.case, self.node, actual, self.expected
+            ),
+            None => write!(
+                f,
+                "{}: no score for node {}",
+                self.case, self.node
+            ),
+        }
+    }
+}
+
+impl Error for ConformanceError {}
+
+/// Returns every reference case, with scores computed without
+/// personalization and with the default handling of dangling nodes
+pub fn cases() -> Vec<Case> {
+    let mut reference = generators::golden();
+    reference.calculate_with_convergence(1e-14);
+    let expected = reference.iter_nodes().map(|n| n.score).collect();
+
+    vec![
+        Case {
+            name: "ring",
+            graph: generators::ring(7),
+            damping: 0.85,
+            expected: generators::expected_ring(7),
+        },
+        Case {
+            name: "complete",
+            graph: generators::complete(5),
+            damping: 0.5,
+            expected: generators::expected_complete(5),
+        },
+        Case {
+            name: "star",
+            graph: generators::star(4),
+            damping: 0.85,
+            expected: generators::expected_star(4, 0.85),
+        },
+        Case {
+            name: "golden",
+            graph: generators::golden(),
+            damping: 0.85,
+            expected,
+        },
+    ]
+}
+
+/// Runs `solve` on every reference case and compares the scores it
+/// returns, indexed by node id, with the expected ones. `solve` gets
+/// the graph and the damping factor to use.
+///
+/// Returns the first score differing by more than `tolerance`.
+pub fn check_solver<F>(
+    mut solve: F,
+    tolerance: f64,
+) -> Result<(), ConformanceError>
+where
+    F: FnMut(Pagerank<usize>, f64) -> Vec<f64>,
+{
+    for case in cases() {
+        let scores = solve(case.graph, case.damping);
+        for (node, expected) in case.expected.iter().enumerate() {
+            let actual = scores.get(node).cloned();
+            let matches = actual
+                .is_some_and(|a| (a - expected).abs() <= tolerance);
+            if !matches {
+                return Err(ConformanceError {
+                    case: case.name,
+                    node,
+                    expected: *expected,
+                    actual,
+                });
+            }
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::check_solver;
+
+    #[test]
+    fn test_check_solver() {
+        // A plain power iteration over the graph view
+        let result = check_solver(
+            |graph, damping| {
+                let view = graph.view();
+                let mut scores = vec![1f64; view.len()];
+                for _ in 0..200 {
+                    scores = (0..view.len())
+                        .map(|id| {
+                            let incoming = view
+                                .in_neighbors(id)
+                                .iter()
+                                .map(|source| {
+                                    scores[*source]
+                                        / view.out_degree(*source)
+                                            as f64
+                                })
+                                .sum::<f64>();
+                            (1.0 - damping) + damping * incoming
+                        })
+                        .collect();
+                }
+                scores
+            },
+            1e-6,
+        );
+        assert_eq!(Ok(()), result);
+
+        let error =
+            check_solver(|_, _| vec![1.0; 3], 1e-6).unwrap_err();
+        assert_eq!("ring", error.case);
+        assert_eq!(3, error.node);
+        assert_eq!(None, error.actual);
+    }
+}
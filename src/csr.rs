@@ -0,0 +1,181 @@
+//! Compressed sparse row (CSR) adjacency arrays
+#[cfg(not(feature = "std"))]
+use crate::compat::prelude::*;
+use crate::compat::HashMap;
+use crate::graph::Node;
+use crate::Pagerank;
+use core::error::Error;
+use core::fmt;
+use core::hash::Hash;
+
+/// Error returned by `Pagerank::from_csr` on inconsistent arrays
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CsrError {
+    /// `offsets` must have one more element than `keys`, start at 0 and
+    /// end at the number of targets
+    BadOffsets,
+    /// `offsets` must be non decreasing. Holds the first offending
+    /// position.
+    DecreasingOffset(usize),
+    /// A target is not a node id. Holds its position in `targets`.
+    TargetOutOfRange(usize),
+    /// A key appears twice. Holds the position of the second one.
+    DuplicatedKey(usize),
+}
+
+impl fmt::Display for CsrError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CsrError::BadOffsets => write!(
+                f,
+                "offsets must go from 0 to the number of targets, with one per key plus one"
+            ),
+            CsrError::DecreasingOffset(at) => {
+                write!(f, "offset {} is lower than the previous one", at)
+            }
+            CsrError::TargetOutOfRange(at) => {
+                write!(f, "target {} is not a node id", at)
+            }
+            CsrError::DuplicatedKey(at) => write!(f, "key {} is duplicated", at),
+        }
+    }
+}
+
+impl Error for CsrError {}
+
+impl<T> Pagerank<T>
+where
+    T: Eq + Hash + Clone,
+{
+    /// Builds a graph from its out edges in CSR form, as produced by an
+    /// external ETL job, skipping the per edge lookups of `add_edge`.
+    ///
+    /// Node `i` is `keys[i]`, and its out edges go to the node ids in
+    /// `targets[offsets[i]..offsets[i + 1]]`. The arrays are consumed so
+    /// their memory is released while the graph is built.
+    pub fn from_csr(
+        offsets: Vec<u64>,
+        targets: Vec<u32>,
+        keys: Vec<T>,
+    ) -> Result<Pagerank<T>, CsrError> {
+        let len = keys.len();
+        if offsets.len() != len + 1
+            || offsets[0] != 0
+            || offsets[len] != targets.len() as u64
+        {
+            return Err(CsrError::BadOffsets);
+        }
+        if let Some(at) = offsets.windows(2).position(|w| w[0] > w[1])
+        {
+            return Err(CsrError::DecreasingOffset(at + 1));
+        }
+
+        let mut in_degrees = vec![0usize; len];
+        for (at, target) in targets.iter().enumerate() {
+            match in_degrees.get_mut(*target as usize) {
+                Some(degree) => *degree += 1,
+                None => return Err(CsrError::TargetOutOfRange(at)),
+            }
+        }
+
+        let mut node_positions = HashMap::with_capacity(len);
+        for (id, key) in keys.iter().enumerate() {
+            if node_positions.insert(key.clone(), id).is_some() {
+                return Err(CsrError::DuplicatedKey(id));
+            }
+        }
+
+        let mut in_edges = in_degrees
+            .iter()
+            .map(|degree| Vec::with_capacity(*degree))
+            .collect::<Vec<Vec<usize>>>();
+        for source in 0..len {
+            let range = offsets[source] as usize
+                ..offsets[source + 1] as usize;
+            for target in targets[range].iter() {
+                in_edges[*target as usize].push(source);
+            }
+        }
+        drop(targets);
+
+        let mut pr = Pagerank::new();
+        let score = 1f64 - pr.damping;
+        pr.nodes = keys
+            .into_iter()
+            .zip(in_edges)
+            .enumerate()
+            .map(|(id, (node, in_edges))| {
+                let out_edges =
+                    (offsets[id + 1] - offsets[id]) as usize;
+                Node {
+                    node,
+                    in_degree: in_edges.len(),
+                    in_edges,
+                    out_edges,
+                    in_weights: Vec::new(),
+                    out_weight: out_edges as f64,
+                    score,
+                }
+            })
+            .collect();
+        pr.edges = offsets[len];
+        pr.node_positions = node_positions;
+
+        Ok(pr)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_csr() {
+        let mut pr = Pagerank::from_csr(
+            vec![0, 1, 2, 3, 3],
+            vec![1, 0, 1],
+            vec!["foo", "bar", "xxx", "yyy"],
+        )
+        .expect("valid csr");
+
+        let mut expected = Pagerank::<&str>::new();
+        expected.add_edge("foo", "bar");
+        expected.add_edge("bar", "foo");
+        expected.add_edge("xxx", "bar");
+        expected.insert_node("yyy");
+
+        assert_eq!(expected.degrees(), pr.degrees());
+        assert_eq!(3, pr.len_node());
+        pr.calculate();
+        expected.calculate();
+        assert_eq!(expected.nodes(), pr.nodes());
+    }
+
+    #[test]
+    fn test_from_csr_errors() {
+        let csr = |offsets: Vec<u64>, targets: Vec<u32>| {
+            Pagerank::from_csr(offsets, targets, vec![1u32, 2]).err()
+        };
+        assert_eq!(
+            Some(CsrError::BadOffsets),
+            csr(vec![0, 1], vec![1])
+        );
+        assert_eq!(
+            Some(CsrError::BadOffsets),
+            csr(vec![0, 1, 2], vec![1])
+        );
+        assert_eq!(
+            Some(CsrError::DecreasingOffset(2)),
+            csr(vec![0, 2, 1], vec![1])
+        );
+        assert_eq!(
+            Some(CsrError::TargetOutOfRange(1)),
+            csr(vec![0, 1, 2], vec![1, 2])
+        );
+        assert_eq!(
+            Some(CsrError::DuplicatedKey(1)),
+            Pagerank::from_csr(vec![0, 0, 0], vec![], vec![1u32, 1])
+                .err()
+        );
+    }
+}
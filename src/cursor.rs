@@ -0,0 +1,208 @@
+//! Paging through a ranking
+#[cfg(not(feature = "std"))]
+use crate::compat::prelude::*;
+use crate::graph::{cmp_scores, hash_bytes};
+use crate::ScoreSet;
+use core::cmp::Ordering;
+use core::error::Error;
+use core::fmt;
+use core::hash::Hash;
+use core::str::FromStr;
+
+/// Resumable position in the ranking of a score set, returned by
+/// `ScoreSet::ranked_cursor` and `ScoreSet::page`.
+///
+/// It is pinned to the scores it was taken from: paging with it through
+/// the same score set never repeats or skips a node, and using it with
+/// other scores fails. It converts to and from an opaque string with
+/// `to_string` and `parse`, to hand it to a web client as a page token.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RankCursor {
+    /// Hash of the scores the cursor is pinned to
+    stamp: u64,
+    /// Score and id of the last node returned, if any
+    last: Option<(f64, usize)>,
+}
+
+/// Error returned when paging with a `RankCursor`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CursorError {
+    /// The token is not a cursor
+    Malformed,
+    /// The cursor was taken from other scores, for instance before the
+    /// ranking was calculated again. Paging restarts with a new cursor.
+    Stale,
+}
+
+impl fmt::Display for CursorError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CursorError::Malformed => write!(f, "malformed cursor"),
+            CursorError::Stale => {
+                write!(f, "cursor taken from other scores")
+            }
+        }
+    }
+}
+
+impl Error for CursorError {}
+
+impl fmt::Display for RankCursor {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:016x}", self.stamp)?;
+        if let Some((score, id)) = self.last {
+            write!(f, ".{:016x}.{:x}", score.to_bits(), id)?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for RankCursor {
+    type Err = CursorError;
+
+    fn from_str(token: &str) -> Result<Self, Self::Err> {
+        let parts = token.split('.').collect::<Vec<&str>>();
+        let hex = |part: &str| {
+            u64::from_str_radix(part, 16)
+                .map_err(|_| CursorError::Malformed)
+        };
+        let last = match parts.len() {
+            1 => None,
+            3 => Some((
+                f64::from_bits(hex(parts[1])?),
+                hex(parts[2])? as usize,
+            )),
+            _ => return Err(CursorError::Malformed),
+        };
+        Ok(RankCursor {
+            stamp: hex(parts[0])?,
+            last,
+        })
+    }
+}
+
+impl<T> ScoreSet<T>
+where
+    T: Eq + Hash + Clone,
+{
+    /// Returns a cursor at the start of the ranking of these scores.
+    ///
+    /// Since a score set is a copy of the scores, a service can keep
+    /// the one its clients are paging through while the graph is
+    /// changed and calculated again, and move them to a new one when
+    /// their cursor becomes stale.
+    pub fn ranked_cursor(&self) -> RankCursor {
+        RankCursor {
+            stamp: self.stamp(),
+            last: None,
+        }
+    }
+
+    /// Returns up to `limit` nodes following `cursor` in the ranking,
+    /// highest first in the same order as `nodes`, and the cursor of
+    /// the next page. The page is empty once the ranking is exhausted.
+    ///
+    /// Each page takes linear time in the number of nodes, regardless of
+    /// how deep it is.
+    #[allow(clippy::type_complexity)]
+    pub fn page(
+        &self,
+        cursor: &RankCursor,
+        limit: usize,
+    ) -> Result<(Vec<(&T, f64)>, RankCursor), CursorError> {
+        if cursor.stamp != self.stamp() {
+            return Err(CursorError::Stale);
+        }
+
+        let scores = &self.scores;
+        let before = |a: (f64, usize), b: (f64, usize)| {
+            cmp_scores(b.0, a.0).then(a.1.cmp(&b.1)) == Ordering::Less
+        };
+        let mut page = self
+            .positions
+            .iter()
+            .map(|(node, id)| (*id, node))
+            .filter(|(id, _)| match cursor.last {
+                Some(last) => before(last, (scores[*id], *id)),
+                None => true,
+            })
+            .collect::<Vec<(usize, &T)>>();
+
+        let order = |a: &(usize, &T), b: &(usize, &T)| {
+            cmp_scores(scores[b.0], scores[a.0]).then(a.0.cmp(&b.0))
+        };
+        if limit == 0 {
+            page.clear();
+        } else if limit < page.len() {
+            page.select_nth_unstable_by(limit - 1, order);
+            page.truncate(limit);
+        }
+        page.sort_by(order);
+
+        let next = RankCursor {
+            stamp: cursor.stamp,
+            last: page
+                .last()
+                .map(|(id, _)| (scores[*id], *id))
+                .or(cursor.last),
+        };
+        let page = page
+            .into_iter()
+            .map(|(id, node)| (node, scores[id]))
+            .collect();
+        Ok((page, next))
+    }
+
+    /// Hash of the scores, which pins cursors to them
+    fn stamp(&self) -> u64 {
+        self.scores.iter().fold(
+            hash_bytes(0, &(self.scores.len() as u64).to_le_bytes()),
+            |stamp, score| {
+                hash_bytes(stamp, &score.to_bits().to_le_bytes())
+            },
+        )
+    }
+}
+
+#[cfg(all(test, feature = "generators"))]
+mod tests {
+    use crate::{generators, CursorError, RankCursor};
+
+    #[test]
+    fn test_page() {
+        let mut pr = generators::watts_strogatz(100, 4, 0.3, 1);
+        pr.calculate();
+        let scores = pr.score_set();
+
+        let mut cursor = scores.ranked_cursor();
+        let mut paged = Vec::new();
+        loop {
+            let token = cursor.to_string();
+            let (page, next) =
+                scores.page(&token.parse().unwrap(), 7).unwrap();
+            if page.is_empty() {
+                assert_eq!(cursor, next);
+                break;
+            }
+            assert!(page.len() <= 7);
+            paged.extend(page);
+            cursor = next;
+        }
+        assert_eq!(scores.nodes(), paged);
+
+        // Recalculating gives other scores
+        pr.add_edge(1, 2);
+        pr.calculate();
+        let newer = pr.score_set();
+        assert_eq!(Err(CursorError::Stale), newer.page(&cursor, 7));
+        assert_eq!(
+            7,
+            newer.page(&newer.ranked_cursor(), 7).unwrap().0.len()
+        );
+
+        assert_eq!(
+            Err(CursorError::Malformed),
+            "12.x".parse::<RankCursor>()
+        );
+    }
+}
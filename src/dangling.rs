@@ -0,0 +1,157 @@
+//! Handling of the score of nodes without out edges
+#[cfg(not(feature = "std"))]
+use crate::compat::prelude::*;
+use crate::solver::ScoreValue;
+use crate::Pagerank;
+use core::hash::{BuildHasher, Hash};
+
+/// What happens to the score of dangling nodes, the nodes without out
+/// edges, which have no edge to pass it on through
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize)
+)]
+pub enum DanglingPolicy {
+    /// The score is lost, so scores don't add up to the number of nodes.
+    /// This is the default.
+    #[default]
+    Ignore,
+    /// The score is spread evenly among every node, as if dangling
+    /// nodes linked to all of them
+    Uniform,
+    /// The score is spread like the teleport probability: among the
+    /// personalization vector or the node types when set
+    Teleport,
+}
+
+impl<T, S> Pagerank<T, S>
+where
+    T: Eq + Hash + Clone,
+    S: BuildHasher,
+{
+    /// Sets what happens to the score of dangling nodes on each
+    /// iteration. With `Uniform` or `Teleport` no score is lost, so the
+    /// scores add up to the number of nodes.
+    pub fn set_dangling_policy(&mut self, policy: DanglingPolicy) {
+        self.dangling_policy = policy;
+    }
+
+    /// Returns how the score of dangling nodes is handled
+    pub fn dangling_policy(&self) -> DanglingPolicy {
+        self.dangling_policy
+    }
+
+    /// Share of the dangling score each node gets, given the teleport
+    /// scores, or `None` if it is lost or there are no dangling nodes
+    pub(crate) fn dangling_shares<F: ScoreValue>(
+        &self,
+        teleport: &[F],
+    ) -> Option<Vec<F>> {
+        if self.dangling_policy == DanglingPolicy::Ignore
+            || self.nodes.iter().all(|n| n.out_edges > 0)
+        {
+            return None;
+        }
+
+        let uniform = F::from_f64(1f64 / self.nodes.len() as f64);
+        match self.dangling_policy {
+            DanglingPolicy::Ignore => None,
+            DanglingPolicy::Uniform => {
+                Some(vec![uniform; self.nodes.len()])
+            }
+            DanglingPolicy::Teleport => {
+                let total =
+                    teleport.iter().map(|t| (*t).into()).sum::<f64>();
+                if total > 0f64 {
+                    Some(
+                        teleport
+                            .iter()
+                            .map(|t| F::from_f64((*t).into() / total))
+                            .collect(),
+                    )
+                } else {
+                    Some(vec![uniform; self.nodes.len()])
+                }
+            }
+        }
+    }
+
+    /// Sum of the scores of the dangling nodes
+    pub(crate) fn dangling_score<F: ScoreValue>(
+        &self,
+        scores: &[F],
+    ) -> f64 {
+        self.nodes
+            .iter()
+            .zip(scores.iter())
+            .filter(|(n, _)| n.out_edges == 0)
+            .map(|(_, score)| (*score).into())
+            .sum()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{DanglingPolicy, Pagerank};
+
+    fn graph() -> Pagerank<&'static str> {
+        let mut pr = Pagerank::new();
+        pr.add_edge("foo", "bar");
+        pr.add_edge("bar", "foo");
+        pr.add_edge("foo", "dead end");
+        pr.add_edge("xxx", "dead end");
+        pr
+    }
+
+    #[test]
+    fn test_dangling_policy() {
+        let mut pr = graph();
+        pr.calculate_with_convergence(1e-12);
+        let total = pr.nodes().iter().map(|(_, s)| s).sum::<f64>();
+        assert!(total < 3.9);
+
+        let mut pr = graph();
+        pr.set_dangling_policy(DanglingPolicy::Uniform);
+        assert_eq!(DanglingPolicy::Uniform, pr.dangling_policy());
+        pr.calculate_with_convergence(1e-12);
+        let total = pr.nodes().iter().map(|(_, s)| s).sum::<f64>();
+        assert!((total - 4.0).abs() < 1e-9);
+
+        let mut pr = graph();
+        pr.set_dangling_policy(DanglingPolicy::Teleport);
+        pr.set_personalization(vec![("xxx", 1.0)]);
+        pr.calculate_with_convergence(1e-12);
+        let total = pr.nodes().iter().map(|(_, s)| s).sum::<f64>();
+        assert!((total - 4.0).abs() < 1e-9);
+        let xxx = pr.get_score("xxx").unwrap();
+        let dead_end = pr.get_score("dead end").unwrap();
+        assert!((xxx - (0.15 * 4.0 + 0.85 * dead_end)).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_dangling_without_edges() {
+        let mut pr = Pagerank::<u32>::new();
+        pr.insert_node(1);
+        pr.insert_node(2);
+        pr.set_dangling_policy(DanglingPolicy::Uniform);
+        pr.calculate_with_convergence(1e-12);
+        assert!((pr.get_score(1).unwrap() - 1.0).abs() < 1e-9);
+    }
+
+    #[cfg(feature = "linalg")]
+    #[test]
+    fn test_dangling_linear() {
+        let mut pr = graph();
+        pr.set_dangling_policy(DanglingPolicy::Uniform);
+        pr.calculate_linear(1e-12, 100);
+        let mut power = graph();
+        power.set_dangling_policy(DanglingPolicy::Uniform);
+        power.calculate_with_convergence(1e-12);
+        for node in ["foo", "bar", "xxx", "dead end"].iter() {
+            let a = pr.get_score(node).unwrap();
+            let b = power.get_score(node).unwrap();
+            assert!((a - b).abs() < 1e-6);
+        }
+    }
+}
@@ -0,0 +1,149 @@
+//! Score decay for nodes without new edges
+#[cfg(not(feature = "std"))]
+use crate::compat::prelude::*;
+use crate::Pagerank;
+use core::hash::{BuildHasher, Hash};
+
+/// State of the decay of stale nodes between runs
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize)
+)]
+pub(crate) struct StaleDecay {
+    /// Multiplier applied per run without new in edges
+    factor: f64,
+    /// Consecutive runs each node went without new in edges
+    stale_runs: Vec<u32>,
+    /// Nodes which got new in edges since the last run
+    touched: Vec<bool>,
+}
+
+impl StaleDecay {
+    /// Marks a node as having received a new in edge
+    pub(crate) fn touch(&mut self, id: usize) {
+        if id >= self.touched.len() {
+            self.touched.resize(id + 1, false);
+        }
+        self.touched[id] = true;
+    }
+
+    /// Forgets the nodes flagged in `removed`, whose following nodes
+    /// move down
+    pub(crate) fn retain(&mut self, removed: &[bool]) {
+        let mut id = 0;
+        self.stale_runs.retain(|_| {
+            id += 1;
+            !removed[id - 1]
+        });
+        id = 0;
+        self.touched.retain(|_| {
+            id += 1;
+            !removed[id - 1]
+        });
+    }
+}
+
+impl<T, S> Pagerank<T, S>
+where
+    T: Eq + Hash + Clone,
+    S: BuildHasher,
+{
+    /// Enables decaying the score of nodes which received no new in edges
+    /// between calculations, so inactive nodes gradually drop in the
+    /// ranking of a graph updated over time.
+    ///
+    /// After each calculation, the score of a node is multiplied by
+    /// `factor` once per consecutive run it went without new in edges,
+    /// so a node stale for three runs is scaled by `factor³`. A new in
+    /// edge resets it. The factor is clamped to `0..=1`.
+    pub fn set_stale_decay(&mut self, factor: f64) {
+        let factor = if factor.is_nan() {
+            1f64
+        } else {
+            factor.clamp(0f64, 1f64)
+        };
+        match self.stale_decay.as_mut() {
+            Some(decay) => decay.factor = factor,
+            None => {
+                self.stale_decay = Some(StaleDecay {
+                    factor,
+                    stale_runs: Vec::new(),
+                    touched: Vec::new(),
+                })
+            }
+        }
+    }
+
+    /// Disables the decay of stale nodes and forgets their staleness
+    pub fn clear_stale_decay(&mut self) {
+        self.stale_decay = None;
+    }
+
+    /// Updates the staleness of every node at the end of a calculation
+    /// and scales the scores of the stale ones
+    pub(crate) fn apply_stale_decay(&mut self) {
+        let decay = match self.stale_decay.as_mut() {
+            Some(decay) => decay,
+            None => return,
+        };
+
+        decay.stale_runs.resize(self.nodes.len(), 0);
+        decay.touched.resize(self.nodes.len(), false);
+        for (id, node) in self.nodes.iter_mut().enumerate() {
+            if decay.touched[id] {
+                decay.stale_runs[id] = 0;
+                decay.touched[id] = false;
+            } else {
+                decay.stale_runs[id] =
+                    decay.stale_runs[id].saturating_add(1);
+                node.score *= decay
+                    .factor
+                    .powi(decay.stale_runs[id].min(i32::MAX as u32)
+                        as i32);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Pagerank;
+
+    #[test]
+    fn test_stale_decay() {
+        let mut pr = Pagerank::<&str>::new();
+        pr.add_edge("a", "b");
+        pr.add_edge("c", "d");
+        pr.set_stale_decay(0.5);
+
+        pr.calculate_with_convergence(1e-12);
+        let mut reference = Pagerank::<&str>::new();
+        reference.add_edge("a", "b");
+        reference.add_edge("c", "d");
+        reference.calculate_with_convergence(1e-12);
+        // Every node is stale on the first run after enabling the decay
+        assert_eq!(
+            reference.get_score("b").unwrap() * 0.5,
+            pr.get_score("b").unwrap()
+        );
+
+        pr.add_edge("a", "b");
+        pr.calculate_with_convergence(1e-12);
+        assert!(
+            pr.get_score("b").unwrap() > pr.get_score("d").unwrap()
+        );
+        let d = pr.get_score("d").unwrap();
+        reference.add_edge("a", "b");
+        reference.calculate_with_convergence(1e-12);
+        assert!(
+            (reference.get_score("d").unwrap() * 0.25 - d).abs()
+                < 1e-9
+        );
+
+        pr.clear_stale_decay();
+        pr.calculate_with_convergence(1e-12);
+        assert!(
+            (pr.get_score("d").unwrap() - 0.15 * 1.85).abs() < 1e-9
+        );
+    }
+}
@@ -0,0 +1,148 @@
+//! Stability of a ranking between two runs
+use crate::compat::{HashMap, HashSet};
+use crate::ScoreSet;
+use core::hash::Hash;
+
+/// Summary of how much a ranking changed, as returned by
+/// `ScoreSet::diff`
+#[derive(Debug, Clone, PartialEq)]
+pub struct ScoreDiff<T> {
+    /// Nodes present in both rankings
+    pub common: usize,
+    /// Nodes only in the newer ranking
+    pub added: usize,
+    /// Nodes only in the older ranking
+    pub removed: usize,
+    /// Mean of the absolute change in rank of the common nodes, 0 if
+    /// there are none
+    pub mean_rank_shift: f64,
+    /// Fraction of the top nodes of the older ranking which are still at
+    /// the top of the newer one, 1 if the older ranking is empty
+    pub top_retained: f64,
+    /// Common node whose rank changed the most, with its change in rank,
+    /// positive if it moved up. Ties go to the highest ranked one.
+    pub max_mover: Option<(T, i64)>,
+    /// Largest absolute change in score of the common nodes
+    pub max_score_change: f64,
+}
+
+impl<T> ScoreSet<T>
+where
+    T: Eq + Hash + Clone,
+{
+    /// Compares these scores with a newer set, summarizing how stable
+    /// the ranking is, so a pipeline can gate the deployment of a new
+    /// ranking on thresholds. `top` is the size of the head of the
+    /// ranking checked by `top_retained`, 100 is a common choice.
+    ///
+    /// Ranks start at 0 for the highest score, with ties broken as in
+    /// `nodes`. To compare runs stored as snapshots, load them with
+    /// `Pagerank::read_snapshot` and take their `score_set`.
+    pub fn diff(
+        &self,
+        newer: &ScoreSet<T>,
+        top: usize,
+    ) -> ScoreDiff<T> {
+        let old = self.nodes();
+        let new = newer.nodes();
+        let new_ranks = new
+            .iter()
+            .enumerate()
+            .map(|(rank, (node, score))| (*node, (rank, *score)))
+            .collect::<HashMap<&T, (usize, f64)>>();
+
+        let mut common = 0;
+        let mut total_shift = 0u64;
+        let mut max_mover: Option<(&T, i64)> = None;
+        let mut max_score_change = 0f64;
+        for (rank, (node, score)) in old.iter().enumerate() {
+            let (new_rank, new_score) = match new_ranks.get(node) {
+                Some(found) => *found,
+                None => continue,
+            };
+            common += 1;
+            let shift = rank as i64 - new_rank as i64;
+            total_shift += shift.unsigned_abs();
+            if max_mover
+                .is_none_or(|(_, max)| shift.abs() > max.abs())
+            {
+                max_mover = Some((node, shift));
+            }
+            max_score_change =
+                max_score_change.max((new_score - score).abs());
+        }
+
+        let top_old = old.iter().take(top).map(|(node, _)| *node);
+        let top_new = new
+            .iter()
+            .take(top)
+            .map(|(node, _)| *node)
+            .collect::<HashSet<&T>>();
+        let checked = top.min(old.len());
+        let top_retained = if checked == 0 {
+            1f64
+        } else {
+            top_old.filter(|node| top_new.contains(node)).count()
+                as f64
+                / checked as f64
+        };
+
+        ScoreDiff {
+            common,
+            added: new.len() - common,
+            removed: old.len() - common,
+            mean_rank_shift: if common == 0 {
+                0f64
+            } else {
+                total_shift as f64 / common as f64
+            },
+            top_retained,
+            max_mover: max_mover
+                .filter(|(_, shift)| *shift != 0)
+                .map(|(node, shift)| (node.clone(), shift)),
+            max_score_change,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Pagerank;
+
+    #[test]
+    fn test_diff() {
+        let mut pr = Pagerank::<&str>::new();
+        pr.add_edge("l1", "a");
+        pr.add_edge("l2", "a");
+        pr.add_edge("l3", "b");
+        pr.calculate();
+        let before = pr.score_set();
+
+        let same = before.diff(&before, 100);
+        assert_eq!(5, same.common);
+        assert_eq!(0.0, same.mean_rank_shift);
+        assert_eq!(1.0, same.top_retained);
+        assert_eq!(None, same.max_mover);
+
+        // b overtakes a, everything else keeps its rank
+        for source in ["m1", "m2", "m3"].iter() {
+            pr.add_edge(source, "b");
+        }
+        pr.calculate();
+        let diff = before.diff(&pr.score_set(), 1);
+        assert_eq!(5, diff.common);
+        assert_eq!(3, diff.added);
+        assert_eq!(0, diff.removed);
+        assert_eq!(0.4, diff.mean_rank_shift);
+        assert_eq!(0.0, diff.top_retained);
+        assert_eq!(Some(("a", -1)), diff.max_mover);
+        assert!(
+            (diff.max_score_change - 3.0 * 0.85 * 0.15).abs() < 1e-9
+        );
+        assert_eq!(1.0, before.diff(&pr.score_set(), 2).top_retained);
+
+        let reverse = pr.score_set().diff(&before, 100);
+        assert_eq!(3, reverse.removed);
+        assert_eq!(Some(("b", -1)), reverse.max_mover);
+    }
+}
@@ -0,0 +1,164 @@
+//! Graphviz export
+#[cfg(not(feature = "std"))]
+use crate::compat::prelude::*;
+use crate::Pagerank;
+use core::fmt::{Display, Write};
+use core::hash::{BuildHasher, Hash};
+
+/// Options of `Pagerank::to_dot`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DotOptions {
+    /// Only this many highest scored nodes, and the edges between
+    /// them, are exported. All of them by default.
+    pub max_nodes: Option<usize>,
+    /// Whether labels include the score under the node
+    pub show_scores: bool,
+    /// Width, in inches, of the lowest scored node
+    pub min_size: f64,
+    /// Width, in inches, of the highest scored node
+    pub max_size: f64,
+}
+
+impl Default for DotOptions {
+    fn default() -> Self {
+        DotOptions {
+            max_nodes: None,
+            show_scores: true,
+            min_size: 0.5,
+            max_size: 2.0,
+        }
+    }
+}
+
+impl<T, S> Pagerank<T, S>
+where
+    T: Eq + Hash + Clone + Display,
+    S: BuildHasher,
+{
+    /// Exports the graph in the Graphviz DOT format, to inspect small
+    /// graphs or subgraphs after ranking, with `dot -Tsvg` for instance.
+    ///
+    /// Nodes are labeled with their key and sized and shaded by their
+    /// current score, from `min_size` and white for the lowest to
+    /// `max_size` and red for the highest. Each stored edge is exported,
+    /// so with an in edges cap only the sample of them is.
+    pub fn to_dot(&self, options: &DotOptions) -> String {
+        let mut kept = vec![true; self.nodes.len()];
+        if let Some(max_nodes) = options.max_nodes {
+            kept = vec![false; self.nodes.len()];
+            for (node, _) in self.top_k(max_nodes) {
+                kept[self.node_positions[node]] = true;
+            }
+        }
+
+        let scores = self
+            .nodes
+            .iter()
+            .zip(kept.iter())
+            .filter(|(_, kept)| **kept)
+            .map(|(n, _)| n.score);
+        let (min, max) = scores.fold(
+            (f64::INFINITY, f64::NEG_INFINITY),
+            |(min, max), score| (min.min(score), max.max(score)),
+        );
+
+        let mut dot = String::from("digraph pagerank {\n");
+        dot.push_str("    node [shape=circle, style=filled];\n");
+        for (id, n) in self.nodes.iter().enumerate() {
+            if !kept[id] {
+                continue;
+            }
+
+            let relative = if max > min {
+                (n.score - min) / (max - min)
+            } else {
+                1f64
+            };
+            let mut label = n.node.to_string();
+            if options.show_scores {
+                let _ = write!(
+                    label,
+                    "\n{:.4}",
+                    self.transformed(n.score)
+                );
+            }
+            let _ = write!(dot, "    n{} [label=", id);
+            write_dot_string(&mut dot, &label);
+            let _ = writeln!(
+                dot,
+                ", width={:.3}, fillcolor=\"0.000 {:.3} 1.000\"];",
+                options.min_size
+                    + relative
+                        * (options.max_size - options.min_size),
+                relative,
+            );
+        }
+
+        for target in 0..self.nodes.len() {
+            for source in self.in_edges(target).iter() {
+                if kept[*source] && kept[target] {
+                    let _ = writeln!(
+                        dot,
+                        "    n{} -> n{};",
+                        source, target
+                    );
+                }
+            }
+        }
+        dot.push_str("}\n");
+        dot
+    }
+}
+
+/// Appends `value` as a quoted and escaped DOT string
+fn write_dot_string(dot: &mut String, value: &str) {
+    dot.push('"');
+    for c in value.chars() {
+        match c {
+            '"' => dot.push_str("\\\""),
+            '\\' => dot.push_str("\\\\"),
+            '\n' => dot.push_str("\\n"),
+            '\r' => {}
+            c => dot.push(c),
+        }
+    }
+    dot.push('"');
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{DotOptions, Pagerank};
+
+    #[test]
+    fn test_to_dot() {
+        let mut pr = Pagerank::<&str>::new();
+        pr.add_edge("a", "b");
+        pr.add_edge("c", "b");
+        pr.add_edge("b", "say \"hi\"");
+        pr.calculate();
+
+        let dot = pr.to_dot(&DotOptions::default());
+        assert!(dot.starts_with("digraph pagerank {\n"));
+        assert!(dot.ends_with("}\n"));
+        assert!(dot.contains("n2 [label=\"c\\n0.1500\", width=0.500"));
+        assert!(dot.contains("[label=\"say \\\"hi\\\"\\n"));
+        assert!(dot.contains("fillcolor=\"0.000 1.000 1.000\""));
+        assert!(dot.contains("    n0 -> n1;\n"));
+        assert_eq!(3, dot.matches("->").count());
+
+        let options = DotOptions {
+            max_nodes: Some(2),
+            show_scores: false,
+            ..DotOptions::default()
+        };
+        let dot = pr.to_dot(&options);
+        assert!(dot.contains("n1 [label=\"b\", width=0.500"));
+        assert!(!dot.contains("label=\"a\""));
+        assert_eq!(
+            vec!["    n1 -> n3;"],
+            dot.lines()
+                .filter(|l| l.contains("->"))
+                .collect::<Vec<_>>()
+        );
+    }
+}
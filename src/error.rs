@@ -0,0 +1,300 @@
+//! Error covering every fallible operation of the crate
+use crate::{
+    CsrError, CursorError, DampingError, EdgeError, Pagerank,
+    RankResult,
+};
+#[cfg(feature = "std")]
+use crate::{GoldenError, ImportError, KeyError};
+use core::error::Error;
+use core::fmt;
+use core::hash::{BuildHasher, Hash};
+#[cfg(feature = "std")]
+use std::io;
+
+/// Any error returned by this crate, so applications mixing several
+/// fallible operations can propagate them with `?` into a single type.
+///
+/// Each operation still returns its own, narrower error, which converts
+/// into this one. Matching on the variant tells what went wrong without
+/// parsing messages.
+#[derive(Debug)]
+pub enum PagerankError {
+    /// The damping factor must be below 100
+    InvalidDamping(u8),
+    /// The convergence must be a positive number
+    InvalidConvergence(f64),
+    /// An edge could not be added
+    Edge(EdgeError),
+    /// The calculation stopped before converging
+    NotConverged {
+        /// Number of iterations run
+        iterations: usize,
+        /// Residual of the last iteration
+        residual: f64,
+    },
+    /// Scores are NaN or infinite, usually because a weight closure
+    /// returned such a weight
+    NonFiniteScores {
+        /// Number of nodes whose score is not finite
+        nodes: usize,
+    },
+    /// An input could not be imported
+    #[cfg(feature = "std")]
+    Import(ImportError),
+    /// CSR arrays are inconsistent
+    Csr(CsrError),
+    /// Scores do not match a golden file
+    #[cfg(feature = "std")]
+    Golden(GoldenError),
+    /// A ranking cursor is malformed or stale
+    Cursor(CursorError),
+    /// A key could not be hashed
+    #[cfg(feature = "std")]
+    Key(KeyError),
+    /// Reading or writing failed
+    #[cfg(feature = "std")]
+    Io(io::Error),
+}
+
+impl fmt::Display for PagerankError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PagerankError::InvalidDamping(factor) => {
+                DampingError::OutOfRange(*factor).fmt(f)
+            }
+            PagerankError::InvalidConvergence(convergence) => write!(
+                f,
+                "convergence {} needs to be a positive number",
+                convergence
+            ),
+            PagerankError::Edge(err) => err.fmt(f),
+            PagerankError::NotConverged {
+                iterations,
+                residual,
+            } => write!(
+                f,
+                "did not converge after {} iterations, residual {}",
+                iterations, residual
+            ),
+            PagerankError::NonFiniteScores { nodes } => {
+                write!(
+                    f,
+                    "{} nodes have a score which is not finite",
+                    nodes
+                )
+            }
+            #[cfg(feature = "std")]
+            PagerankError::Import(err) => err.fmt(f),
+            PagerankError::Csr(err) => err.fmt(f),
+            #[cfg(feature = "std")]
+            PagerankError::Golden(err) => err.fmt(f),
+            PagerankError::Cursor(err) => err.fmt(f),
+            #[cfg(feature = "std")]
+            PagerankError::Key(err) => err.fmt(f),
+            #[cfg(feature = "std")]
+            PagerankError::Io(err) => err.fmt(f),
+        }
+    }
+}
+
+impl Error for PagerankError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            PagerankError::InvalidDamping(_)
+            | PagerankError::InvalidConvergence(_)
+            | PagerankError::NotConverged { .. }
+            | PagerankError::NonFiniteScores { .. } => None,
+            PagerankError::Edge(err) => Some(err),
+            #[cfg(feature = "std")]
+            PagerankError::Import(err) => Some(err),
+            PagerankError::Csr(err) => Some(err),
+            #[cfg(feature = "std")]
+            PagerankError::Golden(err) => Some(err),
+            PagerankError::Cursor(err) => Some(err),
+            #[cfg(feature = "std")]
+            PagerankError::Key(err) => Some(err),
+            #[cfg(feature = "std")]
+            PagerankError::Io(err) => Some(err),
+        }
+    }
+}
+
+impl From<DampingError> for PagerankError {
+    fn from(err: DampingError) -> Self {
+        match err {
+            DampingError::OutOfRange(factor) => {
+                PagerankError::InvalidDamping(factor)
+            }
+        }
+    }
+}
+
+impl From<EdgeError> for PagerankError {
+    fn from(err: EdgeError) -> Self {
+        PagerankError::Edge(err)
+    }
+}
+
+#[cfg(feature = "std")]
+impl From<ImportError> for PagerankError {
+    fn from(err: ImportError) -> Self {
+        PagerankError::Import(err)
+    }
+}
+
+impl From<CsrError> for PagerankError {
+    fn from(err: CsrError) -> Self {
+        PagerankError::Csr(err)
+    }
+}
+
+#[cfg(feature = "std")]
+impl From<GoldenError> for PagerankError {
+    fn from(err: GoldenError) -> Self {
+        PagerankError::Golden(err)
+    }
+}
+
+impl From<CursorError> for PagerankError {
+    fn from(err: CursorError) -> Self {
+        PagerankError::Cursor(err)
+    }
+}
+
+#[cfg(feature = "std")]
+impl From<KeyError> for PagerankError {
+    fn from(err: KeyError) -> Self {
+        PagerankError::Key(err)
+    }
+}
+
+#[cfg(feature = "std")]
+impl From<io::Error> for PagerankError {
+    fn from(err: io::Error) -> Self {
+        PagerankError::Io(err)
+    }
+}
+
+impl RankResult {
+    /// Returns the result if the calculation converged, and
+    /// `PagerankError::NotConverged` otherwise, for callers which treat
+    /// hitting the iteration limit as a failure
+    pub fn ensure_converged(
+        self,
+    ) -> Result<RankResult, PagerankError> {
+        if self.converged {
+            Ok(self)
+        } else {
+            Err(PagerankError::NotConverged {
+                iterations: self.iterations,
+                residual: self.residual,
+            })
+        }
+    }
+}
+
+impl<T, S> Pagerank<T, S>
+where
+    T: Eq + Hash + Clone,
+    S: BuildHasher,
+{
+    /// Checks that every score is a finite number, failing with
+    /// `PagerankError::NonFiniteScores` otherwise, so services can reject
+    /// a broken calculation before serving it. Queries never panic on
+    /// such scores: NaN is ranked below every other score.
+    pub fn check_scores(&self) -> Result<(), PagerankError> {
+        let nodes = self
+            .nodes
+            .iter()
+            .filter(|n| !n.score.is_finite())
+            .count();
+        if nodes == 0 {
+            Ok(())
+        } else {
+            Err(PagerankError::NonFiniteScores { nodes })
+        }
+    }
+}
+
+#[cfg(all(test, feature = "generators"))]
+mod tests {
+    use super::PagerankError;
+    use crate::{generators, ImportOptions, Pagerank};
+
+    fn build(damping: u8) -> Result<f64, PagerankError> {
+        let mut pr = generators::ring(10);
+        pr.set_damping_factor(damping)?;
+        pr.try_add_edge(0, 5)?;
+        pr.import_tsv(&b"1\t7\n"[..], &ImportOptions::default())?;
+        let result = pr.calculate_with_limits(1e-12, 5);
+        Ok(result.ensure_converged()?.residual)
+    }
+
+    #[test]
+    fn test_pagerank_error() {
+        match build(100) {
+            Err(PagerankError::InvalidDamping(100)) => {}
+            other => panic!("unexpected {:?}", other),
+        }
+        match build(85) {
+            Err(PagerankError::NotConverged {
+                iterations, ..
+            }) => {
+                assert_eq!(5, iterations)
+            }
+            other => panic!("unexpected {:?}", other),
+        }
+        assert_eq!(
+            "damping factor 100 needs to be below 100",
+            build(100).unwrap_err().to_string()
+        );
+
+        let mut pr = Pagerank::<u32>::new();
+        let err: PagerankError =
+            pr.add_edge_weighted(1, 2, -1.0).unwrap_err().into();
+        assert!(std::error::Error::source(&err).is_some());
+    }
+
+    #[test]
+    fn test_nan_scores() {
+        let mut pr = generators::ring(10);
+        pr.add_edge(20, 21);
+        pr.calculate();
+        assert!(pr.check_scores().is_ok());
+
+        // Invalid edge weights count as 0, so a NaN score is planted
+        let id = pr.node_positions[&21];
+        pr.nodes[id].score = f64::NAN;
+        match pr.check_scores() {
+            Err(PagerankError::NonFiniteScores { nodes }) => {
+                assert_eq!(1, nodes)
+            }
+            other => panic!("unexpected {:?}", other),
+        }
+
+        // Queries rank NaN last instead of panicking
+        fn keys(nodes: &[(&usize, f64)]) -> Vec<usize> {
+            nodes.iter().map(|(n, _)| **n).collect()
+        }
+        let nodes = pr.nodes();
+        assert_eq!((&21, true), (nodes[11].0, nodes[11].1.is_nan()));
+        assert_eq!(keys(&nodes), keys(&pr.top_k(12)));
+        let mut top = Vec::new();
+        pr.top_k_into(12, &mut top);
+        assert_eq!(keys(&nodes), keys(&top));
+        let ranked = pr.iter_ranked().collect::<Vec<_>>();
+        assert_eq!(keys(&nodes), keys(&ranked));
+        assert!(pr
+            .score_quantiles(10)
+            .score_at(0.5)
+            .unwrap()
+            .is_finite());
+
+        let scores = pr.score_set();
+        let cursor = scores.ranked_cursor();
+        let (page, cursor) = scores.page(&cursor, 11).unwrap();
+        assert_eq!(keys(&nodes[..11]), keys(&page));
+        let (page, _) = scores.page(&cursor, 11).unwrap();
+        assert_eq!(vec![21], keys(&page));
+    }
+}
@@ -0,0 +1,347 @@
+//! Comparing calculations with different parameters
+#[cfg(not(feature = "std"))]
+use crate::compat::prelude::*;
+use crate::compat::HashSet;
+use crate::compat::Instant;
+use crate::graph::cmp_scores;
+use crate::Pagerank;
+use core::fmt;
+use core::hash::{BuildHasher, Hash};
+use core::mem;
+use core::time::Duration;
+#[cfg(feature = "std")]
+use std::thread;
+
+/// Parameters of one calculation of `Pagerank::run_experiments`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ExperimentParams {
+    /// Damping factor, between 0 and 1
+    pub damping: f64,
+    /// Residual below which the calculation stops
+    pub convergence: f64,
+    /// Iterations after which the calculation gives up, if any
+    pub max_iterations: Option<usize>,
+}
+
+impl ExperimentParams {
+    /// Returns every combination of the given damping factors and
+    /// convergences, without an iterations limit
+    pub fn grid(
+        dampings: &[f64],
+        convergences: &[f64],
+    ) -> Vec<ExperimentParams> {
+        dampings
+            .iter()
+            .flat_map(|damping| {
+                convergences.iter().map(move |convergence| {
+                    ExperimentParams {
+                        damping: *damping,
+                        convergence: *convergence,
+                        max_iterations: None,
+                    }
+                })
+            })
+            .collect()
+    }
+}
+
+/// Outcome of one calculation of `Pagerank::run_experiments`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ExperimentRun {
+    /// Parameters of the calculation
+    pub params: ExperimentParams,
+    /// Number of iterations run, the last one included
+    pub iterations: usize,
+    /// Whether the residual fell below the convergence
+    pub converged: bool,
+    /// Residual of the last iteration
+    pub residual: f64,
+    /// Share of the top `k` nodes of the baseline which are also in the
+    /// top `k` of this calculation, from 0 to 1
+    pub overlap: f64,
+    /// Time spent calculating
+    pub elapsed: Duration,
+}
+
+/// Comparison of calculations against a baseline, returned by
+/// `Pagerank::run_experiments`. It displays as a text table.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ExperimentTable {
+    /// Number of top nodes compared
+    pub k: usize,
+    /// Calculation with the settings of the graph
+    pub baseline: ExperimentRun,
+    /// Calculations of the grid, in the same order
+    pub runs: Vec<ExperimentRun>,
+}
+
+impl fmt::Display for ExperimentTable {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(
+            f,
+            "{:<10} {:>8} {:>12} {:>10} {:>10} {:>12} {:>8}",
+            "run",
+            "damping",
+            "convergence",
+            "iterations",
+            "converged",
+            "residual",
+            format!("top{}", self.k),
+        )?;
+        let runs = self.runs.iter().enumerate();
+        let rows =
+            core::iter::once(("baseline".to_owned(), &self.baseline))
+                .chain(runs.map(|(i, run)| (i.to_string(), run)));
+        for (label, run) in rows {
+            writeln!(
+                f,
+                "{:<10} {:>8.3} {:>12.1e} {:>10} {:>10} {:>12.3e} {:>8.3}",
+                label,
+                run.params.damping,
+                run.params.convergence,
+                run.iterations,
+                run.converged,
+                run.residual,
+                run.overlap,
+            )?;
+        }
+        Ok(())
+    }
+}
+
+impl<T, S> Pagerank<T, S>
+where
+    T: Eq + Hash + Clone + Sync,
+    S: BuildHasher + Sync,
+{
+    /// Runs a calculation for each parameter set of `grid`, and compares
+    /// it with a baseline calculation using the damping factor,
+    /// convergence and iterations limit of the graph, for instance to
+    /// choose a damping factor or see how much a looser convergence
+    /// changes the top `k` nodes.
+    ///
+    /// Calculations run over `threads` scoped threads, sharing the graph
+    /// read-only, or on the calling thread if it is 0 or 1, or without
+    /// the `std` feature. Each one
+    /// starts from the current scores, in double precision and with the
+    /// convergence norm and dangling policy of the graph. The scores of
+    /// the graph are left untouched.
+    pub fn run_experiments(
+        &mut self,
+        grid: &[ExperimentParams],
+        k: usize,
+        threads: usize,
+    ) -> ExperimentTable {
+        self.finalize_weights();
+        let nodes_with_in_edges = self.len_nodes_with_in_edges();
+        let graph = &*self;
+
+        let params = ExperimentParams {
+            damping: graph.damping,
+            convergence: graph.convergence,
+            max_iterations: graph.max_iterations,
+        };
+        let (mut baseline, scores) =
+            graph.run_experiment(&params, nodes_with_in_edges);
+        let top = top_ids(&scores, k);
+        baseline.overlap = 1f64;
+        let run = |params: &ExperimentParams| {
+            let (mut run, scores) =
+                graph.run_experiment(params, nodes_with_in_edges);
+            run.overlap = overlap(&top, &top_ids(&scores, k));
+            run
+        };
+
+        let runs = if threads <= 1 || grid.len() <= 1 {
+            grid.iter().map(run).collect()
+        } else {
+            run_in_threads(grid, threads, &run)
+        };
+
+        ExperimentTable { k, baseline, runs }
+    }
+
+    /// Runs one calculation without touching the graph, returning its
+    /// outcome, without the overlap, and the scores
+    fn run_experiment(
+        &self,
+        params: &ExperimentParams,
+        nodes_with_in_edges: usize,
+    ) -> (ExperimentRun, Vec<f64>) {
+        let started = Instant::now();
+        let mut teleport = Vec::<f64>::new();
+        self.teleport_scores_into(params.damping, &mut teleport);
+        let dangling = self.dangling_shares(&teleport);
+        let norm = self.convergence_norm;
+
+        let mut previous =
+            self.nodes.iter().map(|n| n.score).collect::<Vec<f64>>();
+        let mut scores = Vec::with_capacity(previous.len());
+        let mut iterations = 0;
+        let residual = loop {
+            iterations += 1;
+            let dangling_score = match dangling {
+                Some(_) => self.dangling_score(&previous),
+                None => 0f64,
+            };
+            let mut total = 0f64;
+            scores.clear();
+            for id in 0..self.nodes.len() {
+                if let Some(score) = self.pinned_score(id) {
+                    scores.push(score);
+                    continue;
+                }
+                let mut incoming = self.incoming(id, &previous);
+                if let Some(shares) = dangling.as_ref() {
+                    incoming += dangling_score * shares[id];
+                }
+                let score = teleport[id] + params.damping * incoming;
+                total = norm.accumulate(total, previous[id], score);
+                scores.push(score);
+            }
+            mem::swap(&mut previous, &mut scores);
+
+            let residual = norm.residual(total, nodes_with_in_edges);
+            if residual < params.convergence
+                || params.max_iterations == Some(iterations)
+            {
+                break residual;
+            }
+        };
+
+        let converged = residual < params.convergence;
+        let run = ExperimentRun {
+            params: *params,
+            iterations,
+            converged,
+            residual,
+            overlap: 0f64,
+            elapsed: started.elapsed(),
+        };
+        (run, previous)
+    }
+}
+
+/// Runs `run` over every parameter set of `grid`, split among
+/// `threads` scoped threads, keeping the order of `grid`
+#[cfg(feature = "std")]
+fn run_in_threads<F>(
+    grid: &[ExperimentParams],
+    threads: usize,
+    run: &F,
+) -> Vec<ExperimentRun>
+where
+    F: Fn(&ExperimentParams) -> ExperimentRun + Sync,
+{
+    let chunk = grid.len().div_ceil(threads);
+    thread::scope(|s| {
+        let workers = grid
+            .chunks(chunk)
+            .map(|params| {
+                s.spawn(move || {
+                    params.iter().map(run).collect::<Vec<_>>()
+                })
+            })
+            .collect::<Vec<_>>();
+        workers
+            .into_iter()
+            .flat_map(|w| w.join().unwrap())
+            .collect()
+    })
+}
+
+/// Runs `run` over every parameter set of `grid` on the calling thread,
+/// without threads to spread them on
+#[cfg(not(feature = "std"))]
+fn run_in_threads<F>(
+    grid: &[ExperimentParams],
+    _threads: usize,
+    run: &F,
+) -> Vec<ExperimentRun>
+where
+    F: Fn(&ExperimentParams) -> ExperimentRun,
+{
+    grid.iter().map(run).collect()
+}
+
+/// Ids of the `k` highest scores. Ties keep the lowest ids.
+fn top_ids(scores: &[f64], k: usize) -> HashSet<usize> {
+    let mut ids = (0..scores.len()).collect::<Vec<usize>>();
+    let order = |a: &usize, b: &usize| {
+        cmp_scores(scores[*b], scores[*a]).then(a.cmp(b))
+    };
+    if k == 0 {
+        ids.clear();
+    } else if k < ids.len() {
+        ids.select_nth_unstable_by(k - 1, order);
+        ids.truncate(k);
+    }
+    ids.into_iter().collect()
+}
+
+/// Share of the ids of `baseline` also in `other`
+fn overlap(baseline: &HashSet<usize>, other: &HashSet<usize>) -> f64 {
+    if baseline.is_empty() {
+        return 1f64;
+    }
+    baseline.intersection(other).count() as f64
+        / baseline.len() as f64
+}
+
+#[cfg(all(test, feature = "generators"))]
+mod tests {
+    use crate::{generators, ExperimentParams, ExperimentTable};
+
+    #[test]
+    fn test_run_experiments() {
+        let mut pr = generators::watts_strogatz(300, 4, 0.3, 3);
+        pr.convergence = 1e-9;
+        let before =
+            pr.nodes().iter().map(|(_, s)| *s).collect::<Vec<_>>();
+        let grid =
+            ExperimentParams::grid(&[0.85, 0.5, 0.99], &[1e-3, 1e-9]);
+        assert_eq!(6, grid.len());
+
+        let table = pr.run_experiments(&grid, 20, 4);
+        assert_eq!(
+            before,
+            pr.nodes().iter().map(|(_, s)| *s).collect::<Vec<_>>()
+        );
+        let outcomes = |table: &ExperimentTable| {
+            table
+                .runs
+                .iter()
+                .map(|run| {
+                    (run.iterations, run.residual, run.overlap)
+                })
+                .collect::<Vec<_>>()
+        };
+        let sequential = pr.run_experiments(&grid, 20, 1);
+        assert_eq!(outcomes(&table), outcomes(&sequential));
+        assert_eq!(6, table.runs.len());
+        assert_eq!(grid[3], table.runs[3].params);
+        assert_eq!(1f64, table.baseline.overlap);
+        assert!(table.runs.iter().all(|run| run.converged));
+        assert!(table.runs[1].iterations > table.runs[0].iterations);
+        assert!(table.runs[5].iterations > table.runs[3].iterations);
+
+        // The baseline is what calculating the graph gives
+        let result = pr.calculate();
+        assert_eq!(result.iterations, table.baseline.iterations);
+        assert_eq!(result.residual, table.baseline.residual);
+        assert_eq!(1f64, table.runs[1].overlap);
+        assert!(table.runs[3].overlap < 1f64);
+
+        let limited = ExperimentParams {
+            max_iterations: Some(2),
+            ..grid[5]
+        };
+        let table = pr.run_experiments(&[limited], 20, 1);
+        assert_eq!(2, table.runs[0].iterations);
+        assert!(!table.runs[0].converged);
+
+        let text = table.to_string();
+        assert_eq!(3, text.lines().count());
+        assert!(text.lines().nth(1).unwrap().starts_with("baseline"));
+    }
+}
@@ -0,0 +1,187 @@
+//! Score provenance
+#[cfg(not(feature = "std"))]
+use crate::compat::prelude::*;
+use crate::graph::cmp_scores;
+use crate::Pagerank;
+use alloc::collections::BTreeMap;
+use core::fmt::{Display, Write};
+use core::hash::{BuildHasher, Hash};
+
+/// How the score of a node is made up from the nodes linking to it, as
+/// returned by `Pagerank::explain`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Contribution<'a, T> {
+    /// The node
+    pub node: &'a T,
+    /// Current score of the node
+    pub score: f64,
+    /// Part of the score coming from the node to its parent in the tree.
+    /// For the root, it is the whole score.
+    pub contribution: f64,
+    /// Part of the score coming from teleporting
+    pub teleport: f64,
+    /// Contributions of the nodes linking to this one, largest first.
+    /// Empty once the requested depth is reached.
+    pub sources: Vec<Contribution<'a, T>>,
+}
+
+impl<'a, T> Contribution<'a, T>
+where
+    T: Display,
+{
+    /// Serializes the contribution tree as JSON, for UI display
+    pub fn to_json(&self) -> String {
+        let mut json = String::new();
+        self.write_json(&mut json);
+        json
+    }
+
+    fn write_json(&self, json: &mut String) {
+        json.push_str("{\"node\":");
+        write_json_string(json, &self.node.to_string());
+        let _ = write!(
+            json,
+            ",\"score\":{},\"contribution\":{},\"teleport\":{},\"sources\":[",
+            self.score, self.contribution, self.teleport
+        );
+        for (i, source) in self.sources.iter().enumerate() {
+            if i > 0 {
+                json.push(',');
+            }
+            source.write_json(json);
+        }
+        json.push_str("]}");
+    }
+}
+
+/// Appends `value` as a quoted and escaped JSON string
+pub(crate) fn write_json_string(json: &mut String, value: &str) {
+    json.push('"');
+    for c in value.chars() {
+        match c {
+            '"' => json.push_str("\\\""),
+            '\\' => json.push_str("\\\\"),
+            '\n' => json.push_str("\\n"),
+            '\r' => json.push_str("\\r"),
+            '\t' => json.push_str("\\t"),
+            c if (c as u32) < 0x20 => {
+                let _ = write!(json, "\\u{:04x}", c as u32);
+            }
+            c => json.push(c),
+        }
+    }
+    json.push('"');
+}
+
+impl<T, S> Pagerank<T, S>
+where
+    T: Eq + Hash + Clone,
+    S: BuildHasher,
+{
+    /// Explains the current score of a node as a tree of contributions:
+    /// how much each node linking to it adds to its score, and in turn
+    /// how their own scores are made up, down to `depth` levels.
+    ///
+    /// Repeated edges from the same source are merged into a single
+    /// contribution. Returns `None` for unknown nodes.
+    pub fn explain(
+        &self,
+        node: T,
+        depth: usize,
+    ) -> Option<Contribution<'_, T>> {
+        let id = *self.node_positions.get(&node)?;
+        let teleport = self.teleport_scores();
+        let score = self.nodes[id].score;
+
+        Some(self.contribution(id, score, depth, &teleport))
+    }
+
+    fn contribution(
+        &self,
+        id: usize,
+        contribution: f64,
+        depth: usize,
+        teleport: &[f64],
+    ) -> Contribution<'_, T> {
+        let n = &self.nodes[id];
+        let mut sources = Vec::new();
+
+        if depth > 0 {
+            let mut edges = BTreeMap::new();
+            let scale = self.in_edges_weight(id);
+            for (slot, source) in self.in_edges(id).iter().enumerate()
+            {
+                *edges.entry(*source).or_insert(0f64) +=
+                    scale * self.edge_share(*source, id, slot);
+            }
+
+            sources = edges
+                .into_iter()
+                .map(|(source, share)| {
+                    let from = &self.nodes[source];
+                    let value = self.damping * share * from.score;
+                    self.contribution(
+                        source,
+                        value,
+                        depth - 1,
+                        teleport,
+                    )
+                })
+                .collect::<Vec<Contribution<T>>>();
+
+            sources.sort_by(|a, b| {
+                cmp_scores(b.contribution, a.contribution)
+            });
+        }
+
+        Contribution {
+            node: &n.node,
+            score: n.score,
+            contribution,
+            teleport: teleport[id],
+            sources,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Pagerank;
+
+    #[test]
+    fn test_explain() {
+        let mut pr = Pagerank::<&str>::new();
+        pr.add_edge("foo", "bar");
+        pr.add_edge("bar", "foo");
+        pr.add_edge("xxx", "bar");
+        pr.add_edge("xxx", "bar");
+        pr.add_edge("xxx", "yyy");
+        pr.calculate_with_convergence(1e-12);
+
+        let tree = pr.explain("bar", 2).expect("known node");
+        assert_eq!("bar", *tree.node);
+        assert_eq!(vec!["foo", "xxx"], {
+            tree.sources
+                .iter()
+                .map(|s| *s.node)
+                .collect::<Vec<&str>>()
+        });
+
+        let from_sources =
+            tree.sources.iter().map(|s| s.contribution).sum::<f64>();
+        assert!(
+            (tree.teleport + from_sources - tree.score).abs() < 1e-9
+        );
+        assert_eq!(
+            0.85 * 0.15 * 2.0 / 3.0,
+            tree.sources[1].contribution
+        );
+        assert_eq!(1, tree.sources[0].sources.len());
+        assert!(tree.sources[0].sources[0].sources.is_empty());
+        assert!(pr.explain("unknown", 2).is_none());
+
+        let json = pr.explain("yyy", 1).unwrap().to_json();
+        assert!(json.starts_with("{\"node\":\"yyy\",\"score\":"));
+        assert!(json.contains("\"sources\":[{\"node\":\"xxx\""));
+    }
+}
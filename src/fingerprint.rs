@@ -0,0 +1,125 @@
+//! Stable fingerprints of the graph structure
+use crate::graph::hash_bytes;
+use crate::io::SnapshotKey;
+use crate::Pagerank;
+use std::hash::{BuildHasher, Hash};
+
+impl<T, S> Pagerank<T, S>
+where
+    T: Eq + Hash + Clone + SnapshotKey,
+    S: BuildHasher,
+{
+    /// Returns a 128 bit hash of the structure of the graph: its nodes,
+    /// its stored edges and their weights, as given to
+    /// `add_edge_weighted`. Two processes get the same fingerprint for
+    /// the same graph, on any platform, so it can be used to check they
+    /// rank the same graph or to key cached results.
+    ///
+    /// It does not depend on the order nodes and edges were added.
+    /// Scores and settings, including the weight closure, are not part
+    /// of it.
+    pub fn fingerprint(&self) -> u128 {
+        let mut key = Vec::new();
+        let keys = self
+            .nodes
+            .iter()
+            .map(|n| {
+                key.clear();
+                n.node.write_key(&mut key);
+                (hash_bytes(0, &key), hash_bytes(1, &key))
+            })
+            .collect::<Vec<(u64, u64)>>();
+
+        // Sums are order independent, and each term is a hash of its
+        // own so related nodes or edges don't cancel out
+        let mut nodes = 0u128;
+        let mut edges = 0u128;
+        let mut buf = Vec::with_capacity(40);
+        for (target, n) in self.nodes.iter().enumerate() {
+            nodes = nodes.wrapping_add(combine(keys[target]));
+            let scale = self.in_edges_weight(target);
+            for (slot, source) in
+                self.in_edges(target).iter().enumerate()
+            {
+                buf.clear();
+                buf.extend_from_slice(&keys[*source].0.to_le_bytes());
+                buf.extend_from_slice(&keys[*source].1.to_le_bytes());
+                buf.extend_from_slice(&keys[target].0.to_le_bytes());
+                buf.extend_from_slice(&keys[target].1.to_le_bytes());
+                let weight = n.edge_weight(slot) * scale;
+                buf.extend_from_slice(
+                    &weight.to_bits().to_le_bytes(),
+                );
+                edges = edges.wrapping_add(combine((
+                    hash_bytes(2, &buf),
+                    hash_bytes(3, &buf),
+                )));
+            }
+        }
+
+        let mut buf = Vec::with_capacity(48);
+        buf.extend_from_slice(&nodes.to_le_bytes());
+        buf.extend_from_slice(&edges.to_le_bytes());
+        buf.extend_from_slice(
+            &(self.nodes.len() as u64).to_le_bytes(),
+        );
+        buf.extend_from_slice(&self.edges.to_le_bytes());
+        combine((hash_bytes(4, &buf), hash_bytes(5, &buf)))
+    }
+}
+
+/// Joins two 64 bit hashes into a 128 bit one
+fn combine((high, low): (u64, u64)) -> u128 {
+    (high as u128) << 64 | low as u128
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Pagerank;
+
+    #[test]
+    fn test_fingerprint() {
+        let mut pr = Pagerank::<String>::new();
+        pr.add_edge("a".to_string(), "b".to_string());
+        pr.add_edge("b".to_string(), "c".to_string());
+        pr.add_edge_weighted("c".to_string(), "a".to_string(), 2.0)
+            .unwrap();
+
+        let mut same = Pagerank::<String>::new();
+        same.insert_node("c".to_string());
+        same.add_edge_weighted("c".to_string(), "a".to_string(), 2.0)
+            .unwrap();
+        same.add_edge("b".to_string(), "c".to_string());
+        same.add_edge("a".to_string(), "b".to_string());
+        assert_eq!(pr.fingerprint(), same.fingerprint());
+
+        same.calculate();
+        same.set_damping_factor(50).unwrap();
+        assert_eq!(pr.fingerprint(), same.fingerprint());
+
+        let before = pr.fingerprint();
+        pr.add_edge("a".to_string(), "b".to_string());
+        assert_ne!(before, pr.fingerprint());
+        assert!(pr.remove_edge(&"a".to_string(), &"b".to_string()));
+        assert_eq!(before, pr.fingerprint());
+
+        pr.insert_node("d".to_string());
+        assert_ne!(before, pr.fingerprint());
+
+        let mut weighted = Pagerank::<String>::new();
+        weighted.add_edge("a".to_string(), "b".to_string());
+        weighted.add_edge("b".to_string(), "c".to_string());
+        weighted
+            .add_edge_weighted("c".to_string(), "a".to_string(), 3.0)
+            .unwrap();
+        assert_ne!(same.fingerprint(), weighted.fingerprint());
+
+        // Fixed value, to catch changes across platforms and versions
+        let mut fixed = Pagerank::<u32>::new();
+        fixed.add_edge(1, 2);
+        assert_eq!(
+            97922704335272773938604276127890736387,
+            fixed.fingerprint()
+        );
+    }
+}
@@ -0,0 +1,484 @@
+//! Compact in edges layout used while iterating
+#[cfg(not(feature = "std"))]
+use crate::compat::prelude::*;
+use crate::solver::ScoreValue;
+use crate::Pagerank;
+use alloc::borrow::Cow;
+use core::hash::{BuildHasher, Hash};
+
+/// In edges of every node in compressed sparse row (CSR) form, with the
+/// share of the source score each edge carries. Nodes in the cold tier
+/// keep theirs compressed in `cold` instead.
+///
+/// While it exists it owns the in edges: the `Node::in_edges` lists are
+/// empty, and are rebuilt from it by `Pagerank::thaw`.
+pub(crate) struct Frozen {
+    /// In edges of node `i` are at `offsets[i]..offsets[i + 1]`
+    offsets: Vec<usize>,
+    /// Source of each in edge
+    sources: Vec<usize>,
+    /// Share of the source score carried by each in edge
+    shares: Vec<f64>,
+    /// Weight of the stored in edges of each node, see
+    /// `Node::in_edges_weight`
+    scales: Vec<f64>,
+    /// In edges of the nodes in the cold tier, if tiered
+    cold: Option<ColdTier>,
+}
+
+/// In edges of the nodes with few of them, built by
+/// `Pagerank::freeze_tiered`. Each node sends the same share of its
+/// score to every one of these edges, so only their sources are kept,
+/// as varints of the zigzag encoded difference from the previous source
+/// (the target for the first one).
+struct ColdTier {
+    /// In edges of node `i` are at `offsets[i]..offsets[i + 1]`
+    offsets: Vec<usize>,
+    /// Encoded sources of the in edges
+    sources: Vec<u8>,
+    /// Share of its score each node sends to each of its out edges
+    shares: Vec<f64>,
+}
+
+impl Frozen {
+    /// Stored in edges of node `id`, in the order they were stored
+    pub(crate) fn in_edges(&self, id: usize) -> Cow<'_, [usize]> {
+        if let Some(cold) = self.cold.as_ref() {
+            let mut bytes =
+                &cold.sources[cold.offsets[id]..cold.offsets[id + 1]];
+            if !bytes.is_empty() {
+                let mut sources = Vec::new();
+                let mut source = id as i64;
+                while !bytes.is_empty() {
+                    source += read_zigzag(&mut bytes);
+                    sources.push(source as usize);
+                }
+                return Cow::Owned(sources);
+            }
+        }
+        Cow::Borrowed(
+            &self.sources[self.offsets[id]..self.offsets[id + 1]],
+        )
+    }
+
+    /// Number of stored in edges of node `id`
+    fn len_in_edges(&self, id: usize) -> usize {
+        let cold = self.cold.as_ref().map_or(0, |cold| {
+            cold.sources[cold.offsets[id]..cold.offsets[id + 1]]
+                .iter()
+                .filter(|byte| *byte & 0x80 == 0)
+                .count()
+        });
+        self.offsets[id + 1] - self.offsets[id] + cold
+    }
+}
+
+impl<T, S> Pagerank<T, S>
+where
+    T: Eq + Hash + Clone,
+    S: BuildHasher,
+{
+    /// Moves the in edges of every node into flat arrays, so the
+    /// following calculations read them sequentially instead of chasing
+    /// one list per node, which is much faster on large graphs. The per
+    /// node lists are released, so edges are not stored twice: each
+    /// stored edge takes 16 bytes, its source and its share of the
+    /// source score, instead of the 8 of the per node lists.
+    ///
+    /// The graph can still be queried and changed: the first change of
+    /// its edges or nodes rebuilds the per node lists from the flat
+    /// arrays, which are then dropped, and the calculations go back to
+    /// the per node lists until `freeze` is called again. Edge weights
+    /// are computed here if needed. Scores are the same either way.
+    pub fn freeze(&mut self) {
+        if self.frozen.is_none() {
+            self.build_frozen(0);
+        }
+    }
+
+    /// Like `freeze`, but only nodes with at least `hot_degree` stored
+    /// in edges get the flat arrays, as the hot tier. The in edges of
+    /// the rest, the cold tier, are compressed to a byte or two each
+    /// instead of the 16 bytes of the hot tier, and decoded on every
+    /// iteration.
+    ///
+    /// As with `freeze`, the layout replaces the per node lists, which
+    /// take 8 bytes per stored edge, until the graph changes. It takes 32
+    /// bytes per node, plus 16 per hot edge and a byte or two per cold
+    /// one, so once nodes average more than about five stored in edges,
+    /// most of them cold, the graph takes less memory frozen than not, at
+    /// the cost of a slower iteration over the cold tier. Most nodes of
+    /// real graphs have few in edges, so most edges usually are. Edges
+    /// which are weighted, or which come from a node whose out edges are,
+    /// stay in the hot tier, so scores are exactly the same as with
+    /// `freeze`.
+    pub fn freeze_tiered(&mut self, hot_degree: usize) {
+        self.build_frozen(hot_degree);
+    }
+
+    /// Returns the bytes taken by the layout built by `freeze` or
+    /// `freeze_tiered`, if any, which holds the only copy of the stored
+    /// edges while the graph is frozen
+    pub fn frozen_size(&self) -> Option<usize> {
+        self.frozen.as_ref().map(|frozen| {
+            let mut words = frozen.offsets.len()
+                + frozen.sources.len()
+                + frozen.shares.len()
+                + frozen.scales.len();
+            let mut bytes = 0;
+            if let Some(cold) = frozen.cold.as_ref() {
+                words += cold.offsets.len() + cold.shares.len();
+                bytes += cold.sources.len();
+            }
+            words * 8 + bytes
+        })
+    }
+
+    /// Builds the layout, with nodes with fewer than `hot_degree` stored
+    /// in edges in the cold tier when they can be
+    fn build_frozen(&mut self, hot_degree: usize) {
+        self.thaw();
+        self.finalize_weights();
+
+        let len = self.nodes.len();
+        let edges = self.nodes.iter().map(|n| n.in_edges.len()).sum();
+        let mut offsets = Vec::with_capacity(len + 1);
+        let mut sources = Vec::with_capacity(edges);
+        let mut shares = Vec::with_capacity(edges);
+        let mut scales = Vec::with_capacity(len);
+
+        let mut cold = if hot_degree > 0 {
+            Some(ColdTier {
+                offsets: Vec::with_capacity(len + 1),
+                sources: Vec::new(),
+                shares: (0..len)
+                    .map(|id| self.out_share(id))
+                    .collect(),
+            })
+        } else {
+            None
+        };
+
+        offsets.push(0);
+        if let Some(cold) = cold.as_mut() {
+            cold.offsets.push(0);
+        }
+        for (id, n) in self.nodes.iter().enumerate() {
+            let in_cold = match cold.as_ref() {
+                Some(cold) => {
+                    n.in_edges.len() < hot_degree
+                        && n.in_edges.iter().enumerate().all(
+                            |(slot, source)| {
+                                self.edge_share(*source, id, slot)
+                                    == cold.shares[*source]
+                            },
+                        )
+                }
+                None => false,
+            };
+
+            if in_cold {
+                let cold = cold.as_mut().unwrap();
+                let mut previous = id;
+                for source in n.in_edges.iter() {
+                    let delta = *source as i64 - previous as i64;
+                    write_zigzag(&mut cold.sources, delta);
+                    previous = *source;
+                }
+            } else {
+                for (slot, source) in n.in_edges.iter().enumerate() {
+                    sources.push(*source);
+                    shares.push(self.edge_share(*source, id, slot));
+                }
+            }
+            offsets.push(sources.len());
+            if let Some(cold) = cold.as_mut() {
+                cold.offsets.push(cold.sources.len());
+            }
+            scales.push(n.in_edges_weight());
+        }
+
+        sources.shrink_to_fit();
+        shares.shrink_to_fit();
+        for n in self.nodes.iter_mut() {
+            n.in_edges = Vec::new();
+        }
+        self.frozen = Some(Frozen {
+            offsets,
+            sources,
+            shares,
+            scales,
+            cold,
+        });
+    }
+
+    /// Share of its score node `id` sends to an out edge of weight 1,
+    /// matching `edge_share`
+    fn out_share(&self, id: usize) -> f64 {
+        match &self.edge_weights {
+            Some(weights) if weights.out_totals[id] > 0f64 => {
+                1f64 / weights.out_totals[id]
+            }
+            Some(_) => 0f64,
+            None => 1f64 / self.nodes[id].out_weight,
+        }
+    }
+
+    /// Whether the flat arrays built by `freeze` are in use
+    pub fn is_frozen(&self) -> bool {
+        self.frozen.is_some()
+    }
+
+    /// Drops the layout built by `freeze`, if any, moving the in edges
+    /// back into the per node lists. Called before the edges or nodes
+    /// change.
+    pub(crate) fn thaw(&mut self) {
+        let frozen = match self.frozen.take() {
+            Some(frozen) => frozen,
+            None => return,
+        };
+        for (id, n) in self.nodes.iter_mut().enumerate() {
+            n.in_edges = frozen.in_edges(id).into_owned();
+        }
+    }
+
+    /// Stored in edges of node `id`, in the order they were stored,
+    /// wherever they are kept
+    pub(crate) fn in_edges(&self, id: usize) -> Cow<'_, [usize]> {
+        match &self.frozen {
+            Some(frozen) => frozen.in_edges(id),
+            None => Cow::Borrowed(&self.nodes[id].in_edges),
+        }
+    }
+
+    /// Number of stored in edges of node `id`
+    pub(crate) fn len_in_edges(&self, id: usize) -> usize {
+        match &self.frozen {
+            Some(frozen) => frozen.len_in_edges(id),
+            None => self.nodes[id].in_edges.len(),
+        }
+    }
+
+    /// Weight of each stored in edge of node `id`, see
+    /// `Node::in_edges_weight`
+    pub(crate) fn in_edges_weight(&self, id: usize) -> f64 {
+        match &self.frozen {
+            Some(frozen) => frozen.scales[id],
+            None => self.nodes[id].in_edges_weight(),
+        }
+    }
+
+    /// Weight of each stored in edge of node `id` once the nodes flagged
+    /// in `removed`, if any, are removed with their edges
+    pub(crate) fn in_edges_weight_without(
+        &self,
+        id: usize,
+        removed: &[bool],
+    ) -> f64 {
+        if removed.is_empty() {
+            return self.in_edges_weight(id);
+        }
+        let sources = self.in_edges(id);
+        let gone = sources.iter().filter(|s| removed[**s]).count();
+        if gone == sources.len() {
+            1f64
+        } else {
+            (self.nodes[id].in_degree - gone) as f64
+                / (sources.len() - gone) as f64
+        }
+    }
+
+    /// Score flowing into node `id` through its in edges, given the
+    /// score of every node, before damping
+    pub(crate) fn incoming<F: ScoreValue>(
+        &self,
+        id: usize,
+        scores: &[F],
+    ) -> f64 {
+        match &self.frozen {
+            Some(frozen) => {
+                let edges =
+                    frozen.offsets[id]..frozen.offsets[id + 1];
+                let mut incoming = frozen.sources[edges.clone()]
+                    .iter()
+                    .zip(frozen.shares[edges].iter())
+                    .map(|(source, share)| {
+                        scores[*source].into() * share
+                    })
+                    .sum::<f64>();
+                if let Some(cold) = frozen.cold.as_ref() {
+                    let mut bytes = &cold.sources
+                        [cold.offsets[id]..cold.offsets[id + 1]];
+                    let mut source = id as i64;
+                    while !bytes.is_empty() {
+                        source += read_zigzag(&mut bytes);
+                        let source = source as usize;
+                        incoming += scores[source].into()
+                            * cold.shares[source];
+                    }
+                }
+                incoming * frozen.scales[id]
+            }
+            None => {
+                let n = &self.nodes[id];
+                n.in_edges
+                    .iter()
+                    .enumerate()
+                    .map(|(slot, source)| {
+                        scores[*source].into()
+                            * self.edge_share(*source, id, slot)
+                    })
+                    .sum::<f64>()
+                    * n.in_edges_weight()
+            }
+        }
+    }
+}
+
+/// Appends `value` zigzag encoded as a varint
+pub(crate) fn write_zigzag(buf: &mut Vec<u8>, value: i64) {
+    let mut value = ((value << 1) ^ (value >> 63)) as u64;
+    while value >= 0x80 {
+        buf.push(value as u8 | 0x80);
+        value >>= 7;
+    }
+    buf.push(value as u8);
+}
+
+/// Reads a value written by `write_zigzag` from the start of `buf`,
+/// advancing it
+#[inline]
+fn read_zigzag(buf: &mut &[u8]) -> i64 {
+    let mut value = 0u64;
+    let mut shift = 0;
+    loop {
+        let byte = buf[0];
+        *buf = &buf[1..];
+        value |= ((byte & 0x7f) as u64) << shift;
+        if byte & 0x80 == 0 {
+            break;
+        }
+        shift += 7;
+    }
+    (value >> 1) as i64 ^ -((value & 1) as i64)
+}
+
+#[cfg(all(test, feature = "generators"))]
+mod tests {
+    use crate::generators;
+    use crate::Pagerank;
+
+    fn graph() -> Pagerank<usize> {
+        let mut pr = generators::watts_strogatz(200, 6, 0.2, 7);
+        pr.add_edge_weighted(3, 5, 2.5).unwrap();
+        pr.set_in_edges_cap(3);
+        pr.add_edge(9, 0);
+        pr
+    }
+
+    #[test]
+    fn test_freeze() {
+        let mut pr = graph();
+        let mut frozen = graph();
+
+        frozen.freeze();
+        assert!(frozen.is_frozen());
+        pr.calculate_with_convergence(1e-9);
+        frozen.calculate_with_convergence(1e-9);
+        assert_eq!(pr.nodes(), frozen.nodes());
+
+        // The flat arrays are the only copy of the edges
+        assert!(frozen
+            .nodes
+            .iter()
+            .all(|n| n.in_edges.capacity() == 0));
+        #[cfg(feature = "analytics")]
+        assert_eq!(
+            pr.similar_by_cocitation(7, 5),
+            frozen.similar_by_cocitation(7, 5)
+        );
+        assert_eq!(
+            pr.view().in_neighbors(7),
+            frozen.view().in_neighbors(7)
+        );
+
+        frozen.add_edge(0, 1);
+        assert!(!frozen.is_frozen());
+        pr.add_edge(0, 1);
+        for id in 0..pr.len() {
+            assert_eq!(
+                pr.nodes[id].in_edges,
+                frozen.nodes[id].in_edges
+            );
+        }
+        frozen.freeze();
+        frozen.insert_node(1000);
+        assert!(!frozen.is_frozen());
+    }
+
+    #[test]
+    fn test_freeze_tiered() {
+        let mut pr = graph();
+        let mut tiered = graph();
+        pr.freeze();
+        tiered.freeze_tiered(7);
+        assert!(tiered.is_frozen());
+        assert!(tiered.frozen_size() < pr.frozen_size());
+
+        pr.calculate_with_convergence(1e-9);
+        tiered.calculate_with_convergence(1e-9);
+        assert_eq!(pr.nodes(), tiered.nodes());
+        #[cfg(feature = "analytics")]
+        assert_eq!(
+            pr.similar_by_cocitation(7, 5),
+            tiered.similar_by_cocitation(7, 5)
+        );
+        for id in 0..pr.len() {
+            assert_eq!(
+                pr.view().in_neighbors(id),
+                tiered.view().in_neighbors(id)
+            );
+        }
+
+        // Changing the graph rebuilds the per node lists
+        let mut unfrozen = graph();
+        tiered.remove_node(&5);
+        unfrozen.remove_node(&5);
+        for id in 0..unfrozen.len() {
+            assert_eq!(
+                unfrozen.nodes[id].in_edges,
+                tiered.nodes[id].in_edges
+            );
+        }
+
+        // Every node in the cold tier, with far apart sources
+        let mut pr = graph();
+        pr.add_edge(199, 0);
+        pr.add_edge(0, 100_000);
+        let mut tiered = graph();
+        tiered.add_edge(199, 0);
+        tiered.add_edge(0, 100_000);
+        pr.freeze();
+        tiered.freeze_tiered(usize::MAX);
+        pr.calculate_with_convergence(1e-9);
+        tiered.calculate_with_convergence(1e-9);
+        assert_eq!(pr.nodes(), tiered.nodes());
+        assert_eq!(None, Pagerank::<u8>::new().frozen_size());
+    }
+
+    #[test]
+    fn test_freeze_tiered_memory() {
+        let mut pr = generators::watts_strogatz(500, 16, 0.2, 7);
+        let lists = pr
+            .nodes
+            .iter()
+            .map(|n| n.in_edges.capacity() * 8)
+            .sum::<usize>();
+
+        pr.freeze();
+        assert!(pr.frozen_size().unwrap() > lists);
+        pr.freeze_tiered(64);
+        assert!(pr.frozen_size().unwrap() < lists / 2);
+        assert!(pr.nodes.iter().all(|n| n.in_edges.capacity() == 0));
+    }
+}
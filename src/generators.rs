@@ -0,0 +1,192 @@
+//! Synthetic graphs with known PageRank scores.
+//!
+//! The symmetric graphs (ring, complete graph and star) have closed form
+//! scores, given by the `expected_*` functions indexed by node, which are
+//! used to check the solver for correctness. The expected scores assume
+//! no personalization, so every node teleports `1 - d`.
+#[cfg(not(feature = "std"))]
+use crate::compat::prelude::*;
+use crate::compat::HashSet;
+use crate::graph::next_random;
+use crate::Pagerank;
+
+/// A directed cycle `0 -> 1 -> ... -> n - 1 -> 0`
+pub fn ring(n: usize) -> Pagerank<usize> {
+    let mut pr = Pagerank::new();
+    for i in 0..n {
+        pr.add_edge(i, (i + 1) % n);
+    }
+    pr
+}
+
+/// A graph with an edge from every node to every other node
+pub fn complete(n: usize) -> Pagerank<usize> {
+    let mut pr = Pagerank::new();
+    for i in 0..n {
+        for j in 0..n {
+            if i != j {
+                pr.add_edge(i, j);
+            }
+        }
+    }
+    pr
+}
+
+/// Node `0` linked in both directions with each of the `leaves` nodes
+/// `1..=leaves`
+pub fn star(leaves: usize) -> Pagerank<usize> {
+    let mut pr = Pagerank::new();
+    for leaf in 1..=leaves {
+        pr.add_edge(0, leaf);
+        pr.add_edge(leaf, 0);
+    }
+    pr
+}
+
+/// A Watts-Strogatz small-world graph.
+///
+/// Starts from a ring lattice of `n` nodes, each linked in both
+/// directions with its `k / 2` nearest neighbors on each side. Then each
+/// link is rewired with probability `beta` to a random node, avoiding
+/// self loops and duplicated links. The same `seed` always yields the
+/// same graph.
+pub fn watts_strogatz(
+    n: usize,
+    k: usize,
+    beta: f64,
+    seed: u64,
+) -> Pagerank<usize> {
+    let mut rng = seed ^ 0x9e3779b97f4a7c15;
+    if rng == 0 {
+        rng = 0x9e3779b97f4a7c15;
+    }
+
+    let mut links = HashSet::new();
+    for i in 0..n {
+        for j in 1..=k / 2 {
+            let target = (i + j) % n;
+            if target != i {
+                links.insert((i.min(target), i.max(target)));
+            }
+        }
+    }
+
+    let mut lattice = links.iter().cloned().collect::<Vec<_>>();
+    lattice.sort_unstable();
+    for (a, b) in lattice {
+        let draw = (next_random(&mut rng) >> 11) as f64
+            / (1u64 << 53) as f64;
+        if draw >= beta {
+            continue;
+        }
+
+        let free = (0..n)
+            .filter(|t| {
+                *t != a && !links.contains(&(a.min(*t), a.max(*t)))
+            })
+            .collect::<Vec<usize>>();
+        if free.is_empty() {
+            continue;
+        }
+
+        let target = free
+            [(next_random(&mut rng) % free.len() as u64) as usize];
+        links.remove(&(a, b));
+        links.insert((a.min(target), a.max(target)));
+    }
+
+    let mut links = links.into_iter().collect::<Vec<_>>();
+    links.sort_unstable();
+
+    let mut pr = Pagerank::new();
+    for i in 0..n {
+        pr.insert_node(i);
+    }
+    for (a, b) in links {
+        pr.add_edge(a, b);
+        pr.add_edge(b, a);
+    }
+    pr
+}
+
+/// A fixed, seeded graph mixing a small world with a few hubs, whose
+/// scores are checked against a golden file with
+/// `Pagerank::check_golden`. It never changes between versions.
+pub fn golden() -> Pagerank<usize> {
+    let mut pr = watts_strogatz(40, 4, 0.2, 7);
+    for i in (0..40).step_by(3) {
+        pr.add_edge(i, 40);
+    }
+    for i in (0..40).step_by(7) {
+        pr.add_edge(40, i);
+        pr.add_edge(i, 41);
+    }
+    pr
+}
+
+/// Expected scores of a `ring`. Every node gets as much as it gives, so
+/// it scores 1 whatever the damping factor.
+pub fn expected_ring(n: usize) -> Vec<f64> {
+    vec![1.0; n]
+}
+
+/// Expected scores of a `complete` graph, 1 for every node
+pub fn expected_complete(n: usize) -> Vec<f64> {
+    vec![1.0; n]
+}
+
+/// Expected scores of a `star`, solving `c = (1 - d) + d * leaves * l`
+/// for the center and `l = (1 - d) + d * c / leaves` for each leaf
+pub fn expected_star(leaves: usize, damping: f64) -> Vec<f64> {
+    let d = damping;
+    let center = (1.0 + d * leaves as f64) / (1.0 + d);
+    let leaf = (1.0 - d) + d * center / leaves as f64;
+
+    let mut expected = vec![leaf; leaves + 1];
+    expected[0] = center;
+    expected
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn max_error(pr: &Pagerank<usize>, expected: &[f64]) -> f64 {
+        pr.iter_nodes()
+            .map(|n| (n.score - expected[*n.key]).abs())
+            .fold(0f64, f64::max)
+    }
+
+    #[test]
+    fn test_symmetric_graphs() {
+        let mut pr = ring(7);
+        pr.calculate_with_convergence(1e-12);
+        assert!(max_error(&pr, &expected_ring(7)) < 1e-9);
+
+        let mut pr = complete(5);
+        pr.set_damping_factor(50).unwrap();
+        pr.calculate_with_convergence(1e-12);
+        assert!(max_error(&pr, &expected_complete(5)) < 1e-9);
+
+        let mut pr = star(4);
+        pr.calculate_with_convergence(1e-12);
+        assert!(max_error(&pr, &expected_star(4, 0.85)) < 1e-9);
+    }
+
+    #[test]
+    fn test_watts_strogatz() {
+        let mut lattice = watts_strogatz(10, 4, 0.0, 1);
+        assert_eq!(10, lattice.len());
+        assert_eq!(40, lattice.len_node());
+        lattice.calculate_with_convergence(1e-12);
+        assert!(max_error(&lattice, &expected_ring(10)) < 1e-9);
+
+        let rewired = watts_strogatz(10, 4, 0.5, 1);
+        assert_eq!(40, rewired.len_node());
+        assert_eq!(
+            rewired.degrees(),
+            watts_strogatz(10, 4, 0.5, 1).degrees()
+        );
+        assert_ne!(rewired.degrees(), lattice.degrees());
+    }
+}
@@ -0,0 +1,196 @@
+//! Golden scores, to detect behavioral changes between versions
+use crate::Pagerank;
+use std::error::Error;
+use std::fmt::{self, Display};
+use std::hash::{BuildHasher, Hash};
+use std::io::{self, BufRead, Write};
+use std::str::FromStr;
+
+/// Why scores do not match a golden file
+#[derive(Debug)]
+pub enum GoldenError {
+    /// The golden file could not be read
+    Io(io::Error),
+    /// A line of the golden file is malformed
+    Malformed {
+        /// Line number, starting at 1
+        line: usize,
+    },
+    /// The score of a node differs by more than the tolerance, or the
+    /// node is not part of the graph
+    Mismatch {
+        /// The node, as written in the golden file
+        node: String,
+        /// Golden score
+        expected: f64,
+        /// Current score, `None` if the node is missing
+        actual: Option<f64>,
+    },
+    /// The graph and the golden file have a different number of nodes
+    NodeCount {
+        /// Nodes in the golden file
+        expected: usize,
+        /// Nodes in the graph
+        actual: usize,
+    },
+}
+
+impl fmt::Display for GoldenError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            GoldenError::Io(err) => write!(f, "{}", err),
+            GoldenError::Malformed { line } => {
+                write!(f, "malformed golden line {}", line)
+            }
+            GoldenError::Mismatch {
+                node,
+                expected,
+                actual: Some(actual),
+            } => write!(
+                f,
+                "score of {} is {}, expected {}",
+                node, actual, expected
+            ),
+            GoldenError::Mismatch { node, .. } => {
+                write!(f, "node {} is missing", node)
+            }
+            GoldenError::NodeCount { expected, actual } => write!(
+                f,
+                "graph has {} nodes, expected {}",
+                actual, expected
+            ),
+        }
+    }
+}
+
+impl Error for GoldenError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            GoldenError::Io(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for GoldenError {
+    fn from(err: io::Error) -> Self {
+        GoldenError::Io(err)
+    }
+}
+
+impl<T, S> Pagerank<T, S>
+where
+    T: Eq + Hash + Clone + Display + FromStr,
+    S: BuildHasher,
+{
+    /// Writes the current scores as a golden file, one `node\tscore`
+    /// line per node in insertion order. Scores are written with full
+    /// precision so they are read back exactly.
+    pub fn write_golden<W: Write>(
+        &self,
+        mut writer: W,
+    ) -> io::Result<()> {
+        for n in self.nodes.iter() {
+            writeln!(writer, "{}\t{:?}", n.node, n.score)?;
+        }
+        Ok(())
+    }
+
+    /// Compares the current scores against a golden file written by
+    /// `write_golden`, failing on the first node whose score differs by
+    /// more than `tolerance`.
+    ///
+    /// Meant for the CI of users upgrading the crate: compute the scores
+    /// of a fixed graph, such as `generators::golden`, and check them
+    /// against a golden file committed with an earlier version.
+    pub fn check_golden<R: BufRead>(
+        &self,
+        reader: R,
+        tolerance: f64,
+    ) -> Result<(), GoldenError> {
+        let mut nodes = 0;
+        for (i, line) in reader.lines().enumerate() {
+            let line = line?;
+            if line.is_empty() {
+                continue;
+            }
+
+            let malformed = GoldenError::Malformed { line: i + 1 };
+            let mut columns = line.splitn(2, '\t');
+            let key = columns.next().unwrap_or_default();
+            let node = key.parse::<T>().map_err(|_| malformed)?;
+            let expected = columns
+                .next()
+                .and_then(|score| score.parse::<f64>().ok())
+                .ok_or(GoldenError::Malformed { line: i + 1 })?;
+            nodes += 1;
+
+            let actual = self
+                .node_positions
+                .get(&node)
+                .map(|id| self.nodes[*id].score);
+            let matches = actual
+                .is_some_and(|a| (a - expected).abs() <= tolerance);
+            if !matches {
+                return Err(GoldenError::Mismatch {
+                    node: key.to_string(),
+                    expected,
+                    actual,
+                });
+            }
+        }
+
+        if nodes != self.nodes.len() {
+            return Err(GoldenError::NodeCount {
+                expected: nodes,
+                actual: self.nodes.len(),
+            });
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Scores of `generators::golden` with the default settings. Update
+    /// only on purpose, when the scores are meant to change.
+    #[cfg(feature = "generators")]
+    const GOLDEN: &str = include_str!("../golden/reference.tsv");
+
+    #[cfg(feature = "generators")]
+    #[test]
+    fn test_golden_scores() {
+        let mut pr = crate::generators::golden();
+        pr.calculate();
+
+        if let Err(err) = pr.check_golden(GOLDEN.as_bytes(), 1e-9) {
+            panic!("{}", err);
+        }
+    }
+
+    #[test]
+    fn test_golden_mismatch() {
+        let mut pr = Pagerank::<u32>::new();
+        pr.add_edge(1, 2);
+        pr.calculate();
+
+        let mut golden = Vec::new();
+        pr.write_golden(&mut golden).unwrap();
+        assert!(pr.check_golden(&golden[..], 0.0).is_ok());
+
+        pr.add_edge(2, 1);
+        pr.calculate();
+        match pr.check_golden(&golden[..], 1e-6) {
+            Err(GoldenError::Mismatch { node, .. }) => {
+                assert_eq!("1", node)
+            }
+            other => panic!("unexpected {:?}", other),
+        }
+
+        pr.add_edge(2, 3);
+        assert!(pr.check_golden(&b"1\tx\n"[..], 1.0).is_err());
+    }
+}
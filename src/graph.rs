@@ -0,0 +1,1401 @@
+//! Graph storage and queries
+use crate::batch::{Batch, StoredEdge};
+#[cfg(not(feature = "std"))]
+use crate::compat::prelude::*;
+use crate::compat::RandomState;
+use crate::compat::{HashMap, HashSet};
+use crate::components::Components;
+use crate::dangling::DanglingPolicy;
+use crate::decay::StaleDecay;
+use crate::frozen::Frozen;
+use crate::solver::{
+    ConvergenceNorm, Precision, RankResult, DEFAULT_CONVERGENCE,
+};
+use crate::sweep::Sweep;
+use crate::transform::ScoreTransform;
+use crate::unknown::UnknownScore;
+use crate::weights::{EdgeWeights, GroupFn, WeightFn};
+use core::cmp::Ordering;
+use core::default::Default;
+use core::error::Error;
+use core::fmt;
+use core::hash::{BuildHasher, Hash};
+use core::iter::FromIterator;
+
+#[derive(Clone)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize)
+)]
+#[cfg_attr(
+    feature = "serde",
+    serde(bound(
+        serialize = "T: serde::Serialize",
+        deserialize = "T: serde::Deserialize<'de>"
+    ))
+)]
+pub(crate) struct Node<T>
+where
+    T: Eq + Hash + Clone,
+{
+    /// Edge type
+    pub(crate) node: T,
+    /// List of edges (the ids which are edges in `nodes`)
+    pub(crate) in_edges: Vec<usize>,
+    /// Number of in edges, which may be larger than `in_edges` when the
+    /// in edges are capped
+    pub(crate) in_degree: usize,
+    /// Number of out edges
+    pub(crate) out_edges: usize,
+    /// Weight of each stored in edge, parallel to `in_edges`. Empty while
+    /// every in edge weighs 1.
+    pub(crate) in_weights: Vec<f64>,
+    /// Sum of the weights of the out edges
+    pub(crate) out_weight: f64,
+    pub(crate) score: f64,
+}
+
+impl<T> Node<T>
+where
+    T: Eq + Hash + Clone,
+{
+    /// Weight of each stored in edge. It is 1 unless the in edges were
+    /// sampled because the node reached the in edges cap.
+    pub(crate) fn in_edges_weight(&self) -> f64 {
+        if self.in_edges.is_empty() {
+            1f64
+        } else {
+            self.in_degree as f64 / self.in_edges.len() as f64
+        }
+    }
+
+    /// Weight of the in edge stored at `slot`
+    pub(crate) fn edge_weight(&self, slot: usize) -> f64 {
+        self.in_weights.get(slot).cloned().unwrap_or(1f64)
+    }
+
+    /// Sets the weight of the in edge stored at `slot`, keeping
+    /// `in_weights` empty as long as every weight is 1
+    pub(crate) fn set_edge_weight(
+        &mut self,
+        slot: usize,
+        weight: f64,
+    ) {
+        if self.in_weights.is_empty() && weight != 1f64 {
+            self.in_weights = vec![1f64; self.in_edges.len()];
+        }
+        if let Some(w) = self.in_weights.get_mut(slot) {
+            *w = weight;
+        }
+    }
+}
+
+/// PageRank structure.
+///
+/// The structure is `Send` and `Sync` whenever `T` is, so a calculated
+/// graph can be shared (e.g. behind an `Arc`) by many query threads.
+///
+/// Nodes are looked up by key through a `HashMap` hashing with `S`,
+/// `RandomState` unless the graph is created with `with_hasher`.
+///
+/// With the `serde` feature, graphs implement `Serialize` and
+/// `Deserialize` with their nodes, edges, scores and settings. The
+/// weight, affinity and group closures and the score transform can't be
+/// serialized, so they must be set again after deserializing; an open
+/// batch is not kept either, and caches are rebuilt when needed.
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize)
+)]
+#[cfg_attr(
+    feature = "serde",
+    serde(bound(
+        serialize = "T: serde::Serialize, S: BuildHasher",
+        deserialize = "T: serde::Deserialize<'de>, \
+                       S: BuildHasher + Default"
+    ))
+)]
+pub struct Pagerank<T, S = RandomState>
+where
+    T: Eq + Hash + Clone,
+{
+    /// Damping factor
+    ///
+    /// The PageRank theory holds that an imaginary surfer who is randomly clicking on edges will
+    /// eventually stop clicking. The probability, at any step, that the person will continue is a
+    /// damping factor d. Various studies have tested different damping factors, but it is generally
+    /// assumed that the damping factor will be set around 0.85.
+    pub(crate) damping: f64,
+    /// List of nodes. Each node is uniquely identified by their type T.
+    pub(crate) nodes: Vec<Node<T>>,
+    /// Total number of edges. It is a `u64` so it has the same range on
+    /// every platform, 32 bits ones included.
+    pub(crate) edges: u64,
+    /// Keeps track of nodes and their position in the nodes vector.
+    pub(crate) node_positions: HashMap<T, usize, S>,
+    /// Sparse personalization vector, as node ids and their share of the
+    /// teleport probability. Empty when teleporting is uniform.
+    pub(crate) personalization: Vec<(usize, f64)>,
+    /// Scores fixed while iterating, as node ids, sorted, and their
+    /// score
+    pub(crate) pinned: Vec<(usize, f64)>,
+    /// Type of each node, by id. Nodes past its end are of type 0.
+    pub(crate) node_types: Vec<u16>,
+    /// Share of the teleport probability of each node type. Empty when
+    /// teleporting ignores types.
+    pub(crate) teleport_shares: Vec<(u16, f64)>,
+    /// Maximum number of in edges stored per node. Once reached, in edges
+    /// are sampled and the stored ones weighted to preserve the count.
+    pub(crate) in_edges_cap: Option<usize>,
+    /// State of the random generator used for sampling in edges
+    pub(crate) rng: u64,
+    /// Every edge in insertion order, as source, target, sequence number
+    /// and weight, when recording it was enabled
+    pub(crate) edge_log: Option<Vec<(usize, usize, u64, f64)>>,
+    /// Source and target ids of every edge, when adding an edge which
+    /// is already in the graph is ignored
+    pub(crate) unique_edges: Option<HashSet<(usize, usize)>>,
+    /// Journal of the open batch, if any
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub(crate) batch: Option<Batch>,
+    /// Decay of the nodes without new in edges between runs, if enabled
+    pub(crate) stale_decay: Option<StaleDecay>,
+    /// Closure giving the weight of each edge, if edges are weighted
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub(crate) weight_fn: Option<WeightFn<T>>,
+    /// Closure giving the affinity of the endpoints of each edge, which
+    /// multiplies its weight
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub(crate) affinity_fn: Option<WeightFn<T>>,
+    /// Closure giving the group of every node, whose links to the same
+    /// target are discounted
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub(crate) group_fn: Option<GroupFn<T>>,
+    /// Edge weights computed from `weight_fn`. Reset whenever edges
+    /// change, and computed again before the next calculation.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub(crate) edge_weights: Option<EdgeWeights>,
+    /// Flat copy of the in edges built by `freeze`. Dropped whenever
+    /// edges or nodes change.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub(crate) frozen: Option<Frozen>,
+    /// Weakly connected components, built on the first query and kept
+    /// up to date as edges are pushed. Dropped whenever edges or nodes
+    /// are removed.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub(crate) components: Option<Components>,
+    /// Iteration interrupted by `calculate_bounded`, if any. Dropped
+    /// whenever edges, nodes or weights change.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub(crate) sweep: Option<Sweep>,
+    /// Precision of the scores while iterating
+    pub(crate) precision: Precision,
+    /// How the change of the scores between iterations is measured
+    pub(crate) convergence_norm: ConvergenceNorm,
+    /// What happens to the score of nodes without out edges
+    pub(crate) dangling_policy: DanglingPolicy,
+    /// Convergence used by `calculate`
+    pub(crate) convergence: f64,
+    /// Iteration limit used by `calculate`, if any
+    pub(crate) max_iterations: Option<usize>,
+    /// Score of nodes not added yet, which they start with instead of
+    /// `1 - d`. Each is removed once its node is added.
+    pub(crate) pending_scores: HashMap<T, f64>,
+    /// Transform applied to the scores handed out by queries
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub(crate) score_transform: Option<ScoreTransform>,
+    /// What `get_score` returns for nodes which are not in the graph
+    pub(crate) unknown_score: UnknownScore,
+    /// Outcome of the last full calculation
+    pub(crate) last_result: Option<RankResult>,
+    /// Memory budget in bytes, if any
+    pub(crate) max_memory: Option<usize>,
+    /// Number of nodes removed to honor the memory budget
+    pub(crate) evicted_nodes: u64,
+    /// Cache to keep the count of total nodes with incoming edges. This cache gets reset everytime
+    /// a new node is being added to the graph.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub(crate) nodes_with_in_edges: Option<usize>,
+}
+
+impl<T> Pagerank<T>
+where
+    T: Eq + Hash + Clone,
+{
+    /// Creates a new instance
+    pub fn new() -> Pagerank<T> {
+        Pagerank::with_hasher(RandomState::default())
+    }
+
+    /// Creates a new instance with room for `nodes` nodes, so building a
+    /// graph whose size is known upfront doesn't grow the node storage
+    /// again and again
+    pub fn with_capacity(nodes: usize) -> Pagerank<T> {
+        let mut pr = Pagerank::new();
+        pr.reserve(nodes);
+        pr
+    }
+}
+
+impl<T, S> Pagerank<T, S>
+where
+    T: Eq + Hash + Clone,
+    S: BuildHasher,
+{
+    /// Creates a new instance whose nodes are looked up with `hasher`,
+    /// for instance a faster, non randomized hasher on integer or short
+    /// string keys, which speeds up building large graphs
+    pub fn with_hasher(hasher: S) -> Pagerank<T, S> {
+        Pagerank {
+            damping: 0.85,
+            nodes: Vec::new(),
+            edges: 0,
+            node_positions: HashMap::with_hasher(hasher),
+            personalization: Vec::new(),
+            pinned: Vec::new(),
+            node_types: Vec::new(),
+            teleport_shares: Vec::new(),
+            in_edges_cap: None,
+            rng: 0x2545f4914f6cdd1d,
+            edge_log: None,
+            unique_edges: None,
+            batch: None,
+            stale_decay: None,
+            weight_fn: None,
+            affinity_fn: None,
+            group_fn: None,
+            edge_weights: None,
+            frozen: None,
+            components: None,
+            sweep: None,
+            precision: Precision::Double,
+            convergence_norm: ConvergenceNorm::ScaledL2,
+            dangling_policy: DanglingPolicy::Ignore,
+            convergence: DEFAULT_CONVERGENCE,
+            max_iterations: None,
+            pending_scores: HashMap::new(),
+            unknown_score: UnknownScore::Missing,
+            score_transform: None,
+            last_result: None,
+            max_memory: None,
+            evicted_nodes: 0,
+            nodes_with_in_edges: None,
+        }
+    }
+
+    /// Reserves room for at least `additional` more nodes
+    pub fn reserve(&mut self, additional: usize) {
+        self.nodes.reserve(additional);
+        self.node_positions.reserve(additional);
+    }
+
+    /// Sets the dumping factor. A value between 0 and 99 is expected.
+    ///
+    /// A factor of 0 means pure teleport: every node ends up with the same
+    /// score of 1, regardless of the edges. A factor of 100 or above is
+    /// rejected since the random surfer would never teleport and the
+    /// calculation would not converge.
+    pub fn set_damping_factor(
+        &mut self,
+        factor: u8,
+    ) -> Result<(), DampingError> {
+        if factor >= 100 {
+            return Err(DampingError::OutOfRange(factor));
+        }
+
+        self.damping = factor as f64 / 100_f64;
+        Ok(())
+    }
+
+    /// Sets the personalization vector, so the teleport probability goes
+    /// only to the given seed nodes, proportionally to their weights,
+    /// rather than uniformly to every node.
+    ///
+    /// Only the seeds are stored, and their weights are renormalized to
+    /// sum 1, so a handful of seeds among millions of nodes is cheap.
+    /// Weights which are not positive numbers are ignored; when no seed
+    /// is left teleporting becomes uniform again. Seeds which are not in
+    /// the graph yet are created.
+    ///
+    /// Any collection of pairs works, such as a `HashMap<T, f64>`. To
+    /// rank from the perspective of a single node, use it as the only
+    /// seed.
+    pub fn set_personalization<I>(&mut self, seeds: I)
+    where
+        I: IntoIterator<Item = (T, f64)>,
+    {
+        let mut weights = HashMap::new();
+        for (node, weight) in seeds {
+            if weight > 0f64 && weight.is_finite() {
+                let id = self.insert_node(node);
+                *weights.entry(id).or_insert(0f64) += weight;
+            }
+        }
+
+        // Summed in id order, so the result doesn't depend on hash order
+        let mut weights = weights.into_iter().collect::<Vec<_>>();
+        weights.sort_by_key(|(id, _)| *id);
+        let total = weights.iter().map(|(_, w)| w).sum::<f64>();
+        self.personalization = weights
+            .into_iter()
+            .map(|(id, weight)| (id, weight / total))
+            .collect();
+    }
+
+    /// Returns the personalization seeds with their normalized weights,
+    /// empty when teleporting is uniform
+    pub fn personalization(&self) -> Vec<(&T, f64)> {
+        self.personalization
+            .iter()
+            .map(|(id, weight)| (&self.nodes[*id].node, *weight))
+            .collect()
+    }
+
+    /// Adds an node between two nodes
+    ///
+    /// Edge counters saturate: an edge which would overflow the degree of
+    /// one of its nodes, or the number of edges, is ignored. Use
+    /// `try_add_edge` to be notified instead.
+    pub fn add_edge(&mut self, source: T, target: T) {
+        let _ = self.insert_edge(source, target, 1f64, None);
+    }
+
+    /// Adds an edge with a weight. A node splits its score among its out
+    /// edges in proportion to their weight, so repeated or stronger links
+    /// carry more of it; `add_edge` adds edges weighing 1.
+    ///
+    /// The weight must be a positive finite number.
+    pub fn add_edge_weighted(
+        &mut self,
+        source: T,
+        target: T,
+        weight: f64,
+    ) -> Result<(), EdgeError> {
+        self.insert_edge(source, target, weight, None)
+    }
+
+    /// Adds an edge between two nodes, failing without changing the graph
+    /// if it would overflow any edge counter
+    pub fn try_add_edge(
+        &mut self,
+        source: T,
+        target: T,
+    ) -> Result<(), EdgeError> {
+        self.insert_edge(source, target, 1f64, None)
+    }
+
+    /// Adds an edge, recording it in the edge log when enabled. Without
+    /// a `sequence` the edge gets the one following the last logged edge.
+    pub(crate) fn insert_edge(
+        &mut self,
+        source: T,
+        target: T,
+        weight: f64,
+        sequence: Option<u64>,
+    ) -> Result<(), EdgeError> {
+        if !(weight > 0f64 && weight.is_finite()) {
+            return Err(EdgeError::InvalidWeight(weight));
+        }
+        if self.is_duplicate_edge(&source, &target) {
+            return Ok(());
+        }
+
+        let out_edges = self
+            .node_positions
+            .get(&source)
+            .map_or(0, |id| self.nodes[*id].out_edges);
+        let in_degree = self
+            .node_positions
+            .get(&target)
+            .map_or(0, |id| self.nodes[*id].in_degree);
+        if out_edges.checked_add(1).is_none()
+            || in_degree.checked_add(1).is_none()
+            || self.edges.checked_add(1).is_none()
+        {
+            return Err(EdgeError::CounterOverflow);
+        }
+
+        let source = self.insert_node(source);
+        let target = self.insert_node(target);
+        if let Some(unique) = self.unique_edges.as_mut() {
+            unique.insert((source, target));
+        }
+        let out_weight = self.nodes[source].out_weight;
+        self.nodes[source].out_weight += weight;
+        self.nodes[source].out_edges += 1;
+        self.nodes[target].in_degree += 1;
+        self.edges += 1;
+        self.edge_weights = None;
+        self.thaw();
+        self.sweep = None;
+        if let Some(decay) = self.stale_decay.as_mut() {
+            decay.touch(target);
+        }
+
+        let stored = match self.in_edges_cap {
+            Some(cap) if self.nodes[target].in_edges.len() >= cap => {
+                // Reservoir sampling, every in edge has the same chance
+                // of being stored
+                let seen = self.nodes[target].in_degree as u64;
+                let slot =
+                    (next_random(&mut self.rng) % seen) as usize;
+                if slot < cap {
+                    let node = &mut self.nodes[target];
+                    let previous = node.in_edges[slot];
+                    let previous_weight = node.edge_weight(slot);
+                    node.in_edges[slot] = source;
+                    node.set_edge_weight(slot, weight);
+                    self.components = None;
+                    StoredEdge::Replaced(
+                        slot,
+                        previous,
+                        previous_weight,
+                    )
+                } else {
+                    StoredEdge::Skipped
+                }
+            }
+            _ => {
+                let node = &mut self.nodes[target];
+                node.in_edges.push(source);
+                if !node.in_weights.is_empty() {
+                    node.in_weights.push(weight);
+                } else {
+                    node.set_edge_weight(
+                        node.in_edges.len() - 1,
+                        weight,
+                    );
+                }
+                if let Some(components) = self.components.as_mut() {
+                    components.union(source, target);
+                }
+                StoredEdge::Pushed
+            }
+        };
+
+        if let Some(batch) = self.batch.as_mut() {
+            batch.record_edge(source, target, stored, out_weight);
+        }
+
+        if let Some(log) = self.edge_log.as_mut() {
+            let sequence = sequence.unwrap_or_else(|| {
+                log.last().map_or(0, |(_, _, last, _)| last + 1)
+            });
+            log.push((source, target, sequence, weight));
+        }
+
+        self.enforce_max_memory([source, target]);
+
+        Ok(())
+    }
+
+    /// Removes an edge from `source` to `target`. If the edge was added
+    /// several times, only the last one added is removed. Both nodes are
+    /// kept, even if they are left without edges.
+    ///
+    /// Returns false if there is no such edge. When the in edges of
+    /// `target` are capped, an edge which was not sampled can't be
+    /// removed either.
+    pub fn remove_edge(&mut self, source: &T, target: &T) -> bool {
+        self.remove_edge_copy(source, target, false)
+    }
+
+    /// Removes an edge from `source` to `target` as `remove_edge` does,
+    /// but the first one added instead of the last one when `oldest`
+    pub(crate) fn remove_edge_copy(
+        &mut self,
+        source: &T,
+        target: &T,
+        oldest: bool,
+    ) -> bool {
+        let (source, target) =
+            match (self.node_id(source), self.node_id(target)) {
+                (Some(source), Some(target)) => (source, target),
+                _ => return false,
+            };
+        self.thaw();
+        let mut sources = self.nodes[target].in_edges.iter();
+        let found = if oldest {
+            sources.position(|id| *id == source)
+        } else {
+            sources.rposition(|id| *id == source)
+        };
+        let slot = match found {
+            Some(slot) => slot,
+            None => return false,
+        };
+
+        let node = &mut self.nodes[target];
+        let weight = node.edge_weight(slot);
+        node.in_edges.remove(slot);
+        if !node.in_weights.is_empty() {
+            node.in_weights.remove(slot);
+        }
+        node.in_degree -= 1;
+
+        let out_weight = self.nodes[source].out_weight;
+        self.nodes[source].out_weight =
+            if self.nodes[source].out_edges == 1 {
+                0f64
+            } else {
+                out_weight - weight
+            };
+        self.nodes[source].out_edges -= 1;
+        self.edges -= 1;
+        if let Some(unique) = self.unique_edges.as_mut() {
+            unique.remove(&(source, target));
+        }
+        self.edge_weights = None;
+        self.sweep = None;
+        self.components = None;
+        self.nodes_with_in_edges = None;
+
+        let logged = self.edge_log.as_mut().and_then(|log| {
+            let is_edge =
+                |(s, t, _, _): &(usize, usize, u64, f64)| {
+                    *s == source && *t == target
+                };
+            let at = if oldest {
+                log.iter().position(is_edge)
+            } else {
+                log.iter().rposition(is_edge)
+            }?;
+            Some((at, log.remove(at).2))
+        });
+
+        if let Some(batch) = self.batch.as_mut() {
+            batch.record_edge(
+                source,
+                target,
+                StoredEdge::Removed(slot, weight, logged),
+                out_weight,
+            );
+        }
+
+        true
+    }
+
+    /// Removes a node with all its in and out edges. The following nodes
+    /// move down one id, so the remaining ones keep their relative
+    /// order, and rankings their tie breaks. It takes a pass over every
+    /// stored edge.
+    ///
+    /// The node is also dropped from the personalization vector, which
+    /// is renormalized, and from the edge log. Removing a node can't be
+    /// rolled back, so an open batch is committed first. When in edges
+    /// are capped, the out degree of sources whose edge to the node was
+    /// not sampled is left as is.
+    ///
+    /// Returns false if the node is not in the graph.
+    pub fn remove_node(&mut self, node: &T) -> bool {
+        let id = match self.node_positions.get(node) {
+            Some(id) => *id,
+            None => return false,
+        };
+
+        let mut removed = vec![false; self.nodes.len()];
+        removed[id] = true;
+        self.remove_ids(&removed);
+        true
+    }
+
+    /// Removes every node flagged in `removed` with all their edges, in
+    /// a single pass, as described in `remove_node`
+    pub(crate) fn remove_ids(&mut self, removed: &[bool]) {
+        // Committed without enforcing the memory budget, which would
+        // move the ids flagged in `removed`
+        self.batch = None;
+        self.thaw();
+
+        let mut new_ids = Vec::with_capacity(self.nodes.len());
+        let mut kept = 0;
+        for is_removed in removed.iter() {
+            new_ids.push(kept);
+            kept += !is_removed as usize;
+        }
+
+        let mut edges = 0u64;
+        for id in 0..self.nodes.len() {
+            if !removed[id] {
+                continue;
+            }
+            let removed_node = &self.nodes[id];
+            edges += (removed_node.in_degree + removed_node.out_edges)
+                as u64;
+            let stored = removed_node.in_edges.len();
+            for slot in 0..stored {
+                let source = self.nodes[id].in_edges[slot];
+                if removed[source] {
+                    // Counted as an out edge of its source already
+                    edges -= 1;
+                    continue;
+                }
+                let weight = self.nodes[id].edge_weight(slot);
+                let source = &mut self.nodes[source];
+                source.out_edges -= 1;
+                source.out_weight = if source.out_edges == 0 {
+                    0f64
+                } else {
+                    source.out_weight - weight
+                };
+            }
+        }
+        self.edges -= edges;
+
+        let nodes = core::mem::take(&mut self.nodes);
+        self.nodes = nodes
+            .into_iter()
+            .zip(removed.iter())
+            .filter_map(|(node, is_removed)| {
+                if *is_removed {
+                    self.node_positions.remove(&node.node);
+                    None
+                } else {
+                    Some(node)
+                }
+            })
+            .collect();
+
+        for node in self.nodes.iter_mut() {
+            let before = node.in_edges.len();
+            let weighted = !node.in_weights.is_empty();
+            let mut slot = 0;
+            for i in 0..before {
+                let source = node.in_edges[i];
+                if removed[source] {
+                    continue;
+                }
+                node.in_edges[slot] = new_ids[source];
+                if weighted {
+                    node.in_weights[slot] = node.in_weights[i];
+                }
+                slot += 1;
+            }
+            node.in_edges.truncate(slot);
+            node.in_weights.truncate(slot);
+            node.in_degree -= before - slot;
+        }
+
+        for position in self.node_positions.values_mut() {
+            *position = new_ids[*position];
+        }
+
+        let total = self
+            .personalization
+            .iter()
+            .filter(|(seed, _)| !removed[*seed])
+            .map(|(_, weight)| weight)
+            .sum::<f64>();
+        self.personalization = self
+            .personalization
+            .iter()
+            .filter(|(seed, _)| !removed[*seed])
+            .map(|(seed, weight)| (new_ids[*seed], weight / total))
+            .collect();
+        self.pinned.retain(|(id, _)| !removed[*id]);
+        for (id, _) in self.pinned.iter_mut() {
+            *id = new_ids[*id];
+        }
+
+        let mut id = 0;
+        self.node_types.retain(|_| {
+            id += 1;
+            !removed[id - 1]
+        });
+        if let Some(decay) = self.stale_decay.as_mut() {
+            decay.retain(removed);
+        }
+        if let Some(unique) = self.unique_edges.as_mut() {
+            *unique = unique
+                .iter()
+                .filter(|(source, target)| {
+                    !removed[*source] && !removed[*target]
+                })
+                .map(|(source, target)| {
+                    (new_ids[*source], new_ids[*target])
+                })
+                .collect();
+        }
+        if let Some(log) = self.edge_log.as_mut() {
+            log.retain(|(source, target, _, _)| {
+                !removed[*source] && !removed[*target]
+            });
+            for (source, target, _, _) in log.iter_mut() {
+                *source = new_ids[*source];
+                *target = new_ids[*target];
+            }
+        }
+
+        self.edge_weights = None;
+        self.sweep = None;
+        self.components = None;
+        self.nodes_with_in_edges = None;
+    }
+
+    /// Caps the number of in edges stored per node, bounding the memory
+    /// used by nodes with a huge number of incoming links.
+    ///
+    /// Once a node reaches the cap, its in edges are sampled uniformly
+    /// (reservoir sampling) and each stored edge is weighted so the total
+    /// number of in edges is preserved. The cap only applies to edges
+    /// added afterwards.
+    pub fn set_in_edges_cap(&mut self, cap: usize) {
+        self.in_edges_cap = Some(cap.max(1));
+    }
+
+    /// Returns the current score of a gien node. Nodes which are not
+    /// in the graph get `None`, unless `set_unknown_score` says
+    /// otherwise.
+    pub fn get_score(&self, node: T) -> Option<f64> {
+        match self.node_positions.get(&node) {
+            Some(id) => Some(self.transformed(self.nodes[*id].score)),
+            None => self.unknown_node_score(),
+        }
+    }
+
+    /// Returns the number of in edges for the given node
+    pub fn get_in_edges(&self, node: T) -> Option<usize> {
+        self.node_positions
+            .get(&node)
+            .map(|id| self.nodes[*id].in_degree)
+    }
+
+    /// Returns the number of out edges for the given node
+    pub fn get_out_edges(&self, node: T) -> Option<usize> {
+        self.node_positions
+            .get(&node)
+            .map(|id| self.nodes[*id].out_edges)
+    }
+
+    /// Returns the number of edges of the given node, regardless of their
+    /// direction (in edges plus out edges)
+    pub fn get_degree(&self, node: T) -> Option<usize> {
+        self.node_positions.get(&node).map(|id| {
+            self.nodes[*id].in_degree + self.nodes[*id].out_edges
+        })
+    }
+
+    /// Returns the in and out degrees of every node, in a single pass
+    pub fn degrees(&self) -> Vec<(&T, usize, usize)> {
+        self.nodes
+            .iter()
+            .map(|n| (&n.node, n.in_degree, n.out_edges))
+            .collect()
+    }
+
+    /// Iterates over every node in insertion order, with its id, score
+    /// and degrees
+    pub fn iter_nodes(
+        &self,
+    ) -> impl Iterator<Item = NodeView<'_, T>> {
+        self.nodes.iter().enumerate().map(move |(id, n)| NodeView {
+            key: &n.node,
+            score: self.transformed(n.score),
+            in_degree: n.in_degree,
+            out_degree: n.out_edges,
+            id,
+        })
+    }
+
+    /// Returns the sum of the degrees of all nodes, which is twice the
+    /// number of edges since every edge has two endpoints
+    pub fn total_degree(&self) -> u64 {
+        self.edges.saturating_mul(2)
+    }
+
+    /// Returns the average number of edges per node
+    pub fn average_degree(&self) -> f64 {
+        if self.nodes.is_empty() {
+            return 0f64;
+        }
+
+        self.total_degree() as f64 / self.nodes.len() as f64
+    }
+
+    /// Returns the id of a node, or `None` if it is not in the graph.
+    /// Unlike `insert_node`, it never changes the graph.
+    pub fn node_id(&self, node: &T) -> Option<usize> {
+        self.node_positions.get(node).cloned()
+    }
+
+    /// If the node is in the graph
+    pub fn contains(&self, node: &T) -> bool {
+        self.node_positions.contains_key(node)
+    }
+
+    /// Returns the node_id for a given node name
+    #[deprecated(
+        note = "it creates missing nodes, use `node_id` to look nodes up or `insert_node` to add them"
+    )]
+    pub fn get_or_create_node(&mut self, node: T) -> usize {
+        self.insert_node(node)
+    }
+
+    /// Adds a node without any edge, if it is not in the graph yet.
+    /// Returns its id either way.
+    pub fn insert_node(&mut self, node: T) -> usize {
+        match self.node_positions.get(&node) {
+            Some(&value) => value,
+            _ => {
+                self.thaw();
+                let id = self.nodes.len();
+                let score = if self.pending_scores.is_empty() {
+                    1f64 - self.damping
+                } else {
+                    self.pending_scores
+                        .remove(&node)
+                        .unwrap_or(1f64 - self.damping)
+                };
+                self.nodes.push(Node::<T> {
+                    node: node.clone(),
+                    in_edges: Vec::new(),
+                    in_degree: 0,
+                    out_edges: 0,
+                    in_weights: Vec::new(),
+                    out_weight: 0f64,
+                    score,
+                });
+                self.node_positions.insert(node, id);
+                self.nodes_with_in_edges = None;
+                self.sweep = None;
+                if let Some(components) = self.components.as_mut() {
+                    components.push();
+                }
+                id
+            }
+        }
+    }
+
+    /// Return all nodes, sorted by their pagerank. See `iter_ranked` to
+    /// stream them instead.
+    pub fn nodes(&self) -> Vec<(&T, f64)> {
+        let mut nodes = self
+            .nodes
+            .iter()
+            .map(|node| (&node.node, self.transformed(node.score)))
+            .collect::<Vec<(&T, f64)>>();
+
+        nodes.sort_by(|a, b| cmp_scores(b.1, a.1));
+
+        nodes
+    }
+
+    /// Len of all edges
+    pub fn len_nodes_with_in_edges(&mut self) -> usize {
+        if let Some(n) = self.nodes_with_in_edges {
+            return n;
+        }
+
+        let mut total = 0;
+
+        for id in 0..self.nodes.len() {
+            if self.len_in_edges(id) > 0 {
+                total += 1;
+            }
+        }
+
+        self.nodes_with_in_edges = Some(total);
+
+        total
+    }
+
+    /// Return the number of vertices/nodes in the current graph
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    /// Returns the number of edges in the current graph
+    pub fn len_node(&self) -> u64 {
+        self.edges
+    }
+
+    /// If the graph is empty
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+}
+
+/// xorshift64* pseudo-random generator. The state must not be zero.
+pub(crate) fn next_random(state: &mut u64) -> u64 {
+    *state ^= *state >> 12;
+    *state ^= *state << 25;
+    *state ^= *state >> 27;
+    state.wrapping_mul(0x2545f4914f6cdd1d)
+}
+
+impl<T, S> Default for Pagerank<T, S>
+where
+    T: Eq + Hash + Clone,
+    S: BuildHasher + Default,
+{
+    fn default() -> Self {
+        Self::with_hasher(S::default())
+    }
+}
+
+/// Adds every `(source, target)` edge as it comes, so a graph can be fed
+/// from any source of edges, such as a channel filled by a consumer
+/// task, without collecting them first.
+///
+/// The edge log, when recording, is sized from the lower bound of the
+/// iterator length. The number of nodes can't be told from the number of
+/// edges, use `with_capacity` or `reserve` to size them.
+impl<T, S> Extend<(T, T)> for Pagerank<T, S>
+where
+    T: Eq + Hash + Clone,
+    S: BuildHasher,
+{
+    fn extend<I: IntoIterator<Item = (T, T)>>(&mut self, edges: I) {
+        let edges = edges.into_iter();
+        if let Some(log) = self.edge_log.as_mut() {
+            log.reserve(edges.size_hint().0);
+        }
+        for (source, target) in edges {
+            self.add_edge(source, target);
+        }
+    }
+}
+
+impl<T, S> FromIterator<(T, T)> for Pagerank<T, S>
+where
+    T: Eq + Hash + Clone,
+    S: BuildHasher + Default,
+{
+    fn from_iter<I: IntoIterator<Item = (T, T)>>(edges: I) -> Self {
+        let mut pr = Pagerank::with_hasher(S::default());
+        pr.extend(edges);
+        pr
+    }
+}
+
+/// 64 bit FNV-1a hash of `key`, with a different basis per `seed`,
+/// followed by the SplitMix64 finalizer to spread similar keys apart
+pub(crate) fn hash_bytes(seed: u64, key: &[u8]) -> u64 {
+    let mut hash =
+        0xcbf29ce484222325 ^ seed.wrapping_mul(0x9e3779b97f4a7c15);
+    for byte in key {
+        hash ^= *byte as u64;
+        hash = hash.wrapping_mul(0x100000001b3);
+    }
+
+    hash = (hash ^ (hash >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+    hash = (hash ^ (hash >> 27)).wrapping_mul(0x94d049bb133111eb);
+    hash ^ (hash >> 31)
+}
+
+/// Error returned when setting an invalid damping factor
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DampingError {
+    /// The factor must be below 100
+    OutOfRange(u8),
+}
+
+impl fmt::Display for DampingError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            DampingError::OutOfRange(factor) => write!(
+                f,
+                "damping factor {} needs to be below 100",
+                factor
+            ),
+        }
+    }
+}
+
+impl Error for DampingError {}
+
+/// A node and everything known about it, as returned by
+/// `Pagerank::iter_nodes`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct NodeView<'a, T> {
+    /// The node
+    pub key: &'a T,
+    /// Current score of the node
+    pub score: f64,
+    /// Number of in edges
+    pub in_degree: usize,
+    /// Number of out edges
+    pub out_degree: usize,
+    /// Internal id of the node, its position in insertion order
+    pub id: usize,
+}
+
+/// Total order of scores, with NaN below every other score, so sorts
+/// never panic and NaN scores, from weight closures returning NaN for
+/// instance, sink to the bottom of rankings
+pub(crate) fn cmp_scores(a: f64, b: f64) -> Ordering {
+    match (a.is_nan(), b.is_nan()) {
+        (false, false) => {
+            a.partial_cmp(&b).unwrap_or(Ordering::Equal)
+        }
+        (true, true) => Ordering::Equal,
+        (true, false) => Ordering::Less,
+        (false, true) => Ordering::Greater,
+    }
+}
+
+/// Error returned when an edge cannot be added
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum EdgeError {
+    /// The edge would overflow the degree of one of its nodes, or the
+    /// total number of edges
+    CounterOverflow,
+    /// The edge weight is not a positive finite number
+    InvalidWeight(f64),
+}
+
+impl fmt::Display for EdgeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            EdgeError::CounterOverflow => {
+                write!(f, "edge would overflow an edge counter")
+            }
+            EdgeError::InvalidWeight(weight) => write!(
+                f,
+                "edge weight {} is not a positive finite number",
+                weight
+            ),
+        }
+    }
+}
+
+impl Error for EdgeError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ScoreSet;
+
+    #[test]
+    fn test_two_nodes_are_created() {
+        let mut pr = Pagerank::<&str>::new();
+        pr.add_edge("foo", "bar");
+        assert_eq!(2, pr.len())
+    }
+
+    #[test]
+    fn test_edges() {
+        let mut pr = Pagerank::<&str>::new();
+        pr.add_edge("foo", "bar");
+        assert_eq!(0, pr.insert_node("foo"));
+        assert_eq!(1, pr.insert_node("bar"));
+        assert_eq!(Some(1), pr.node_id(&"bar"));
+        assert!(pr.contains(&"bar"));
+        assert_eq!(None, pr.node_id(&"xxx"));
+        assert!(!pr.contains(&"xxx"));
+        assert_eq!(2, pr.len());
+        assert_eq!(2, pr.insert_node("xxx"));
+
+        assert_eq!(Some(0), pr.get_in_edges("foo"));
+        assert_eq!(Some(1), pr.get_out_edges("foo"));
+        assert_eq!(Some(1), pr.get_in_edges("bar"));
+        assert_eq!(Some(0), pr.get_out_edges("bar"));
+    }
+
+    #[test]
+    fn test_edge_counter_overflow() {
+        let mut pr = Pagerank::<&str>::new();
+        pr.add_edge("foo", "bar");
+        let id = pr.insert_node("foo");
+        pr.nodes[id].out_edges = usize::MAX;
+
+        assert_eq!(
+            Err(EdgeError::CounterOverflow),
+            pr.try_add_edge("foo", "xxx")
+        );
+        assert_eq!(2, pr.len());
+
+        pr.add_edge("foo", "xxx");
+        assert_eq!(Some(usize::MAX), pr.get_out_edges("foo"));
+        assert_eq!(1, pr.len_node());
+
+        pr.edges = u64::MAX;
+        assert_eq!(
+            Err(EdgeError::CounterOverflow),
+            pr.try_add_edge("bar", "foo")
+        );
+        assert_eq!(u64::MAX, pr.total_degree());
+    }
+
+    #[test]
+    fn test_from_edges() {
+        let (sender, receiver) = std::sync::mpsc::channel();
+        let producer = std::thread::spawn(move || {
+            for i in 0..10u32 {
+                sender.send((i, (i + 1) % 10)).unwrap();
+            }
+        });
+
+        let mut pr = receiver.into_iter().collect::<Pagerank<u32>>();
+        producer.join().unwrap();
+        assert_eq!(10, pr.len());
+        assert_eq!(10, pr.len_node());
+
+        pr.extend(vec![(0, 5), (5, 0)]);
+        assert_eq!(Some(2), pr.get_out_edges(0));
+
+        let mut pr = Pagerank::<u32>::with_capacity(100);
+        assert!(pr.nodes.capacity() >= 100);
+        pr.record_edge_order();
+        pr.extend((0..50).map(|i| (i, i + 1)));
+        assert!(pr.edge_log.as_ref().unwrap().capacity() >= 50);
+        assert_eq!(51, pr.len());
+    }
+
+    #[test]
+    fn test_with_hasher() {
+        use std::hash::{BuildHasherDefault, Hasher};
+
+        #[derive(Default)]
+        struct Fnv(u64);
+
+        impl Hasher for Fnv {
+            fn finish(&self) -> u64 {
+                self.0
+            }
+
+            fn write(&mut self, bytes: &[u8]) {
+                for byte in bytes {
+                    self.0 = (self.0 ^ *byte as u64)
+                        .wrapping_mul(0x100000001b3);
+                }
+            }
+        }
+
+        let mut pr =
+            Pagerank::<u32, BuildHasherDefault<Fnv>>::default();
+        pr.extend(vec![(1, 2), (3, 2), (2, 1)]);
+        pr.calculate();
+
+        let mut expected = Pagerank::<u32>::new();
+        expected.extend(vec![(1, 2), (3, 2), (2, 1)]);
+        expected.calculate();
+        assert_eq!(expected.nodes(), pr.nodes());
+        assert_eq!(expected.get_score(2), pr.get_score(2));
+        assert_eq!(Some(0), pr.view().id(&1));
+    }
+
+    #[test]
+    fn test_add_edge_weighted() {
+        let mut pr = Pagerank::<&str>::new();
+        pr.add_edge("a", "b");
+        pr.add_edge_weighted("a", "c", 3.0).unwrap();
+        assert_eq!(
+            Err(EdgeError::InvalidWeight(0.0)),
+            pr.add_edge_weighted("a", "c", 0.0)
+        );
+        assert!(pr.add_edge_weighted("a", "c", f64::NAN).is_err());
+        assert_eq!(Some(2), pr.get_out_edges("a"));
+
+        pr.calculate_with_convergence(1e-12);
+        let a = pr.get_score("a").unwrap();
+        let c = pr.get_score("c").unwrap();
+        assert!((c - (0.15 + 0.85 * a * 0.75)).abs() < 1e-12);
+
+        // Unit weights behave as unweighted edges
+        let mut unit = Pagerank::<&str>::new();
+        unit.add_edge_weighted("a", "b", 1.0).unwrap();
+        unit.add_edge("a", "c");
+        unit.calculate();
+        assert!(unit.nodes.iter().all(|n| n.in_weights.is_empty()));
+        assert_eq!(unit.get_score("b"), unit.get_score("c"));
+    }
+
+    #[test]
+    fn test_iter_nodes() {
+        let mut pr = Pagerank::<&str>::new();
+        pr.add_edge("foo", "bar");
+        pr.add_edge("xxx", "bar");
+        pr.calculate();
+
+        let views = pr.iter_nodes().collect::<Vec<NodeView<&str>>>();
+        assert_eq!(3, views.len());
+        assert_eq!("bar", *views[1].key);
+        assert_eq!(1, views[1].id);
+        assert_eq!((2, 0), (views[1].in_degree, views[1].out_degree));
+        assert_eq!(pr.get_score("bar"), Some(views[1].score));
+    }
+
+    #[test]
+    fn test_degrees() {
+        let mut pr = Pagerank::<&str>::new();
+        pr.add_edge("foo", "bar");
+        pr.add_edge("bar", "foo");
+        pr.add_edge("xxx", "bar");
+
+        assert_eq!(Some(3), pr.get_degree("bar"));
+        assert_eq!(Some(1), pr.get_degree("xxx"));
+        assert_eq!(None, pr.get_degree("yyy"));
+        assert_eq!(6, pr.total_degree());
+        assert_eq!(2.0, pr.average_degree());
+        assert_eq!(
+            vec![("foo", 1, 1), ("bar", 2, 1), ("xxx", 0, 1)],
+            pr.degrees()
+                .into_iter()
+                .map(|(n, i, o)| (*n, i, o))
+                .collect::<Vec<(&str, usize, usize)>>()
+        );
+    }
+
+    #[test]
+    fn test_default_score() {
+        let mut pr = Pagerank::<&str>::new();
+        pr.add_edge("foo", "bar");
+        pr.add_edge("bar", "foo");
+        pr.add_edge("xxx", "bar");
+        pr.add_edge("yyy", "xxx");
+
+        assert_eq!(
+            15_i64,
+            (pr.get_score("foo").expect("float") * 100_f64) as i64
+        );
+        assert_eq!(pr.get_score("foo"), pr.get_score("bar"));
+        assert_eq!(pr.get_score("foo"), pr.get_score("xxx"));
+        assert_eq!(pr.get_score("foo"), pr.get_score("yyy"));
+    }
+
+    #[test]
+    fn test_in_edges_cap() {
+        let mut pr = Pagerank::<u32>::new();
+        pr.set_in_edges_cap(10);
+        for source in 1..=100 {
+            pr.add_edge(source, 0);
+        }
+
+        assert_eq!(Some(100), pr.get_in_edges(0));
+        let id = pr.insert_node(0);
+        assert_eq!(10, pr.nodes[id].in_edges.len());
+
+        pr.calculate();
+        let expected = 0.15 + 0.85 * 100.0 * 0.15;
+        assert!((pr.get_score(0).unwrap() - expected).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_send_sync() {
+        fn assert_send_sync<T: Send + Sync>() {}
+        assert_send_sync::<Pagerank<String>>();
+        assert_send_sync::<ScoreSet<String>>();
+    }
+
+    #[test]
+    fn test_remove_edge() {
+        let mut pr = Pagerank::<&str>::new();
+        pr.add_edge("foo", "bar");
+        pr.add_edge("foo", "bar");
+        pr.add_edge_weighted("foo", "xxx", 2.0).unwrap();
+        pr.add_edge("bar", "foo");
+        pr.calculate();
+        let before = pr.get_score("xxx").unwrap();
+
+        assert!(pr.remove_edge(&"foo", &"bar"));
+        assert_eq!(Some(1), pr.get_in_edges("bar"));
+        assert_eq!(Some(2), pr.get_out_edges("foo"));
+        assert_eq!(3, pr.len_node());
+        assert!(pr.remove_edge(&"foo", &"bar"));
+        assert!(!pr.remove_edge(&"foo", &"bar"));
+        assert!(!pr.remove_edge(&"foo", &"unknown"));
+        assert_eq!(Some(0), pr.get_in_edges("bar"));
+        assert_eq!(2, pr.len_node());
+        assert_eq!(3, pr.len());
+
+        pr.calculate();
+        assert!(pr.get_score("xxx").unwrap() > before);
+        assert_eq!(Some(1.0 - 0.85), pr.get_score("bar"));
+
+        assert!(pr.remove_edge(&"foo", &"xxx"));
+        assert_eq!(0f64, pr.nodes[0].out_weight);
+    }
+
+    #[test]
+    fn test_remove_node() {
+        let mut pr = Pagerank::<&str>::new();
+        pr.record_edge_order();
+        pr.add_edge("foo", "bar");
+        pr.add_edge("bar", "foo");
+        pr.add_edge_weighted("gone", "bar", 2.0).unwrap();
+        pr.add_edge("foo", "gone");
+        pr.add_edge("gone", "gone");
+        pr.add_edge("xxx", "foo");
+        pr.set_personalization(vec![("gone", 1.0), ("xxx", 3.0)]);
+
+        assert!(pr.remove_node(&"gone"));
+        assert!(!pr.remove_node(&"gone"));
+        assert!(!pr.contains(&"gone"));
+        assert_eq!(3, pr.len());
+        assert_eq!(3, pr.len_node());
+        assert_eq!(Some(1), pr.get_out_edges("foo"));
+        assert_eq!(1.0, pr.nodes[0].out_weight);
+        assert_eq!(Some(1), pr.get_in_edges("bar"));
+        assert_eq!(Some(2), pr.node_id(&"xxx"));
+        assert_eq!(vec![(&"xxx", 1.0)], pr.personalization());
+        assert_eq!(
+            Some(vec![
+                (&"foo", &"bar", 0),
+                (&"bar", &"foo", 1),
+                (&"xxx", &"foo", 5)
+            ]),
+            pr.edge_sequence()
+        );
+
+        let mut expected = Pagerank::<&str>::new();
+        expected.add_edge("foo", "bar");
+        expected.add_edge("bar", "foo");
+        expected.add_edge("xxx", "foo");
+        expected.set_personalization(vec![("xxx", 1.0)]);
+        pr.calculate();
+        expected.calculate();
+        assert_eq!(expected.nodes(), pr.nodes());
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde() {
+        let graph = || {
+            let mut pr = Pagerank::<String>::new();
+            pr.set_unique_edges(true);
+            pr.record_edge_order();
+            for (source, target) in
+                [("foo", "bar"), ("bar", "foo"), ("xxx", "bar")]
+            {
+                pr.add_edge(source.to_string(), target.to_string());
+            }
+            pr.add_edge_weighted("xxx".into(), "foo".into(), 2.5)
+                .unwrap();
+            pr.set_personalization(vec![("xxx".to_string(), 1.0)]);
+            pr.set_dangling_policy(DanglingPolicy::Uniform);
+            pr.set_weight_fn(|_, target| target.len() as f64);
+            pr.set_score_transform(f64::ln_1p);
+            pr.calculate_with_convergence(1e-12);
+            pr
+        };
+
+        let mut pr = graph();
+        let json = serde_json::to_string(&pr).expect("serialize");
+        let mut restored: Pagerank<String> =
+            serde_json::from_str(&json).expect("deserialize");
+        assert_eq!(pr.len(), restored.len());
+        assert_eq!(pr.len_node(), restored.len_node());
+        assert_eq!(pr.personalization(), restored.personalization());
+        assert_eq!(pr.edge_sequence(), restored.edge_sequence());
+        assert_eq!(pr.last_result, restored.last_result);
+        assert_eq!(
+            DanglingPolicy::Uniform,
+            restored.dangling_policy()
+        );
+        for (a, b) in pr.nodes.iter().zip(restored.nodes.iter()) {
+            assert_eq!((&a.node, a.score), (&b.node, b.score));
+        }
+
+        // Closures are set again, and the graph keeps growing as the
+        // original one
+        restored.set_weight_fn(|_, target| target.len() as f64);
+        restored.set_score_transform(f64::ln_1p);
+        for pr in [&mut pr, &mut restored] {
+            pr.add_edge("bar".into(), "yyy".into());
+            pr.add_edge("bar".into(), "yyy".into());
+            pr.calculate_with_convergence(1e-12);
+        }
+        assert_eq!(pr.nodes(), restored.nodes());
+    }
+}
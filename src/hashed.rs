@@ -0,0 +1,301 @@
+//! Nodes identified by a hash of their key
+use crate::graph::hash_bytes;
+use crate::{EdgeError, Pagerank};
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+use std::hash::{BuildHasher, Hash};
+use std::io::{self, BufRead, Write};
+
+/// Error returned while hashing keys
+#[derive(Debug)]
+pub enum KeyError {
+    /// Two different keys have the same hash
+    Collision(u128),
+    /// The key recovery map could not be written
+    Io(io::Error),
+    /// The edge could not be added
+    Edge(EdgeError),
+}
+
+impl fmt::Display for KeyError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            KeyError::Collision(hash) => {
+                write!(f, "two keys have the same hash {}", hash)
+            }
+            KeyError::Io(err) => write!(f, "{}", err),
+            KeyError::Edge(err) => write!(f, "{}", err),
+        }
+    }
+}
+
+impl Error for KeyError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            KeyError::Collision(_) => None,
+            KeyError::Io(err) => Some(err),
+            KeyError::Edge(err) => Some(err),
+        }
+    }
+}
+
+impl From<io::Error> for KeyError {
+    fn from(err: io::Error) -> Self {
+        KeyError::Io(err)
+    }
+}
+
+/// Hashes of the keys which can identify nodes, `u64` or `u128`
+pub trait KeyHash: Eq + Hash + Clone + fmt::Display {
+    /// Hashes a key with the given hasher
+    fn hash_key(
+        hasher: &mut KeyHasher,
+        key: &[u8],
+    ) -> Result<Self, KeyError>;
+}
+
+impl KeyHash for u64 {
+    fn hash_key(
+        hasher: &mut KeyHasher,
+        key: &[u8],
+    ) -> Result<Self, KeyError> {
+        let hash = hash_bytes(0, key);
+        hasher.check(hash as u128, hash_bytes(1, key), key)?;
+        Ok(hash)
+    }
+}
+
+impl KeyHash for u128 {
+    fn hash_key(
+        hasher: &mut KeyHasher,
+        key: &[u8],
+    ) -> Result<Self, KeyError> {
+        let hash = (hash_bytes(1, key) as u128) << 64
+            | hash_bytes(0, key) as u128;
+        hasher.check(hash, hash_bytes(2, key), key)?;
+        Ok(hash)
+    }
+}
+
+/// Turns long keys, such as URLs, into 64 or 128 bit hashes, so a graph
+/// of `Pagerank<u64>` or `Pagerank<u128>` holds a fixed size integer per
+/// node instead of the key itself.
+///
+/// Hashes are stable across runs and platforms. Optionally, a 64 bit
+/// fingerprint of every key is kept to detect collisions, and a
+/// recovery map from hashes back to keys is written out, so the hashes
+/// in the results can be turned into keys again.
+pub struct KeyHasher {
+    /// Fingerprint of the key of every hash seen, if tracked
+    seen: Option<HashMap<u128, u64>>,
+    /// Where to write each new hash with its key
+    recovery: Option<Box<dyn Write>>,
+}
+
+impl KeyHasher {
+    /// Creates a hasher which neither checks collisions nor writes a
+    /// recovery map
+    pub fn new() -> KeyHasher {
+        KeyHasher {
+            seen: None,
+            recovery: None,
+        }
+    }
+
+    /// Checks every key against a second, independent hash, returning
+    /// `KeyError::Collision` when two keys share a hash. It takes about
+    /// 24 bytes per distinct key, still much less than long keys.
+    pub fn with_verification(mut self) -> KeyHasher {
+        if self.seen.is_none() {
+            self.seen = Some(HashMap::new());
+        }
+        self
+    }
+
+    /// Writes a `hash<TAB>key` line to `writer` for every new hash,
+    /// which `read_recovery_map` loads back. Collisions are checked too,
+    /// since new hashes are told apart the same way.
+    pub fn with_recovery_map<W: Write + 'static>(
+        self,
+        writer: W,
+    ) -> KeyHasher {
+        let mut hasher = self.with_verification();
+        hasher.recovery = Some(Box::new(writer));
+        hasher
+    }
+
+    /// Hashes a key
+    pub fn hash<H: KeyHash>(
+        &mut self,
+        key: &[u8],
+    ) -> Result<H, KeyError> {
+        H::hash_key(self, key)
+    }
+
+    /// Flushes the recovery map, if any
+    pub fn flush(&mut self) -> io::Result<()> {
+        match self.recovery.as_mut() {
+            Some(writer) => writer.flush(),
+            None => Ok(()),
+        }
+    }
+
+    /// Reads a recovery map written by a hasher, giving the key of each
+    /// hash. 64 bit hashes are widened to `u128`.
+    pub fn read_recovery_map<R: BufRead>(
+        reader: R,
+    ) -> io::Result<HashMap<u128, String>> {
+        let mut keys = HashMap::new();
+        for line in reader.lines() {
+            let line = line?;
+            let mut columns = line.splitn(2, '\t');
+            let hash = columns
+                .next()
+                .and_then(|hash| hash.parse::<u128>().ok());
+            match (hash, columns.next()) {
+                (Some(hash), Some(key)) => {
+                    keys.insert(hash, key.to_owned());
+                }
+                _ => {
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        "expected a hash and a key per line",
+                    ))
+                }
+            }
+        }
+        Ok(keys)
+    }
+
+    /// Records a hash with the fingerprint of its key, if tracked,
+    /// writing it to the recovery map the first time it is seen
+    fn check(
+        &mut self,
+        hash: u128,
+        fingerprint: u64,
+        key: &[u8],
+    ) -> Result<(), KeyError> {
+        let seen = match self.seen.as_mut() {
+            Some(seen) => seen,
+            None => return Ok(()),
+        };
+
+        match seen.get(&hash) {
+            Some(previous) if *previous == fingerprint => Ok(()),
+            Some(_) => Err(KeyError::Collision(hash)),
+            None => {
+                if let Some(writer) = self.recovery.as_mut() {
+                    write!(writer, "{}\t", hash)?;
+                    writer.write_all(key)?;
+                    writer.write_all(b"\n")?;
+                }
+                seen.insert(hash, fingerprint);
+                Ok(())
+            }
+        }
+    }
+}
+
+impl Default for KeyHasher {
+    fn default() -> Self {
+        KeyHasher::new()
+    }
+}
+
+impl<H, S> Pagerank<H, S>
+where
+    H: KeyHash,
+    S: BuildHasher,
+{
+    /// Adds an edge between two keys, stored by their hash
+    pub fn add_edge_hashed<K: AsRef<[u8]>>(
+        &mut self,
+        hasher: &mut KeyHasher,
+        source: K,
+        target: K,
+    ) -> Result<(), KeyError> {
+        let source = hasher.hash(source.as_ref())?;
+        let target = hasher.hash(target.as_ref())?;
+        self.try_add_edge(source, target).map_err(KeyError::Edge)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{KeyError, KeyHasher};
+    use crate::Pagerank;
+    use std::cell::RefCell;
+    use std::io::{self, Write};
+    use std::rc::Rc;
+
+    /// Writer whose content can still be read once moved into a hasher
+    #[derive(Clone, Default)]
+    struct Shared(Rc<RefCell<Vec<u8>>>);
+
+    impl Write for Shared {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.borrow_mut().write(buf)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_add_edge_hashed() {
+        let recovery = Shared::default();
+        let mut hasher =
+            KeyHasher::new().with_recovery_map(recovery.clone());
+        let mut pr = Pagerank::<u64>::new();
+        pr.add_edge_hashed(
+            &mut hasher,
+            "https://a.example",
+            "https://b.example",
+        )
+        .unwrap();
+        pr.add_edge_hashed(
+            &mut hasher,
+            "https://b.example",
+            "https://a.example",
+        )
+        .unwrap();
+        pr.add_edge_hashed(
+            &mut hasher,
+            "https://c.example",
+            "https://a.example",
+        )
+        .unwrap();
+        assert_eq!(3, pr.len());
+
+        let keys =
+            KeyHasher::read_recovery_map(&recovery.0.borrow()[..])
+                .unwrap();
+        assert_eq!(3, keys.len());
+        pr.calculate();
+        let (best, _) = pr.nodes()[0];
+        assert_eq!("https://a.example", keys[&(*best as u128)]);
+
+        let mut wide = Pagerank::<u128>::new();
+        let mut plain = KeyHasher::new();
+        wide.add_edge_hashed(&mut plain, "a", "b").unwrap();
+        let hash: u128 = plain.hash(b"a").unwrap();
+        assert!(wide.contains(&hash));
+        assert_ne!(hash >> 64, 0);
+    }
+
+    #[test]
+    fn test_collision() {
+        let mut hasher = KeyHasher::new().with_verification();
+        let hash = hasher.hash::<u64>(b"foo").unwrap();
+        // Forge a colliding key by tampering with the stored fingerprint
+        hasher.seen.as_mut().unwrap().insert(hash as u128, 0);
+        match hasher.hash::<u64>(b"foo") {
+            Err(KeyError::Collision(h)) => {
+                assert_eq!(hash as u128, h)
+            }
+            _ => panic!("collision expected"),
+        }
+    }
+}
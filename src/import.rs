@@ -0,0 +1,433 @@
+//! Importing edges from delimited text files
+use crate::Pagerank;
+use std::error::Error;
+use std::fmt;
+use std::hash::{BuildHasher, Hash};
+use std::io::{self, BufRead};
+use std::str::FromStr;
+
+/// How malformed rows are handled while importing
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ParseMode {
+    /// Abort on the first malformed row, reporting its line number
+    Strict,
+    /// Skip malformed rows, counting them in the `ImportReport`
+    Lenient,
+}
+
+/// Options of `Pagerank::import_tsv`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ImportOptions {
+    /// Character separating the columns, a tab by default
+    pub delimiter: char,
+    /// Zero based column holding the source of each edge
+    pub source_column: usize,
+    /// Zero based column holding the target of each edge
+    pub target_column: usize,
+    /// Whether the first line is a header to be ignored
+    pub skip_header: bool,
+    /// How malformed rows are handled
+    pub mode: ParseMode,
+    /// Longest key, in bytes, accepted in the source and target
+    /// columns. Rows with a longer key are handled as malformed.
+    pub max_key_len: Option<usize>,
+    /// Number of anomalies of each kind recorded in the report. Further
+    /// ones are only counted, so a badly broken input can't flood it.
+    pub max_anomalies: usize,
+}
+
+impl Default for ImportOptions {
+    fn default() -> Self {
+        ImportOptions {
+            delimiter: '\t',
+            source_column: 0,
+            target_column: 1,
+            skip_header: false,
+            mode: ParseMode::Strict,
+            max_key_len: None,
+            max_anomalies: 10,
+        }
+    }
+}
+
+/// Kind of data quality issue found while importing
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum AnomalyKind {
+    /// The row lacks a column, a node can't be parsed or it is not
+    /// valid UTF-8. The row is skipped.
+    Unparsable,
+    /// A key is longer than `ImportOptions::max_key_len`. The row is
+    /// skipped.
+    OversizedKey,
+    /// The source and the target are the same node. The edge is still
+    /// added.
+    SelfLoop,
+}
+
+impl fmt::Display for AnomalyKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            AnomalyKind::Unparsable => write!(f, "unparsable row"),
+            AnomalyKind::OversizedKey => write!(f, "oversized key"),
+            AnomalyKind::SelfLoop => write!(f, "self loop"),
+        }
+    }
+}
+
+/// A data quality issue found in a row while importing
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Anomaly {
+    /// What is wrong with the row
+    pub kind: AnomalyKind,
+    /// Line number of the row, starting at 1
+    pub line: usize,
+    /// Beginning of the row, at most `ANOMALY_EXCERPT` bytes, with
+    /// invalid UTF-8 replaced
+    pub excerpt: String,
+}
+
+impl fmt::Display for Anomaly {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} at line {}: {:?}",
+            self.kind, self.line, self.excerpt
+        )
+    }
+}
+
+/// Longest excerpt of a row kept in an `Anomaly`, in bytes
+pub const ANOMALY_EXCERPT: usize = 80;
+
+/// Summary of an import
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct ImportReport {
+    /// Number of edges added to the graph
+    pub edges: usize,
+    /// Number of malformed rows skipped in lenient mode
+    pub skipped: usize,
+    /// Line number (starting at 1) of the first skipped row
+    pub first_skipped_line: Option<usize>,
+    /// Number of self loops added
+    pub self_loops: usize,
+    /// Number of rows ignored because their edge was already in the
+    /// graph, see `Pagerank::set_unique_edges`
+    pub duplicates: usize,
+    /// Number of rows skipped for an oversized key, also counted in
+    /// `skipped`
+    pub oversized_keys: usize,
+    /// First anomalies of each kind, up to
+    /// `ImportOptions::max_anomalies`, in the order they were found
+    pub anomalies: Vec<Anomaly>,
+    /// Number of anomalies left out of `anomalies` by the limit
+    pub suppressed_anomalies: usize,
+}
+
+impl ImportReport {
+    /// Records an anomaly unless its kind already reached the limit
+    fn record(
+        &mut self,
+        kind: AnomalyKind,
+        line: usize,
+        row: &[u8],
+        limit: usize,
+    ) {
+        let recorded =
+            self.anomalies.iter().filter(|a| a.kind == kind).count();
+        if recorded >= limit {
+            self.suppressed_anomalies += 1;
+            return;
+        }
+
+        let row = trim_line(row);
+        let excerpt = &row[..row.len().min(ANOMALY_EXCERPT)];
+        self.anomalies.push(Anomaly {
+            kind,
+            line,
+            excerpt: String::from_utf8_lossy(excerpt).into_owned(),
+        });
+    }
+}
+
+/// Error returned by `Pagerank::import_tsv`
+#[derive(Debug)]
+pub enum ImportError {
+    /// The input could not be read
+    Io(io::Error),
+    /// A row is malformed. Only returned in strict mode.
+    Malformed {
+        /// Line number of the row, starting at 1
+        line: usize,
+    },
+}
+
+impl fmt::Display for ImportError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ImportError::Io(err) => write!(f, "{}", err),
+            ImportError::Malformed { line } => {
+                write!(f, "malformed row at line {}", line)
+            }
+        }
+    }
+}
+
+impl Error for ImportError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            ImportError::Io(err) => Some(err),
+            ImportError::Malformed { .. } => None,
+        }
+    }
+}
+
+impl From<io::Error> for ImportError {
+    fn from(err: io::Error) -> Self {
+        ImportError::Io(err)
+    }
+}
+
+impl<T> Pagerank<T>
+where
+    T: Eq + Hash + Clone + FromStr,
+{
+    /// Creates a graph from an edge list, with an edge for every row
+    /// of `reader`, such as a CSV (`','`) or TSV (`'\t'`) file, whose
+    /// source and target are in the given zero based columns.
+    ///
+    /// Malformed rows are reported with their line number, see
+    /// `import_tsv`, which takes more options.
+    pub fn from_edge_list_reader<R: BufRead>(
+        reader: R,
+        delimiter: char,
+        source_column: usize,
+        target_column: usize,
+    ) -> Result<Pagerank<T>, ImportError> {
+        let options = ImportOptions {
+            delimiter,
+            source_column,
+            target_column,
+            ..ImportOptions::default()
+        };
+        let mut pr = Pagerank::new();
+        pr.import_tsv(reader, &options)?;
+        Ok(pr)
+    }
+}
+
+impl<T, S> Pagerank<T, S>
+where
+    T: Eq + Hash + Clone + FromStr,
+    S: BuildHasher,
+{
+    /// Adds an edge for every row of a delimited input, tab separated
+    /// unless `options` says otherwise.
+    ///
+    /// A row is malformed if it lacks one of the configured columns, one
+    /// of them cannot be parsed as a node, it has a key longer than
+    /// `max_key_len`, or it is not valid UTF-8. In strict mode the
+    /// import stops there, keeping the edges already added; in lenient
+    /// mode the row is skipped and counted.
+    ///
+    /// Skipped rows and self loops are reported as anomalies, so data
+    /// quality issues are visible without inspecting the input.
+    pub fn import_tsv<R: BufRead>(
+        &mut self,
+        mut reader: R,
+        options: &ImportOptions,
+    ) -> Result<ImportReport, ImportError> {
+        let mut report = ImportReport::default();
+        let mut buf = Vec::new();
+        let mut line = 0;
+
+        loop {
+            buf.clear();
+            if reader.read_until(b'\n', &mut buf)? == 0 {
+                break;
+            }
+            line += 1;
+            if line == 1 && options.skip_header {
+                continue;
+            }
+
+            let kind = match parse_row::<T>(&buf, options) {
+                Ok((source, target)) => {
+                    if self.is_duplicate_edge(&source, &target) {
+                        report.duplicates += 1;
+                        continue;
+                    }
+                    if source == target {
+                        report.self_loops += 1;
+                        report.record(
+                            AnomalyKind::SelfLoop,
+                            line,
+                            &buf,
+                            options.max_anomalies,
+                        );
+                    }
+                    self.add_edge(source, target);
+                    report.edges += 1;
+                    continue;
+                }
+                Err(_) if options.mode == ParseMode::Strict => {
+                    return Err(ImportError::Malformed { line });
+                }
+                Err(kind) => kind,
+            };
+
+            report.skipped += 1;
+            report.first_skipped_line.get_or_insert(line);
+            if kind == AnomalyKind::OversizedKey {
+                report.oversized_keys += 1;
+            }
+            report.record(kind, line, &buf, options.max_anomalies);
+        }
+
+        Ok(report)
+    }
+}
+
+/// Strips the line break at the end of a row
+fn trim_line(buf: &[u8]) -> &[u8] {
+    let mut end = buf.len();
+    while end > 0 && (buf[end - 1] == b'\n' || buf[end - 1] == b'\r')
+    {
+        end -= 1;
+    }
+    &buf[..end]
+}
+
+/// Parses the source and target of a row, or tells why it can't
+fn parse_row<T: FromStr>(
+    buf: &[u8],
+    options: &ImportOptions,
+) -> Result<(T, T), AnomalyKind> {
+    let row = std::str::from_utf8(trim_line(buf))
+        .map_err(|_| AnomalyKind::Unparsable)?;
+    let columns = row.split(options.delimiter).collect::<Vec<&str>>();
+    let column = |i: usize| {
+        let key = columns.get(i).ok_or(AnomalyKind::Unparsable)?;
+        match options.max_key_len {
+            Some(max) if key.len() > max => {
+                Err(AnomalyKind::OversizedKey)
+            }
+            _ => key.parse().map_err(|_| AnomalyKind::Unparsable),
+        }
+    };
+    let source = column(options.source_column)?;
+    let target = column(options.target_column)?;
+
+    Ok((source, target))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const INPUT: &[u8] = b"from\tto\n1\t2\n2\tx\n3\n3\t1\n";
+
+    #[test]
+    fn test_import_lenient() {
+        let mut pr = Pagerank::<u32>::new();
+        let options = ImportOptions {
+            skip_header: true,
+            mode: ParseMode::Lenient,
+            ..ImportOptions::default()
+        };
+
+        let report = pr.import_tsv(INPUT, &options).expect("import");
+        assert_eq!(2, report.edges);
+        assert_eq!(2, report.skipped);
+        assert_eq!(Some(3), report.first_skipped_line);
+        assert_eq!(2, pr.len_node());
+    }
+
+    #[test]
+    fn test_import_strict() {
+        let mut pr = Pagerank::<u32>::new();
+        let options = ImportOptions {
+            skip_header: true,
+            ..ImportOptions::default()
+        };
+
+        match pr.import_tsv(INPUT, &options) {
+            Err(ImportError::Malformed { line }) => {
+                assert_eq!(3, line)
+            }
+            other => panic!("unexpected {:?}", other),
+        }
+        assert_eq!(1, pr.len_node());
+
+        let mut pr = Pagerank::<String>::new();
+        let options = ImportOptions {
+            source_column: 1,
+            target_column: 0,
+            ..ImportOptions::default()
+        };
+        let report = pr.import_tsv(&b"a\tb\n"[..], &options).unwrap();
+        assert_eq!(1, report.edges);
+        assert_eq!(Some(1), pr.get_out_edges("b".to_string()));
+    }
+
+    #[test]
+    fn test_import_anomalies() {
+        let mut input = b"a\tb\na\ta\nlong-key\tb\nc\n".to_vec();
+        for _ in 0..5 {
+            input.extend_from_slice(b"x\n");
+        }
+        input.extend_from_slice(b"b\tb\xff\n");
+
+        let mut pr = Pagerank::<String>::new();
+        let options = ImportOptions {
+            mode: ParseMode::Lenient,
+            max_key_len: Some(4),
+            max_anomalies: 2,
+            ..ImportOptions::default()
+        };
+        let report = pr.import_tsv(&input[..], &options).unwrap();
+        assert_eq!(2, report.edges);
+        assert_eq!(8, report.skipped);
+        assert_eq!(1, report.self_loops);
+        assert_eq!(1, report.oversized_keys);
+        assert_eq!(5, report.suppressed_anomalies);
+        assert_eq!(
+            vec![
+                (AnomalyKind::SelfLoop, 2),
+                (AnomalyKind::OversizedKey, 3),
+                (AnomalyKind::Unparsable, 4),
+                (AnomalyKind::Unparsable, 5),
+            ],
+            report
+                .anomalies
+                .iter()
+                .map(|a| (a.kind, a.line))
+                .collect::<Vec<_>>()
+        );
+        assert_eq!("long-key\tb", report.anomalies[1].excerpt);
+        assert_eq!(
+            "unparsable row at line 4: \"c\"",
+            report.anomalies[2].to_string()
+        );
+    }
+
+    #[test]
+    fn test_from_edge_list_reader() {
+        let input = &b"x,a,b\ny,b,c\r\nz,c,a\n"[..];
+        let pr = Pagerank::<String>::from_edge_list_reader(
+            input, ',', 2, 1,
+        )
+        .unwrap();
+        assert_eq!(3, pr.len());
+        assert_eq!(Some(1), pr.get_out_edges("b".to_string()));
+        assert_eq!(Some(1), pr.get_in_edges("a".to_string()));
+
+        let input = &b"1;2\n2;3\n3\n"[..];
+        match Pagerank::<u32>::from_edge_list_reader(input, ';', 0, 1)
+        {
+            Err(ImportError::Malformed { line }) => {
+                assert_eq!(3, line)
+            }
+            _ => panic!("malformed row expected"),
+        }
+    }
+}
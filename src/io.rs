@@ -0,0 +1,580 @@
+//! Binary snapshots of a graph and its scores
+use crate::frozen::write_zigzag;
+use crate::graph::Node;
+use crate::Pagerank;
+use std::fs::File;
+use std::hash::{BuildHasher, Hash};
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::path::Path;
+
+/// Magic bytes at the beginning of every binary snapshot
+const SNAPSHOT_MAGIC: &[u8; 4] = b"PRNK";
+
+/// Current version of the binary snapshot format. Version 2 added edge
+/// weights, version 3 the teleport settings: personalization, node
+/// types and their shares, and version 4 keeps the in edges in the order
+/// they were added instead of sorting them. Older snapshots are still
+/// read, with unit weights and uniform teleporting.
+const SNAPSHOT_VERSION: u8 = 4;
+
+/// Keys which can be stored in a binary snapshot.
+pub trait SnapshotKey: Sized {
+    /// Appends the binary representation of the key to `buf`
+    fn write_key(&self, buf: &mut Vec<u8>);
+
+    /// Reads a key from the beginning of `buf`. Returns the key and the
+    /// number of bytes consumed, or `None` if `buf` is malformed.
+    fn read_key(buf: &[u8]) -> Option<(Self, usize)>;
+}
+
+impl SnapshotKey for String {
+    fn write_key(&self, buf: &mut Vec<u8>) {
+        write_varint(buf, self.len() as u64);
+        buf.extend_from_slice(self.as_bytes());
+    }
+
+    fn read_key(buf: &[u8]) -> Option<(Self, usize)> {
+        let (len, read) = read_varint(buf)?;
+        let end = read.checked_add(len as usize)?;
+        let bytes = buf.get(read..end)?;
+        let key = String::from_utf8(bytes.to_vec()).ok()?;
+        Some((key, end))
+    }
+}
+
+macro_rules! snapshot_key_int {
+    ($($t:ty),*) => {
+        $(
+            impl SnapshotKey for $t {
+                fn write_key(&self, buf: &mut Vec<u8>) {
+                    buf.extend_from_slice(&self.to_le_bytes());
+                }
+
+                fn read_key(buf: &[u8]) -> Option<(Self, usize)> {
+                    const SIZE: usize = std::mem::size_of::<$t>();
+                    let mut bytes = [0u8; SIZE];
+                    bytes.copy_from_slice(buf.get(..SIZE)?);
+                    Some((<$t>::from_le_bytes(bytes), SIZE))
+                }
+            }
+        )*
+    };
+}
+
+snapshot_key_int!(u8, u16, u32, u64, u128, i8, i16, i32, i64, i128);
+
+/// Appends `value` as a LEB128 variable-length integer
+fn write_varint(buf: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        buf.push((value as u8) | 0x80);
+        value >>= 7;
+    }
+    buf.push(value as u8);
+}
+
+/// Reads a LEB128 variable-length integer. Returns the value and the
+/// number of bytes consumed.
+fn read_varint(buf: &[u8]) -> Option<(u64, usize)> {
+    let mut value = 0u64;
+    for (i, byte) in buf.iter().enumerate().take(10) {
+        value |= ((byte & 0x7f) as u64) << (7 * i);
+        if byte & 0x80 == 0 {
+            return Some((value, i + 1));
+        }
+    }
+    None
+}
+
+/// Appends the in edges of node `id`, in their order, as varints of the
+/// zigzag encoded difference from the previous source (`id` for the
+/// first one).
+///
+/// Neighbor ids of a node tend to be close to each other, so the gaps
+/// between them take far fewer bytes than the ids themselves. They are
+/// not sorted, so a loaded graph sums the contributions of the in edges
+/// in the same order, and gets the same scores to the last bit.
+fn write_delta_list(buf: &mut Vec<u8>, id: usize, ids: &[usize]) {
+    write_varint(buf, ids.len() as u64);
+    let mut previous = id;
+    for source in ids {
+        write_zigzag(buf, *source as i64 - previous as i64);
+        previous = *source;
+    }
+}
+
+/// FNV-1a 64 bits hash, used as the snapshot integrity checksum
+pub(crate) fn checksum(buf: &[u8]) -> u64 {
+    buf.iter().fold(0xcbf29ce484222325, |hash, byte| {
+        (hash ^ *byte as u64).wrapping_mul(0x100000001b3)
+    })
+}
+
+/// Cursor over the bytes of a snapshot being decoded
+struct SnapshotReader<'a> {
+    buf: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> SnapshotReader<'a> {
+    fn varint(&mut self) -> io::Result<u64> {
+        let (value, read) = read_varint(&self.buf[self.pos..])
+            .ok_or_else(|| invalid_snapshot("bad varint"))?;
+        self.pos += read;
+        Ok(value)
+    }
+
+    /// Reads the in edges of node `id` written by `write_delta_list`,
+    /// checking they are below `len`. Snapshots before version 4 have
+    /// them sorted, as plain differences from the previous one.
+    fn delta_list(
+        &mut self,
+        id: usize,
+        len: usize,
+        version: u8,
+    ) -> io::Result<Vec<usize>> {
+        let edges = self.varint()? as usize;
+        let mut ids = Vec::with_capacity(edges.min(self.buf.len()));
+        let mut previous = if version >= 4 { id as i64 } else { 0 };
+        for _ in 0..edges {
+            let delta = self.varint()?;
+            let delta = if version >= 4 {
+                (delta >> 1) as i64 ^ -((delta & 1) as i64)
+            } else {
+                delta as i64
+            };
+            previous = previous
+                .checked_add(delta)
+                .filter(|id| *id >= 0 && (*id as u64) < len as u64)
+                .ok_or_else(|| invalid_snapshot("bad edge"))?;
+            ids.push(previous as usize);
+        }
+        Ok(ids)
+    }
+
+    fn f64(&mut self) -> io::Result<f64> {
+        let bytes = self
+            .buf
+            .get(self.pos..self.pos + 8)
+            .ok_or_else(|| invalid_snapshot("truncated float"))?;
+        let mut value = [0u8; 8];
+        value.copy_from_slice(bytes);
+        self.pos += 8;
+        Ok(f64::from_le_bytes(value))
+    }
+
+    fn node_type(&mut self) -> io::Result<u16> {
+        let node_type = self.varint()?;
+        if node_type > u16::MAX as u64 {
+            return Err(invalid_snapshot("bad node type"));
+        }
+        Ok(node_type as u16)
+    }
+
+    fn key<T: SnapshotKey>(&mut self) -> io::Result<T> {
+        let (key, read) = T::read_key(&self.buf[self.pos..])
+            .ok_or_else(|| invalid_snapshot("bad node key"))?;
+        self.pos += read;
+        Ok(key)
+    }
+}
+
+fn invalid_snapshot(reason: &str) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!("invalid snapshot: {}", reason),
+    )
+}
+
+impl<T, S> Pagerank<T, S>
+where
+    T: Eq + Hash + Clone + SnapshotKey,
+    S: BuildHasher,
+{
+    /// Writes the graph and its current scores as a compact binary
+    /// snapshot, along with the damping factor, the in edges cap and the
+    /// teleport settings, so a built graph can be reloaded without adding
+    /// its edges again.
+    ///
+    /// The snapshot is versioned and ends with a checksum of its content,
+    /// which is verified by `read_snapshot`. The edge log, open batches,
+    /// stale decay and weight closure are not part of it.
+    pub fn write_snapshot<W: Write>(
+        &self,
+        mut writer: W,
+    ) -> io::Result<()> {
+        let mut buf = Vec::new();
+        buf.extend_from_slice(SNAPSHOT_MAGIC);
+        buf.push(SNAPSHOT_VERSION);
+        buf.extend_from_slice(&self.damping.to_le_bytes());
+        write_varint(&mut buf, self.edges);
+        write_varint(&mut buf, self.in_edges_cap.unwrap_or(0) as u64);
+        write_varint(&mut buf, self.nodes.len() as u64);
+
+        for (id, node) in self.nodes.iter().enumerate() {
+            node.node.write_key(&mut buf);
+            buf.extend_from_slice(&node.score.to_le_bytes());
+            write_varint(&mut buf, node.out_edges as u64);
+            write_varint(&mut buf, node.in_degree as u64);
+            write_delta_list(&mut buf, id, &self.in_edges(id));
+            buf.extend_from_slice(&node.out_weight.to_le_bytes());
+            write_varint(&mut buf, node.in_weights.len() as u64);
+            for weight in node.in_weights.iter() {
+                buf.extend_from_slice(&weight.to_le_bytes());
+            }
+        }
+
+        write_varint(&mut buf, self.personalization.len() as u64);
+        for (id, weight) in self.personalization.iter() {
+            write_varint(&mut buf, *id as u64);
+            buf.extend_from_slice(&weight.to_le_bytes());
+        }
+        write_varint(&mut buf, self.node_types.len() as u64);
+        for node_type in self.node_types.iter() {
+            write_varint(&mut buf, *node_type as u64);
+        }
+        write_varint(&mut buf, self.teleport_shares.len() as u64);
+        for (node_type, share) in self.teleport_shares.iter() {
+            write_varint(&mut buf, *node_type as u64);
+            buf.extend_from_slice(&share.to_le_bytes());
+        }
+
+        let sum = checksum(&buf);
+        buf.extend_from_slice(&sum.to_le_bytes());
+        writer.write_all(&buf)
+    }
+
+    /// Writes a binary snapshot of the graph to the file at `path`, see
+    /// `write_snapshot`. Reloading it with `load` is much faster than
+    /// parsing the edge list again, since nodes come with their in
+    /// edges and scores, and no edge is looked up.
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        let mut file = BufWriter::new(File::create(path)?);
+        self.write_snapshot(&mut file)?;
+        file.flush()
+    }
+}
+
+impl<T> Pagerank<T>
+where
+    T: Eq + Hash + Clone + SnapshotKey,
+{
+    /// Reads a graph saved with `save` from the file at `path`
+    pub fn load<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        Pagerank::read_snapshot(BufReader::new(File::open(path)?))
+    }
+
+    /// Reads a graph previously written with `write_snapshot`
+    pub fn read_snapshot<R: Read>(mut reader: R) -> io::Result<Self> {
+        let mut buf = Vec::new();
+        reader.read_to_end(&mut buf)?;
+
+        if buf.len() < SNAPSHOT_MAGIC.len() + 1 + 8
+            || &buf[..SNAPSHOT_MAGIC.len()] != SNAPSHOT_MAGIC
+        {
+            return Err(invalid_snapshot("bad magic bytes"));
+        }
+
+        let (content, sum) = buf.split_at(buf.len() - 8);
+        let mut expected = [0u8; 8];
+        expected.copy_from_slice(sum);
+        if checksum(content) != u64::from_le_bytes(expected) {
+            return Err(invalid_snapshot("checksum mismatch"));
+        }
+
+        let version = content[SNAPSHOT_MAGIC.len()];
+        if version == 0 || version > SNAPSHOT_VERSION {
+            return Err(invalid_snapshot("unsupported version"));
+        }
+
+        let mut reader = SnapshotReader {
+            buf: content,
+            pos: SNAPSHOT_MAGIC.len() + 1,
+        };
+
+        let mut pr = Pagerank::new();
+        pr.damping = reader.f64()?;
+        if !(0f64..1f64).contains(&pr.damping) {
+            return Err(invalid_snapshot("bad damping factor"));
+        }
+        pr.edges = reader.varint()?;
+        pr.in_edges_cap = match reader.varint()? {
+            0 => None,
+            cap => Some(cap as usize),
+        };
+        let len = reader.varint()? as usize;
+        // Every node takes at least a byte, so a corrupt length can't
+        // reserve more than the size of the snapshot
+        let capacity = len.min(content.len());
+        pr.nodes.reserve(capacity);
+        pr.node_positions.reserve(capacity);
+
+        for id in 0..len {
+            let key: T = reader.key()?;
+            let score = reader.f64()?;
+            let out_edges = reader.varint()? as usize;
+            let in_degree = reader.varint()? as usize;
+            let in_edges = reader.delta_list(id, len, version)?;
+            // Stored in edges are a sample of the counted ones
+            if in_degree < in_edges.len() {
+                return Err(invalid_snapshot("bad in degree"));
+            }
+
+            let mut out_weight = out_edges as f64;
+            let mut in_weights = Vec::new();
+            if version >= 2 {
+                out_weight = reader.f64()?;
+                let weights = reader.varint()? as usize;
+                if weights != 0 && weights != in_edges.len() {
+                    return Err(invalid_snapshot("bad edge weights"));
+                }
+                for _ in 0..weights {
+                    in_weights.push(reader.f64()?);
+                }
+            }
+
+            if pr.node_positions.insert(key.clone(), id).is_some() {
+                return Err(invalid_snapshot("duplicated node"));
+            }
+            pr.nodes.push(Node {
+                node: key,
+                in_edges,
+                in_degree,
+                out_edges,
+                in_weights,
+                out_weight,
+                score,
+            });
+        }
+
+        if version >= 3 {
+            for _ in 0..reader.varint()? {
+                let id = reader.varint()? as usize;
+                if id >= len {
+                    return Err(invalid_snapshot(
+                        "bad personalization",
+                    ));
+                }
+                let weight = reader.f64()?;
+                pr.personalization.push((id, weight));
+            }
+            for _ in 0..reader.varint()? {
+                let node_type = reader.node_type()?;
+                pr.node_types.push(node_type);
+            }
+            if pr.node_types.len() > len {
+                return Err(invalid_snapshot("bad node types"));
+            }
+            for _ in 0..reader.varint()? {
+                let node_type = reader.node_type()?;
+                let share = reader.f64()?;
+                pr.teleport_shares.push((node_type, share));
+            }
+        }
+
+        if reader.pos != content.len() {
+            return Err(invalid_snapshot("trailing bytes"));
+        }
+
+        Ok(pr)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_snapshot_roundtrip() {
+        let mut pr = Pagerank::<String>::new();
+        pr.add_edge("foo".to_string(), "bar".to_string());
+        pr.add_edge("bar".to_string(), "foo".to_string());
+        pr.add_edge("xxx".to_string(), "bar".to_string());
+        pr.calculate();
+
+        let mut buf = Vec::new();
+        pr.write_snapshot(&mut buf).expect("write");
+        let loaded = Pagerank::<String>::read_snapshot(&buf[..])
+            .expect("read");
+
+        assert_eq!(pr.len(), loaded.len());
+        assert_eq!(pr.len_node(), loaded.len_node());
+        assert_eq!(
+            pr.get_score("bar".to_string()),
+            loaded.get_score("bar".to_string())
+        );
+        assert_eq!(Some(2), loaded.get_in_edges("bar".to_string()));
+    }
+
+    #[test]
+    fn test_snapshot_weights() {
+        let mut pr = Pagerank::<u32>::new();
+        pr.add_edge(1, 2);
+        pr.add_edge_weighted(3, 2, 2.5).unwrap();
+        pr.add_edge(1, 3);
+
+        let mut buf = Vec::new();
+        pr.write_snapshot(&mut buf).expect("write");
+        let mut loaded =
+            Pagerank::<u32>::read_snapshot(&buf[..]).expect("read");
+
+        pr.calculate();
+        loaded.calculate();
+        assert_eq!(pr.get_score(2), loaded.get_score(2));
+        assert_eq!(pr.get_score(3), loaded.get_score(3));
+    }
+
+    #[test]
+    fn test_snapshot_checksum() {
+        let mut pr = Pagerank::<u32>::new();
+        pr.add_edge(1, 2);
+
+        let mut buf = Vec::new();
+        pr.write_snapshot(&mut buf).expect("write");
+        buf[6] ^= 0xff;
+
+        assert!(Pagerank::<u32>::read_snapshot(&buf[..]).is_err());
+    }
+
+    #[test]
+    fn test_snapshot_validation() {
+        let read = |pr: &Pagerank<u32>| {
+            let mut buf = Vec::new();
+            pr.write_snapshot(&mut buf).expect("write");
+            Pagerank::<u32>::read_snapshot(&buf[..])
+                .map(|_| ())
+                .map_err(|err| err.to_string())
+        };
+
+        let graph = || {
+            let mut pr = Pagerank::<u32>::new();
+            pr.add_edge(1, 2);
+            pr.add_edge(3, 2);
+            pr
+        };
+        assert_eq!(Ok(()), read(&graph()));
+
+        for damping in [-0.1, 1.0, 1.5, f64::NAN].iter() {
+            let mut bad = graph();
+            bad.damping = *damping;
+            assert_eq!(
+                Err("invalid snapshot: bad damping factor"
+                    .to_string()),
+                read(&bad)
+            );
+        }
+
+        let mut bad = graph();
+        bad.nodes[1].in_degree = 1;
+        assert_eq!(
+            Err("invalid snapshot: bad in degree".to_string()),
+            read(&bad)
+        );
+    }
+
+    #[test]
+    fn test_delta_list() {
+        let mut buf = Vec::new();
+        write_delta_list(&mut buf, 5, &[300, 7, 301, 7]);
+        assert_eq!(vec![4, 206, 4, 201, 4, 204, 4, 203, 4], buf);
+
+        let mut reader = SnapshotReader { buf: &buf, pos: 0 };
+        assert_eq!(
+            vec![300, 7, 301, 7],
+            reader.delta_list(5, 302, 4).unwrap()
+        );
+        let mut reader = SnapshotReader { buf: &buf, pos: 0 };
+        assert!(reader.delta_list(5, 301, 4).is_err());
+
+        // Before version 4 lists are sorted
+        let sorted = [4, 7, 0, 165, 2, 1];
+        let mut reader = SnapshotReader {
+            buf: &sorted,
+            pos: 0,
+        };
+        assert_eq!(
+            vec![7, 7, 300, 301],
+            reader.delta_list(0, 302, 3).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_snapshot_edge_order() {
+        // Random weighted edges, so sources come in no particular order
+        let mut pr = Pagerank::<u64>::new();
+        let mut rng = 7u64;
+        for source in 0..1800 {
+            rng =
+                rng.wrapping_mul(6364136223846793005).wrapping_add(1);
+            let target = (rng >> 33) % 300;
+            let weight = 1.0 + (rng >> 44) as f64 / 1e6;
+            pr.add_edge_weighted(source % 300, target, weight)
+                .unwrap();
+        }
+        pr.add_edge(3, 9);
+        pr.add_edge_weighted(3, 9, 2.0).unwrap();
+
+        let mut buf = Vec::new();
+        pr.write_snapshot(&mut buf).expect("write");
+        let mut loaded =
+            Pagerank::<u64>::read_snapshot(&buf[..]).expect("read");
+        pr.calculate_with_convergence(1e-13);
+        loaded.calculate_with_convergence(1e-13);
+        assert_eq!(pr.nodes(), loaded.nodes());
+
+        // The last one added of duplicated edges is removed
+        assert!(pr.remove_edge(&3, &9));
+        assert!(loaded.remove_edge(&3, &9));
+        let id = pr.node_id(&9).unwrap();
+        assert_eq!(pr.nodes[id].in_edges, loaded.nodes[id].in_edges);
+        assert_eq!(
+            pr.nodes[id].in_weights,
+            loaded.nodes[id].in_weights
+        );
+    }
+
+    #[test]
+    fn test_snapshot_teleport() {
+        let mut pr = Pagerank::<u128>::new();
+        pr.add_edge(1, 2);
+        pr.add_edge(2, 3);
+        pr.add_edge(3, 1);
+        pr.set_node_type(3, 7);
+        pr.set_teleport_shares(vec![(0, 1.0), (7, 3.0)]);
+        pr.set_personalization(vec![(2, 1.0)]);
+        pr.calculate();
+
+        let mut buf = Vec::new();
+        pr.write_snapshot(&mut buf).expect("write");
+        let mut loaded =
+            Pagerank::<u128>::read_snapshot(&buf[..]).expect("read");
+        assert_eq!(pr.personalization(), loaded.personalization());
+        assert_eq!(Some(7), loaded.node_type(&3));
+
+        loaded.set_personalization(Vec::new());
+        pr.set_personalization(Vec::new());
+        pr.calculate();
+        loaded.calculate();
+        assert_eq!(pr.nodes(), loaded.nodes());
+    }
+
+    #[test]
+    fn test_save_load() {
+        let mut pr = Pagerank::<u64>::new();
+        for i in 0..100 {
+            pr.add_edge(i, (i * 7 + 1) % 100);
+            pr.add_edge(i, (i * 3 + 2) % 100);
+        }
+        pr.calculate();
+        let path = std::env::temp_dir().join(format!(
+            "pagerank-save-{}.bin",
+            std::process::id()
+        ));
+        pr.save(&path).unwrap();
+
+        let loaded = Pagerank::<u64>::load(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(pr.nodes(), loaded.nodes());
+        assert_eq!(pr.len_node(), loaded.len_node());
+        assert!(Pagerank::<u64>::load(&path).is_err());
+    }
+}
@@ -0,0 +1,520 @@
+//! Queries over the graph and its scores
+#[cfg(not(feature = "std"))]
+use crate::compat::prelude::*;
+use crate::compat::{HashMap, HashSet};
+use crate::graph::cmp_scores;
+use crate::transform::transform as transform_score;
+use crate::Pagerank;
+use core::cmp::Ordering;
+use core::hash::{BuildHasher, Hash};
+
+impl<T, S> Pagerank<T, S>
+where
+    T: Eq + Hash + Clone,
+    S: BuildHasher,
+{
+    /// Returns a copy of the current scores, which can be queried and
+    /// combined with other computations over the same graph
+    pub fn score_set(&self) -> ScoreSet<T> {
+        ScoreSet {
+            positions: self
+                .node_positions
+                .iter()
+                .map(|(node, id)| (node.clone(), *id))
+                .collect(),
+            scores: self
+                .nodes
+                .iter()
+                .map(|n| self.transformed(n.score))
+                .collect(),
+        }
+    }
+
+    /// Returns the `k` highest scored nodes, highest first, in the same
+    /// order as the head of `nodes`.
+    ///
+    /// Only the selected nodes are sorted: the `k` best ones are found
+    /// in linear time, so it is much faster than `nodes` when `k` is a
+    /// small part of a large graph.
+    pub fn top_k(&self, k: usize) -> Vec<(&T, f64)> {
+        self.top_k_where(k, (0..self.nodes.len()).collect())
+    }
+
+    /// Returns the `k` highest scored nodes which are not in `visited`,
+    /// highest first, for crawlers which fetch the most promising pages
+    /// next: fetch the frontier, add the new edges, recalculate (for
+    /// instance with `update_after_changes`) and ask again.
+    ///
+    /// It takes linear time like `top_k`, with a set lookup per node.
+    pub fn frontier(
+        &self,
+        k: usize,
+        visited: &HashSet<T>,
+    ) -> Vec<(&T, f64)> {
+        let ids = (0..self.nodes.len())
+            .filter(|id| !visited.contains(&self.nodes[*id].node))
+            .collect();
+        self.top_k_where(k, ids)
+    }
+
+    /// Returns the `k` highest scored nodes among `ids`, highest first
+    fn top_k_where(
+        &self,
+        k: usize,
+        mut ids: Vec<usize>,
+    ) -> Vec<(&T, f64)> {
+        if k == 0 {
+            return Vec::new();
+        }
+
+        let nodes = &self.nodes;
+        let order = |a: &usize, b: &usize| {
+            cmp_scores(nodes[*b].score, nodes[*a].score)
+                .then(a.cmp(b))
+        };
+
+        if k < ids.len() {
+            ids.select_nth_unstable_by(k - 1, order);
+            ids.truncate(k);
+        }
+        ids.sort_by(order);
+
+        ids.into_iter()
+            .map(|id| {
+                (&nodes[id].node, self.transformed(nodes[id].score))
+            })
+            .collect()
+    }
+
+    /// Fills `top` with the `k` highest scored nodes, highest first,
+    /// replacing its previous content.
+    ///
+    /// It does not allocate as long as `top` has room for `k` nodes, so
+    /// a hot service can reuse the same vector across queries.
+    pub fn top_k_into<'a>(
+        &'a self,
+        k: usize,
+        top: &mut Vec<(&'a T, f64)>,
+    ) {
+        top.clear();
+        if k == 0 {
+            return;
+        }
+
+        for n in self.nodes.iter() {
+            let score = self.transformed(n.score);
+            if top.len() == k {
+                if cmp_scores(top[k - 1].1, score) != Ordering::Less {
+                    continue;
+                }
+                top.pop();
+            }
+
+            let at = top
+                .iter()
+                .position(|(_, other)| {
+                    cmp_scores(*other, score) == Ordering::Less
+                })
+                .unwrap_or(top.len());
+            top.insert(at, (&n.node, score));
+        }
+    }
+
+    /// Consumes the graph keeping only the `k` highest scored nodes and a
+    /// few aggregate statistics, for pipelines which only need the head
+    /// of the ranking and want the memory of the long tail back as soon
+    /// as the scores are calculated.
+    pub fn into_top_k(self, k: usize) -> TopK<T> {
+        let mut result = TopK {
+            top: Vec::with_capacity(k.min(self.nodes.len())),
+            nodes: self.nodes.len(),
+            edges: self.edges,
+            total_score: 0f64,
+            min_score: f64::INFINITY,
+            max_score: f64::NEG_INFINITY,
+        };
+
+        let transform = self.score_transform;
+        for n in self.nodes.into_iter() {
+            let score = transform_score(transform.as_ref(), n.score);
+            result.total_score += score;
+            result.min_score = result.min_score.min(score);
+            result.max_score = result.max_score.max(score);
+            if k == 0 {
+                continue;
+            }
+
+            if result.top.len() == k {
+                if cmp_scores(result.top[k - 1].1, score)
+                    != Ordering::Less
+                {
+                    continue;
+                }
+                result.top.pop();
+            }
+
+            let at = result
+                .top
+                .iter()
+                .position(|(_, other)| {
+                    cmp_scores(*other, score) == Ordering::Less
+                })
+                .unwrap_or(result.top.len());
+            result.top.insert(at, (n.node, score));
+        }
+
+        result
+    }
+
+    /// Copies the score of every node, in insertion order (the `id` of
+    /// `iter_nodes`), into `scores` without allocating. Returns the
+    /// number of scores written, which is less than the number of nodes
+    /// if `scores` is too short.
+    pub fn write_scores_into(&self, scores: &mut [f64]) -> usize {
+        let len = scores.len().min(self.nodes.len());
+        for (score, n) in scores.iter_mut().zip(self.nodes.iter()) {
+            *score = self.transformed(n.score);
+        }
+        len
+    }
+
+    /// Aggregates the scores of groups of aliased nodes, for instance all
+    /// the pages of a domain, to rank the groups themselves.
+    ///
+    /// `group_of` returns the group of each node, or `None` for nodes
+    /// which are left out. Returns every group with its aggregated
+    /// score, sorted by it; per node scores are still given by `nodes`.
+    pub fn group_scores<G, F>(
+        &self,
+        group_of: F,
+        aggregate: Aggregate,
+    ) -> Vec<(G, f64)>
+    where
+        G: Eq + Hash,
+        F: Fn(&T) -> Option<G>,
+    {
+        let mut groups = HashMap::new();
+        for n in self.nodes.iter() {
+            if let Some(group) = group_of(&n.node) {
+                let first = groups.len();
+                let initial = match aggregate {
+                    Aggregate::Sum => 0f64,
+                    Aggregate::Max => f64::MIN,
+                };
+                let (_, score) =
+                    groups.entry(group).or_insert((first, initial));
+                *score = match aggregate {
+                    Aggregate::Sum => {
+                        *score + self.transformed(n.score)
+                    }
+                    Aggregate::Max => {
+                        score.max(self.transformed(n.score))
+                    }
+                };
+            }
+        }
+
+        // Ties are ranked by first appearance, not by hash order
+        let mut groups = groups.into_iter().collect::<Vec<_>>();
+        groups.sort_by(|(_, a), (_, b)| {
+            cmp_scores(b.1, a.1).then(a.0.cmp(&b.0))
+        });
+
+        groups
+            .into_iter()
+            .map(|(group, (_, score))| (group, score))
+            .collect()
+    }
+}
+
+/// Head of a ranking and aggregate statistics of the whole graph, as
+/// returned by `Pagerank::into_top_k`
+#[derive(Debug, Clone, PartialEq)]
+pub struct TopK<T> {
+    /// Highest scored nodes, highest first
+    pub top: Vec<(T, f64)>,
+    /// Number of nodes of the graph
+    pub nodes: usize,
+    /// Number of edges of the graph
+    pub edges: u64,
+    /// Sum of the scores of every node
+    pub total_score: f64,
+    /// Lowest score, infinity for an empty graph
+    pub min_score: f64,
+    /// Highest score, minus infinity for an empty graph
+    pub max_score: f64,
+}
+
+/// How `Pagerank::group_scores` combines the scores of a group
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Aggregate {
+    /// Sum of the scores of the group members
+    Sum,
+    /// Highest score among the group members
+    Max,
+}
+
+/// Scores of every node of a graph, detached from the graph itself.
+///
+/// A score set is taken with `Pagerank::score_set` and can be combined
+/// with another score set of the same graph, for instance to blend the
+/// global PageRank with a personalized run.
+#[derive(Clone)]
+pub struct ScoreSet<T>
+where
+    T: Eq + Hash + Clone,
+{
+    /// Position of each node in `scores`
+    pub(crate) positions: HashMap<T, usize>,
+    /// Score of each node
+    pub(crate) scores: Vec<f64>,
+}
+
+impl<T> ScoreSet<T>
+where
+    T: Eq + Hash + Clone,
+{
+    /// Returns the score of a given node
+    pub fn get(&self, node: &T) -> Option<f64> {
+        self.positions.get(node).map(|id| self.scores[*id])
+    }
+
+    /// Return all nodes, sorted by their score. Ties keep the order of
+    /// the graph the scores were taken from.
+    pub fn nodes(&self) -> Vec<(&T, f64)> {
+        let mut nodes = self
+            .positions
+            .iter()
+            .map(|(node, id)| (*id, node))
+            .collect::<Vec<(usize, &T)>>();
+
+        nodes.sort_by(|a, b| {
+            cmp_scores(self.scores[b.0], self.scores[a.0])
+                .then(a.0.cmp(&b.0))
+        });
+
+        nodes
+            .into_iter()
+            .map(|(id, node)| (node, self.scores[id]))
+            .collect()
+    }
+
+    /// Combines the score of every node with its score in `other`,
+    /// producing a new score set. Nodes which are not part of both sets
+    /// are left out.
+    pub fn combine<F>(&self, other: &ScoreSet<T>, f: F) -> ScoreSet<T>
+    where
+        F: Fn(f64, f64) -> f64,
+    {
+        let mut positions = HashMap::new();
+        let mut scores = Vec::new();
+
+        let mut nodes = self.positions.iter().collect::<Vec<_>>();
+        nodes.sort_by_key(|(_, id)| **id);
+        for (node, id) in nodes {
+            if let Some(score) = other.get(node) {
+                positions.insert(node.clone(), scores.len());
+                scores.push(f(self.scores[*id], score));
+            }
+        }
+
+        ScoreSet { positions, scores }
+    }
+
+    /// Return the number of nodes in the set
+    pub fn len(&self) -> usize {
+        self.scores.len()
+    }
+
+    /// If the set is empty
+    pub fn is_empty(&self) -> bool {
+        self.scores.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Aggregate, Pagerank};
+    use std::collections::HashSet;
+
+    #[test]
+    fn test_combine_score_sets() {
+        let mut pr = Pagerank::<&str>::new();
+        pr.add_edge("foo", "bar");
+        pr.add_edge("bar", "foo");
+        pr.add_edge("xxx", "bar");
+
+        let before = pr.score_set();
+        pr.calculate();
+        let after = pr.score_set();
+
+        let blend = before.combine(&after, |a, b| (a + b) / 2.0);
+        assert_eq!(3, blend.len());
+        assert_eq!(
+            Some(
+                (before.get(&"bar").unwrap()
+                    + pr.get_score("bar").unwrap())
+                    / 2.0
+            ),
+            blend.get(&"bar")
+        );
+        assert_eq!("bar", *blend.nodes()[0].0);
+    }
+
+    #[test]
+    fn test_top_k_into() {
+        let mut pr = Pagerank::<&str>::new();
+        pr.add_edge("foo", "bar");
+        pr.add_edge("bar", "foo");
+        pr.add_edge("xxx", "bar");
+        pr.add_edge("yyy", "bar");
+        pr.calculate();
+
+        let mut top = Vec::with_capacity(2);
+        pr.top_k_into(2, &mut top);
+        assert_eq!(2, top.capacity());
+        assert_eq!(pr.nodes()[..2].to_vec(), top,);
+
+        pr.top_k_into(10, &mut top);
+        assert_eq!(pr.nodes(), top);
+
+        let mut scores = [0f64; 2];
+        assert_eq!(2, pr.write_scores_into(&mut scores));
+        assert_eq!(pr.get_score("foo"), Some(scores[0]));
+        let mut scores = [0f64; 8];
+        assert_eq!(4, pr.write_scores_into(&mut scores));
+        assert_eq!(0.0, scores[4]);
+    }
+
+    #[test]
+    fn test_group_scores() {
+        let mut pr = Pagerank::<&str>::new();
+        pr.add_edge("a.com/1", "b.com/1");
+        pr.add_edge("a.com/2", "b.com/1");
+        pr.add_edge("b.com/1", "a.com/1");
+        pr.add_edge("b.com/1", "c.org/1");
+        pr.calculate();
+
+        let domain = |n: &&str| {
+            n.split('/')
+                .next()
+                .filter(|d| d.ends_with(".com"))
+                .map(|d| d.to_string())
+        };
+        let sum = pr.group_scores(domain, Aggregate::Sum);
+        assert_eq!(2, sum.len());
+        assert_eq!(
+            pr.get_score("a.com/1").unwrap()
+                + pr.get_score("a.com/2").unwrap(),
+            sum.iter().find(|(d, _)| *d == "a.com").unwrap().1
+        );
+
+        let max = pr.group_scores(domain, Aggregate::Max);
+        assert_eq!(
+            pr.get_score("a.com/1"),
+            max.iter().find(|(d, _)| *d == "a.com").map(|g| g.1)
+        );
+    }
+
+    #[test]
+    fn test_deterministic_output() {
+        let build = || {
+            let mut pr = Pagerank::<String>::new();
+            for i in 0..50 {
+                pr.add_edge(
+                    format!("hub{}", i % 3),
+                    format!("n{}", i),
+                );
+                pr.add_edge(
+                    format!("n{}", i),
+                    format!("hub{}", i % 3),
+                );
+            }
+            pr.calculate();
+            pr
+        };
+
+        let (a, b) = (build(), build());
+        let bits = |pr: &Pagerank<String>| {
+            pr.score_set()
+                .nodes()
+                .iter()
+                .map(|(n, s)| (n.to_string(), s.to_bits()))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(bits(&a), bits(&b));
+
+        let scores = a.score_set();
+        let ties = scores.nodes();
+        assert_eq!(
+            a.nodes().iter().map(|(n, _)| *n).collect::<Vec<_>>(),
+            ties.iter().map(|(n, _)| *n).collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn test_into_top_k() {
+        let mut pr = Pagerank::<&str>::new();
+        pr.add_edge("foo", "bar");
+        pr.add_edge("bar", "foo");
+        pr.add_edge("xxx", "bar");
+        pr.add_edge("yyy", "bar");
+        pr.calculate();
+
+        let expected = pr.nodes()[..2]
+            .iter()
+            .map(|(n, s)| (**n, *s))
+            .collect::<Vec<(&str, f64)>>();
+        let total = pr.nodes().iter().map(|(_, s)| s).sum::<f64>();
+
+        let head = pr.into_top_k(2);
+        assert_eq!(expected, head.top);
+        assert_eq!((4, 4), (head.nodes, head.edges));
+        assert_eq!(total, head.total_score);
+        assert_eq!(expected[0].1, head.max_score);
+        assert!((head.min_score - 0.15).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_top_k() {
+        let mut pr = Pagerank::<u32>::new();
+        for source in 0..200 {
+            pr.add_edge(source, source % 7);
+            pr.add_edge(source, source % 13);
+        }
+        pr.calculate();
+
+        let nodes = pr.nodes();
+        for k in [0, 1, 5, 20, 200, 500].iter() {
+            let top = pr.top_k(*k);
+            assert_eq!(&nodes[..(*k).min(nodes.len())], &top[..]);
+        }
+    }
+
+    #[test]
+    fn test_frontier() {
+        let mut pr = Pagerank::<&str>::new();
+        pr.add_edge("seed", "a");
+        pr.add_edge("seed", "b");
+        pr.add_edge("a", "b");
+        pr.add_edge("b", "c");
+        pr.calculate();
+
+        let mut visited = HashSet::new();
+        visited.insert("seed");
+        assert_eq!(pr.top_k(3)[..], pr.frontier(3, &visited)[..]);
+
+        visited.insert("c");
+        let frontier = pr.frontier(5, &visited);
+        assert_eq!(
+            vec!["b", "a"],
+            frontier.iter().map(|(n, _)| **n).collect::<Vec<_>>()
+        );
+        assert_eq!(pr.get_score("b"), Some(frontier[0].1));
+        assert!(pr.frontier(0, &visited).is_empty());
+
+        visited.insert("a");
+        visited.insert("b");
+        assert!(pr.frontier(5, &visited).is_empty());
+    }
+}
@@ -0,0 +1,224 @@
+//! Nodes from several sources, identified by a namespace and a key
+#[cfg(not(feature = "std"))]
+use crate::compat::prelude::*;
+use crate::compat::HashMap;
+use alloc::sync::Arc;
+use core::error::Error;
+use core::fmt;
+
+/// Error returned while interning keys
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InternError {
+    /// There are already 65536 namespaces
+    TooManyNamespaces,
+    /// There are already 2³² distinct keys
+    TooManyKeys,
+    /// The namespace was not created by this interner
+    UnknownNamespace(u16),
+}
+
+impl fmt::Display for InternError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            InternError::TooManyNamespaces => {
+                write!(f, "too many namespaces")
+            }
+            InternError::TooManyKeys => write!(f, "too many keys"),
+            InternError::UnknownNamespace(namespace) => {
+                write!(f, "unknown namespace {}", namespace)
+            }
+        }
+    }
+}
+
+impl Error for InternError {}
+
+/// Key of a node in a graph mixing several sources, such as wiki pages,
+/// web pages and citations: the id of its namespace and the id of its
+/// key, both given by a `KeyInterner`.
+///
+/// It takes 8 bytes and is `Copy`, so a `Pagerank<NamespacedKey>` keeps
+/// two small integers per node instead of a prefixed string, and the
+/// same key in different namespaces is stored once.
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord,
+)]
+pub struct NamespacedKey {
+    namespace: u16,
+    key: u32,
+}
+
+impl NamespacedKey {
+    /// Returns the id of the namespace
+    pub fn namespace(&self) -> u16 {
+        self.namespace
+    }
+
+    /// Returns the id of the key, shared by every namespace
+    pub fn key_id(&self) -> u32 {
+        self.key
+    }
+}
+
+impl fmt::Display for NamespacedKey {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}:{}", self.namespace, self.key)
+    }
+}
+
+/// Turns namespaces and keys into `NamespacedKey`s, keeping a single
+/// copy of every distinct key whatever the namespaces it is used in, and
+/// turns them back into strings to present the results.
+#[derive(Debug, Clone, Default)]
+pub struct KeyInterner {
+    /// Name of every namespace, by id
+    namespaces: Vec<String>,
+    /// Every distinct key, by id
+    keys: Vec<Arc<str>>,
+    /// Id of every key, sharing its storage with `keys`
+    key_ids: HashMap<Arc<str>, u32>,
+}
+
+impl KeyInterner {
+    /// Creates an interner without namespaces nor keys
+    pub fn new() -> KeyInterner {
+        KeyInterner::default()
+    }
+
+    /// Returns the id of a namespace, creating it if needed
+    pub fn namespace(
+        &mut self,
+        name: &str,
+    ) -> Result<u16, InternError> {
+        if let Some(id) = self.namespace_id(name) {
+            return Ok(id);
+        }
+        if self.namespaces.len() > u16::MAX as usize {
+            return Err(InternError::TooManyNamespaces);
+        }
+        self.namespaces.push(name.to_owned());
+        Ok((self.namespaces.len() - 1) as u16)
+    }
+
+    /// Returns the id of an existing namespace
+    pub fn namespace_id(&self, name: &str) -> Option<u16> {
+        self.namespaces
+            .iter()
+            .position(|namespace| namespace == name)
+            .map(|id| id as u16)
+    }
+
+    /// Returns the name of a namespace
+    pub fn namespace_name(&self, namespace: u16) -> Option<&str> {
+        self.namespaces.get(namespace as usize).map(|n| n.as_str())
+    }
+
+    /// Returns the node key of `key` in `namespace`, interning `key` if
+    /// it wasn't yet
+    pub fn key(
+        &mut self,
+        namespace: u16,
+        key: &str,
+    ) -> Result<NamespacedKey, InternError> {
+        if namespace as usize >= self.namespaces.len() {
+            return Err(InternError::UnknownNamespace(namespace));
+        }
+        if let Some(id) = self.key_ids.get(key) {
+            return Ok(NamespacedKey {
+                namespace,
+                key: *id,
+            });
+        }
+        if self.keys.len() > u32::MAX as usize {
+            return Err(InternError::TooManyKeys);
+        }
+
+        let id = self.keys.len() as u32;
+        let key: Arc<str> = Arc::from(key);
+        self.keys.push(key.clone());
+        self.key_ids.insert(key, id);
+        Ok(NamespacedKey { namespace, key: id })
+    }
+
+    /// Returns the node key of `key` in `namespace` if both exist,
+    /// without interning anything, to query a graph
+    pub fn get(
+        &self,
+        namespace: &str,
+        key: &str,
+    ) -> Option<NamespacedKey> {
+        Some(NamespacedKey {
+            namespace: self.namespace_id(namespace)?,
+            key: *self.key_ids.get(key)?,
+        })
+    }
+
+    /// Returns the name of the namespace and the key of a node key
+    pub fn resolve(
+        &self,
+        key: &NamespacedKey,
+    ) -> Option<(&str, &str)> {
+        Some((
+            self.namespace_name(key.namespace)?,
+            self.keys.get(key.key as usize)?,
+        ))
+    }
+
+    /// Returns the number of distinct keys
+    pub fn len(&self) -> usize {
+        self.keys.len()
+    }
+
+    /// If no key was interned
+    pub fn is_empty(&self) -> bool {
+        self.keys.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Aggregate, InternError, KeyInterner, Pagerank};
+
+    #[test]
+    fn test_namespaced_keys() {
+        let mut interner = KeyInterner::new();
+        let wiki = interner.namespace("wiki").unwrap();
+        let web = interner.namespace("web").unwrap();
+        assert_eq!(Ok(wiki), interner.namespace("wiki"));
+
+        let mut pr = Pagerank::new();
+        let page = interner.key(wiki, "Rust").unwrap();
+        let site = interner.key(web, "rust-lang.org").unwrap();
+        let mirror = interner.key(web, "Rust").unwrap();
+        pr.add_edge(page, site);
+        pr.add_edge(mirror, site);
+        pr.calculate();
+
+        // The key of both namespaces is stored once
+        assert_eq!(2, interner.len());
+        assert_eq!(page.key_id(), mirror.key_id());
+        assert_ne!(page, mirror);
+        assert_eq!(3, pr.len());
+
+        let (top, _) = pr.nodes()[0];
+        assert_eq!(
+            Some(("web", "rust-lang.org")),
+            interner.resolve(top)
+        );
+        assert_eq!(Some(site), interner.get("web", "rust-lang.org"));
+        assert_eq!(None, interner.get("citations", "Rust"));
+        let unlinked = interner.get("wiki", "rust-lang.org").unwrap();
+        assert_eq!(None, pr.get_score(unlinked));
+
+        let scores = pr.group_scores(
+            |key| interner.namespace_name(key.namespace()),
+            Aggregate::Sum,
+        );
+        assert_eq!("web", scores[0].0);
+        assert_eq!(2, scores.len());
+        assert_eq!(
+            Err(InternError::UnknownNamespace(7)),
+            interner.key(7, "Rust")
+        );
+    }
+}
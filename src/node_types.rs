@@ -0,0 +1,127 @@
+//! Node types with their own share of the teleport probability
+#[cfg(not(feature = "std"))]
+use crate::compat::prelude::*;
+use crate::solver::ScoreValue;
+use crate::Pagerank;
+use alloc::collections::BTreeMap;
+use core::hash::{BuildHasher, Hash};
+
+impl<T, S> Pagerank<T, S>
+where
+    T: Eq + Hash + Clone,
+    S: BuildHasher,
+{
+    /// Tags a node with a type, such as entity or document in a
+    /// knowledge graph. Nodes are of type 0 until tagged. The node is
+    /// created if it is not in the graph yet.
+    pub fn set_node_type(&mut self, node: T, node_type: u16) {
+        let id = self.insert_node(node);
+        if id >= self.node_types.len() {
+            if node_type == 0 {
+                return;
+            }
+            self.node_types.resize(id + 1, 0);
+        }
+        self.node_types[id] = node_type;
+    }
+
+    /// Returns the type of a node
+    pub fn node_type(&self, node: &T) -> Option<u16> {
+        self.node_positions.get(node).map(|id| self.type_of(*id))
+    }
+
+    /// Sets which fraction of the teleport probability goes to each node
+    /// type, so different types don't compete for the same teleport
+    /// mass. Within a type it is split evenly among its nodes.
+    ///
+    /// Shares are renormalized to sum 1, and types without a share, or
+    /// with a share which is not a positive number, get none. When no
+    /// share is left teleporting becomes uniform again. A personalization
+    /// vector, if set, takes precedence over the shares.
+    pub fn set_teleport_shares<I>(&mut self, shares: I)
+    where
+        I: IntoIterator<Item = (u16, f64)>,
+    {
+        let mut by_type = BTreeMap::new();
+        for (node_type, share) in shares {
+            if share > 0f64 && share.is_finite() {
+                *by_type.entry(node_type).or_insert(0f64) += share;
+            }
+        }
+
+        let total = by_type.values().sum::<f64>();
+        self.teleport_shares = by_type
+            .into_iter()
+            .map(|(node_type, share)| (node_type, share / total))
+            .collect();
+    }
+
+    /// Type of the node with the given id
+    pub(crate) fn type_of(&self, id: usize) -> u16 {
+        self.node_types.get(id).cloned().unwrap_or(0)
+    }
+
+    /// Splits a total teleport mass among the nodes by type share,
+    /// leaving out the nodes flagged in `removed`, if any
+    pub(crate) fn teleport_by_type<F: ScoreValue>(
+        &self,
+        total: f64,
+        removed: &[bool],
+    ) -> Vec<F> {
+        let is_removed = |id: usize| removed.get(id) == Some(&true);
+        let mut counts = BTreeMap::new();
+        for id in (0..self.nodes.len()).filter(|id| !is_removed(*id))
+        {
+            *counts.entry(self.type_of(id)).or_insert(0usize) += 1;
+        }
+
+        (0..self.nodes.len())
+            .map(|id| {
+                if is_removed(id) {
+                    return 0f64;
+                }
+                let node_type = self.type_of(id);
+                self.teleport_shares
+                    .iter()
+                    .find(|(t, _)| *t == node_type)
+                    .map_or(0f64, |(_, share)| {
+                        total * share / counts[&node_type] as f64
+                    })
+            })
+            .map(F::from_f64)
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Pagerank;
+
+    #[test]
+    fn test_teleport_shares() {
+        let mut pr = Pagerank::<&str>::new();
+        pr.add_edge("doc1", "entity");
+        pr.add_edge("doc2", "entity");
+        pr.add_edge("entity", "doc1");
+        pr.set_node_type("entity", 1);
+        assert_eq!(Some(1), pr.node_type(&"entity"));
+        assert_eq!(Some(0), pr.node_type(&"doc2"));
+        assert_eq!(None, pr.node_type(&"unknown"));
+
+        pr.set_teleport_shares(vec![(0, 1.0), (1, 1.0)]);
+        let teleport = pr.teleport_scores();
+        let total = 0.15 * 3.0;
+        let expected = [total / 4.0, total / 2.0, total / 4.0];
+        for (e, t) in expected.iter().zip(teleport.iter()) {
+            assert!((e - t).abs() < 1e-12);
+        }
+
+        pr.set_teleport_shares(vec![(1, 2.0)]);
+        pr.calculate_with_convergence(1e-12);
+        assert!(pr.get_score("doc2").unwrap() < 1e-9);
+        assert!(pr.get_score("doc1").unwrap() > 0.1);
+
+        pr.set_teleport_shares(Vec::new());
+        assert_eq!(vec![1.0 - 0.85; 3], pr.teleport_scores());
+    }
+}
@@ -0,0 +1,135 @@
+//! Scores in the NumPy `.npy` format
+use crate::Pagerank;
+use std::fmt::Display;
+use std::fs::File;
+use std::hash::{BuildHasher, Hash};
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+/// Magic string and version (1.0) every `.npy` file starts with
+const MAGIC: &[u8] = b"\x93NUMPY\x01\x00";
+
+/// The header, including the magic string, is padded to a multiple of
+/// this size so the data is aligned
+const ALIGNMENT: usize = 64;
+
+impl<T, S> Pagerank<T, S>
+where
+    T: Eq + Hash + Clone + Display,
+    S: BuildHasher,
+{
+    /// Writes the scores to the file at `path` in the NumPy `.npy`
+    /// format, so they can be loaded with `np.load` without parsing
+    /// text, along with a sidecar file with the key of every node.
+    ///
+    /// The sidecar is written next to it with the `.keys.txt`
+    /// extension, `scores.npy` gives `scores.keys.txt`, and is returned.
+    /// See `write_npy_scores` and `write_npy_keys`.
+    pub fn write_npy<P: AsRef<Path>>(
+        &self,
+        path: P,
+    ) -> io::Result<PathBuf> {
+        let path = path.as_ref();
+        let mut file = BufWriter::new(File::create(path)?);
+        self.write_npy_scores(&mut file)?;
+        file.flush()?;
+
+        let keys_path = path.with_extension("keys.txt");
+        let mut file = BufWriter::new(File::create(&keys_path)?);
+        self.write_npy_keys(&mut file)?;
+        file.flush()?;
+
+        Ok(keys_path)
+    }
+
+    /// Writes the key of every node, one per line, in the same order as
+    /// the scores written by `write_npy_scores`, so
+    /// `np.loadtxt(path, dtype=str)` lines them up. Keys must not
+    /// contain line breaks.
+    pub fn write_npy_keys<W: Write>(
+        &self,
+        mut writer: W,
+    ) -> io::Result<()> {
+        for n in self.nodes.iter() {
+            writeln!(writer, "{}", n.node)?;
+        }
+        Ok(())
+    }
+}
+
+impl<T, S> Pagerank<T, S>
+where
+    T: Eq + Hash + Clone,
+    S: BuildHasher,
+{
+    /// Writes the score of every node, in insertion order, as a one
+    /// dimensional `.npy` array of little endian `f64` (`<f8`)
+    pub fn write_npy_scores<W: Write>(
+        &self,
+        mut writer: W,
+    ) -> io::Result<()> {
+        let mut header = format!(
+            "{{'descr': '<f8', 'fortran_order': False, 'shape': ({},), }}",
+            self.nodes.len()
+        );
+        // Magic, two bytes of header length, the header and a newline
+        let unpadded = MAGIC.len() + 2 + header.len() + 1;
+        let padding = (ALIGNMENT - unpadded % ALIGNMENT) % ALIGNMENT;
+        header.extend(std::iter::repeat_n(' ', padding));
+        header.push('\n');
+
+        writer.write_all(MAGIC)?;
+        writer.write_all(&(header.len() as u16).to_le_bytes())?;
+        writer.write_all(header.as_bytes())?;
+        for n in self.nodes.iter() {
+            writer.write_all(
+                &self.transformed(n.score).to_le_bytes(),
+            )?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Pagerank;
+    use std::convert::TryInto;
+
+    #[test]
+    fn test_write_npy() {
+        let mut pr = Pagerank::<&str>::new();
+        pr.add_edge("foo", "bar");
+        pr.add_edge("bar", "foo");
+        pr.add_edge("xxx", "bar");
+        pr.calculate();
+
+        let mut buf = Vec::new();
+        pr.write_npy_scores(&mut buf).unwrap();
+        assert_eq!(b"\x93NUMPY\x01\x00", &buf[..8]);
+        let header_len =
+            u16::from_le_bytes([buf[8], buf[9]]) as usize;
+        assert_eq!(0, (10 + header_len) % 64);
+        let header =
+            std::str::from_utf8(&buf[10..10 + header_len]).unwrap();
+        assert!(header.starts_with("{'descr': '<f8', "));
+        assert!(header.contains("'shape': (3,)"));
+        assert!(header.ends_with('\n'));
+
+        let scores = buf[10 + header_len..]
+            .chunks(8)
+            .map(|c| f64::from_le_bytes(c.try_into().unwrap()))
+            .collect::<Vec<f64>>();
+        assert_eq!(
+            vec![
+                pr.get_score("foo").unwrap(),
+                pr.get_score("bar").unwrap(),
+                pr.get_score("xxx").unwrap(),
+            ],
+            scores
+        );
+
+        let mut keys = Vec::new();
+        pr.write_npy_keys(&mut keys).unwrap();
+        assert_eq!(b"foo\nbar\nxxx\n", &keys[..]);
+    }
+}
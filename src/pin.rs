@@ -0,0 +1,130 @@
+//! Scores fixed while iterating
+#[cfg(not(feature = "std"))]
+use crate::compat::prelude::*;
+use crate::Pagerank;
+use core::hash::{BuildHasher, Hash};
+
+impl<T, S> Pagerank<T, S>
+where
+    T: Eq + Hash + Clone,
+    S: BuildHasher,
+{
+    /// Fixes the score of a node as a boundary condition: it keeps
+    /// `score` through every calculation, still passing it along its out
+    /// edges, but never updated from its in edges. The node is created
+    /// if it is not in the graph yet.
+    ///
+    /// This enables hierarchical computations, where the results of a
+    /// parent graph seed the nodes a child subgraph links to or from.
+    /// Scores which are not finite, non negative numbers are ignored.
+    /// Pins are kept by `filter_by_score`, but not by snapshots, and
+    /// `calculate_linear` doesn't honor them.
+    pub fn pin_score(&mut self, node: T, score: f64) {
+        if !(score >= 0f64 && score.is_finite()) {
+            return;
+        }
+        let id = self.insert_node(node);
+        match self.pinned.binary_search_by_key(&id, |(id, _)| *id) {
+            Ok(at) => self.pinned[at].1 = score,
+            Err(at) => self.pinned.insert(at, (id, score)),
+        }
+        self.nodes[id].score = score;
+        self.sweep = None;
+    }
+
+    /// Lets the score of a node be calculated again. Returns false if
+    /// it was not pinned.
+    pub fn unpin_score(&mut self, node: &T) -> bool {
+        let id = match self.node_positions.get(node) {
+            Some(id) => *id,
+            None => return false,
+        };
+        match self.pinned.binary_search_by_key(&id, |(id, _)| *id) {
+            Ok(at) => {
+                self.pinned.remove(at);
+                self.sweep = None;
+                true
+            }
+            Err(_) => false,
+        }
+    }
+
+    /// Lets the score of every node be calculated again
+    pub fn clear_pinned_scores(&mut self) {
+        self.pinned.clear();
+        self.sweep = None;
+    }
+
+    /// Returns the pinned nodes with their score, in the order the nodes
+    /// were added
+    pub fn pinned_scores(&self) -> Vec<(&T, f64)> {
+        self.pinned
+            .iter()
+            .map(|(id, score)| (&self.nodes[*id].node, *score))
+            .collect()
+    }
+
+    /// Returns the score a node is pinned to, if any
+    #[inline]
+    pub(crate) fn pinned_score(&self, id: usize) -> Option<f64> {
+        if self.pinned.is_empty() {
+            return None;
+        }
+        self.pinned
+            .binary_search_by_key(&id, |(id, _)| *id)
+            .ok()
+            .map(|at| self.pinned[at].1)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Pagerank;
+
+    #[test]
+    fn test_pin_score() {
+        // Results of a parent graph seed the child
+        let mut parent = Pagerank::<&str>::new();
+        parent.add_edge("a", "hub");
+        parent.add_edge("b", "hub");
+        parent.add_edge("c", "hub");
+        parent.calculate_with_convergence(1e-9);
+        let hub = parent.get_score("hub").unwrap();
+
+        let subgraph = || {
+            let mut child = Pagerank::<&str>::new();
+            child.pin_score("hub", hub);
+            child.add_edge("hub", "x");
+            child.add_edge("x", "y");
+            child.add_edge("y", "hub");
+            child.add_edge("y", "x");
+            child
+        };
+        let mut child = subgraph();
+        child.calculate_with_convergence(1e-9);
+        assert_eq!(Some(hub), child.get_score("hub"));
+        assert_eq!(vec![(&"hub", hub)], child.pinned_scores());
+
+        let x = child.get_score("x").unwrap();
+        let y = child.get_score("y").unwrap();
+        assert!((x - (0.15 + 0.85 * (hub + y / 2.0))).abs() < 1e-6);
+
+        // The same with calculate_bounded
+        let mut bounded = subgraph();
+        while bounded.calculate_bounded(1e-9, 2).is_none() {}
+        assert_eq!(Some(hub), bounded.get_score("hub"));
+        assert!((x - bounded.get_score("x").unwrap()).abs() < 1e-6);
+
+        // Removing nodes keeps the pins on the right ids
+        child.pin_score("y", 2.0);
+        assert!(child.remove_node(&"hub"));
+        child.calculate();
+        assert_eq!(vec![(&"y", 2.0)], child.pinned_scores());
+        assert_eq!(Some(2.0), child.get_score("y"));
+
+        assert!(child.unpin_score(&"y"));
+        assert!(!child.unpin_score(&"y"));
+        child.calculate_with_convergence(1e-9);
+        assert!(child.get_score("y").unwrap() < 2.0);
+    }
+}
@@ -0,0 +1,135 @@
+//! Pregel-like vertex programs over the graph
+#[cfg(not(feature = "std"))]
+use crate::compat::prelude::*;
+use crate::Pagerank;
+use core::hash::{BuildHasher, Hash};
+
+impl<T, S> Pagerank<T, S>
+where
+    T: Eq + Hash + Clone,
+    S: BuildHasher,
+{
+    /// Runs a vertex program over the graph, in the spirit of Pregel.
+    ///
+    /// Every node starts with the value returned by `init`. On each
+    /// superstep, every node receives one message per in edge, built by
+    /// `gather` from the value and out degree of the source node, and
+    /// its new value is computed by `apply` from its current value and
+    /// all the messages it received. All nodes are updated at once from
+    /// the values of the previous superstep.
+    ///
+    /// The program stops once a superstep leaves every value unchanged,
+    /// or after `max_supersteps`. Returns each node with its final value.
+    pub fn vertex_program<V, M, I, G, A>(
+        &self,
+        max_supersteps: usize,
+        init: I,
+        gather: G,
+        apply: A,
+    ) -> Vec<(&T, V)>
+    where
+        V: PartialEq,
+        I: Fn(&T) -> V,
+        G: Fn(&V, usize) -> M,
+        A: Fn(&V, &[M]) -> V,
+    {
+        let mut values = self
+            .nodes
+            .iter()
+            .map(|n| init(&n.node))
+            .collect::<Vec<V>>();
+        let mut messages = Vec::new();
+
+        for _ in 0..max_supersteps {
+            let next = self
+                .nodes
+                .iter()
+                .enumerate()
+                .map(|(id, _)| {
+                    messages.clear();
+                    messages.extend(self.in_edges(id).iter().map(
+                        |source| {
+                            gather(
+                                &values[*source],
+                                self.nodes[*source].out_edges,
+                            )
+                        },
+                    ));
+
+                    apply(&values[id], &messages)
+                })
+                .collect::<Vec<V>>();
+
+            let changed = next != values;
+            values = next;
+            if !changed {
+                break;
+            }
+        }
+
+        self.nodes.iter().map(|n| &n.node).zip(values).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Pagerank;
+
+    #[test]
+    fn test_shortest_hops() {
+        let mut pr = Pagerank::<&str>::new();
+        pr.add_edge("a", "b");
+        pr.add_edge("b", "c");
+        pr.add_edge("a", "c");
+        pr.add_edge("c", "d");
+        pr.add_edge("e", "e");
+
+        let hops = pr.vertex_program(
+            10,
+            |node| if *node == "a" { Some(0) } else { None },
+            |hops: &Option<u32>, _| hops.map(|h| h + 1),
+            |hops, messages| {
+                messages
+                    .iter()
+                    .chain(std::iter::once(hops))
+                    .filter_map(|h| *h)
+                    .min()
+            },
+        );
+
+        assert_eq!(
+            vec![
+                ("a", Some(0)),
+                ("b", Some(1)),
+                ("c", Some(1)),
+                ("d", Some(2)),
+                ("e", None)
+            ],
+            hops.into_iter()
+                .map(|(n, h)| (*n, h))
+                .collect::<Vec<(&str, Option<u32>)>>()
+        );
+    }
+
+    #[test]
+    fn test_pagerank_as_vertex_program() {
+        let mut pr = Pagerank::<&str>::new();
+        pr.add_edge("foo", "bar");
+        pr.add_edge("bar", "foo");
+        pr.add_edge("xxx", "bar");
+        pr.calculate_with_convergence(1e-12);
+
+        let scores = pr.vertex_program(
+            1000,
+            |_| 0.15,
+            |score, out| score / out as f64,
+            |_, messages| 0.15 + 0.85 * messages.iter().sum::<f64>(),
+        );
+
+        for (node, score) in scores {
+            assert!(
+                (pr.get_score(*node).unwrap() - score).abs() < 1e-9
+            );
+        }
+    }
+}
@@ -0,0 +1,278 @@
+//! Graph pruning
+#[cfg(not(feature = "std"))]
+use crate::compat::prelude::*;
+use crate::Pagerank;
+use core::hash::{BuildHasher, Hash};
+
+impl<T, S> Pagerank<T, S>
+where
+    T: Eq + Hash + Clone,
+    S: BuildHasher,
+{
+    /// Drops every in edge whose contribution to its target, given the
+    /// current scores, is below `threshold`. Meant to be called after a
+    /// first `calculate`, to make further calculations cheaper.
+    ///
+    /// The contribution of an edge is `d * score(source)` times its share
+    /// of the source out edges, `1 / out(source)` for unweighted edges.
+    /// Sources lose the dropped edges from their out degree but keep
+    /// their weight in their total out weight, so the remaining edges
+    /// keep their transition probability and the dropped score mass is
+    /// simply lost. Sources left without out edges become dangling.
+    /// When in edges are capped, each dropped stored edge counts as a
+    /// single edge, as with `remove_edge`, and the edges which were not
+    /// sampled stay counted in the in degree of their target.
+    /// Close to the current scores, each node loses at most the sum of
+    /// its dropped contributions, and the total error over all nodes is
+    /// bounded by the sum of all dropped contributions over `1 - d`.
+    ///
+    /// Pruning can't be rolled back, so an open batch is committed
+    /// first. Returns the number of dropped edges and that error bound.
+    pub fn prune_in_edges(&mut self, threshold: f64) -> (usize, f64) {
+        self.commit_batch();
+        self.thaw();
+        self.finalize_weights();
+        let contributions = (0..self.nodes.len())
+            .map(|target| {
+                let n = &self.nodes[target];
+                (0..n.in_edges.len())
+                    .map(|slot| {
+                        self.damping
+                            * self.nodes[n.in_edges[slot]].score
+                            * self.edge_share(
+                                n.in_edges[slot],
+                                target,
+                                slot,
+                            )
+                    })
+                    .collect::<Vec<f64>>()
+            })
+            .collect::<Vec<Vec<f64>>>();
+
+        let mut dropped_edges = 0;
+        let mut dropped_score = 0f64;
+        let mut dropped = Vec::new();
+
+        for (target, (node, contributions)) in
+            self.nodes.iter_mut().zip(contributions).enumerate()
+        {
+            let weight = node.in_edges_weight();
+            let before = node.in_edges.len();
+            let weighted = !node.in_weights.is_empty();
+
+            let mut slot = 0;
+            for (i, contribution) in contributions.iter().enumerate()
+            {
+                if *contribution < threshold {
+                    dropped_score += contribution * weight;
+                    dropped.push((node.in_edges[i], target));
+                } else {
+                    node.in_edges[slot] = node.in_edges[i];
+                    if weighted {
+                        node.in_weights[slot] = node.in_weights[i];
+                    }
+                    slot += 1;
+                }
+            }
+            node.in_edges.truncate(slot);
+            node.in_weights.truncate(slot);
+
+            // Each dropped edge is one edge less for its target, its
+            // source and the graph, as with `remove_edge`
+            let dropped_here = before - node.in_edges.len();
+            node.in_degree -= dropped_here;
+            dropped_edges += dropped_here;
+        }
+
+        for (source, target) in dropped {
+            let n = &mut self.nodes[source];
+            n.out_edges -= 1;
+            if n.out_edges == 0 {
+                n.out_weight = 0f64;
+            }
+            // Unless a duplicate of the edge is still stored
+            if let Some(unique) = self.unique_edges.as_mut() {
+                if !self.nodes[target].in_edges.contains(&source) {
+                    unique.remove(&(source, target));
+                }
+            }
+        }
+
+        self.edges -= dropped_edges as u64;
+        self.edge_weights = None;
+        self.sweep = None;
+        self.components = None;
+        self.nodes_with_in_edges = None;
+
+        (dropped_edges, dropped_score / (1f64 - self.damping))
+    }
+
+    /// Builds the subgraph of the nodes scoring at least `min_score`,
+    /// with the edges between them. Meant for a two pass ranking: a
+    /// coarse PageRank over everything, then a finer one, for instance
+    /// weighted, over the important core.
+    ///
+    /// The subgraph keeps the damping factor, in edges cap, weight and
+    /// affinity closures, the group discount, and the personalization
+    /// seeds and pinned scores of the nodes which are kept.
+    /// Nodes keep their current score as the starting point of the next
+    /// calculation.
+    pub fn filter_by_score(&self, min_score: f64) -> Pagerank<T> {
+        let mut pr = Pagerank::new();
+        pr.damping = self.damping;
+        pr.in_edges_cap = self.in_edges_cap;
+        pr.weight_fn = self.weight_fn.clone();
+        pr.affinity_fn = self.affinity_fn.clone();
+        pr.group_fn = self.group_fn.clone();
+        pr.score_transform = self.score_transform.clone();
+
+        let kept = self
+            .nodes
+            .iter()
+            .map(|n| n.score >= min_score)
+            .collect::<Vec<bool>>();
+
+        for (n, _) in
+            self.nodes.iter().zip(kept.iter()).filter(|k| *k.1)
+        {
+            let id = pr.insert_node(n.node.clone());
+            pr.nodes[id].score = n.score;
+        }
+
+        for (n, _) in
+            self.nodes.iter().zip(kept.iter()).filter(|k| *k.1)
+        {
+            let id = self.node_positions[&n.node];
+            for (slot, source) in self.in_edges(id).iter().enumerate()
+            {
+                if kept[*source] {
+                    let _ = pr.add_edge_weighted(
+                        self.nodes[*source].node.clone(),
+                        n.node.clone(),
+                        n.edge_weight(slot),
+                    );
+                }
+            }
+        }
+
+        pr.set_personalization(
+            self.personalization
+                .iter()
+                .filter(|(id, _)| kept[*id])
+                .map(|(id, weight)| {
+                    (self.nodes[*id].node.clone(), *weight)
+                }),
+        );
+        for (id, score) in self.pinned.iter() {
+            if kept[*id] {
+                pr.pin_score(self.nodes[*id].node.clone(), *score);
+            }
+        }
+
+        pr
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Pagerank;
+
+    #[test]
+    fn test_prune_in_edges() {
+        let mut pr = Pagerank::<&str>::new();
+        pr.add_edge("foo", "bar");
+        pr.add_edge("bar", "foo");
+        pr.add_edge("xxx", "bar");
+        for i in 0..10 {
+            pr.add_edge("xxx", ["a", "b", "c", "d", "e"][i % 5]);
+        }
+        pr.calculate_with_convergence(1e-12);
+        let before = pr.score_set();
+
+        let (dropped, bound) = pr.prune_in_edges(0.05);
+        assert_eq!(11, dropped);
+        assert_eq!(2, pr.len_node());
+        assert_eq!(Some(0), pr.get_out_edges("xxx"));
+        assert_eq!(Some(1), pr.get_in_edges("bar"));
+
+        pr.calculate_with_convergence(1e-12);
+        let error = before
+            .combine(&pr.score_set(), |a, b| (a - b).abs())
+            .nodes()
+            .iter()
+            .map(|(_, diff)| diff)
+            .sum::<f64>();
+        assert!(error > 0f64);
+        assert!(error <= bound);
+    }
+
+    #[test]
+    fn test_prune_in_edges_bookkeeping() {
+        let mut pr = Pagerank::<&str>::new();
+        pr.set_unique_edges(true);
+        pr.add_edge("a", "b");
+        pr.calculate();
+        assert_eq!(1, pr.prune_in_edges(1e9).0);
+        assert_eq!(Some(0), pr.get_out_edges("a"));
+        assert_eq!(0, pr.len_node());
+
+        // A pruned edge can be added again
+        pr.add_edge("a", "b");
+        assert_eq!(1, pr.len_node());
+        pr.prune_in_edges(1e9);
+        assert!(pr.remove_node(&"a"));
+        assert_eq!(0, pr.len_node());
+
+        // Pruning commits the open batch
+        pr.begin_batch();
+        pr.add_edge("c", "b");
+        pr.prune_in_edges(1e9);
+        assert!(!pr.rollback_batch());
+        assert_eq!(Some(0), pr.get_in_edges("b"));
+        assert!(pr.remove_node(&"c"));
+        assert_eq!(0, pr.len_node());
+    }
+
+    #[test]
+    fn test_prune_in_edges_capped() {
+        let mut pr = Pagerank::<u32>::new();
+        pr.set_in_edges_cap(4);
+        for source in 1..=20 {
+            pr.add_edge(source, 0);
+            pr.add_edge(source, source % 5 + 100);
+        }
+        pr.add_edge(0, 1);
+        pr.calculate();
+
+        let degrees = |pr: &Pagerank<u32>| {
+            pr.iter_nodes().fold((0, 0), |(i, o), n| {
+                (i + n.in_degree, o + n.out_degree)
+            })
+        };
+        let (dropped, _) = pr.prune_in_edges(0.1);
+        assert!(dropped > 0);
+        let edges = pr.len_node() as usize;
+        assert_eq!(41 - dropped, edges);
+        assert_eq!((edges, edges), degrees(&pr));
+    }
+
+    #[test]
+    fn test_filter_by_score() {
+        let mut pr = Pagerank::<&str>::new();
+        pr.add_edge("foo", "bar");
+        pr.add_edge("bar", "foo");
+        pr.add_edge("xxx", "bar");
+        pr.add_edge("bar", "yyy");
+        pr.calculate();
+
+        let min = pr.get_score("yyy").unwrap();
+        let core = pr.filter_by_score(min);
+        assert_eq!(3, core.len());
+        assert_eq!(3, core.len_node());
+        assert_eq!(None, core.get_score("xxx"));
+        assert_eq!(pr.get_score("foo"), core.get_score("foo"));
+        assert_eq!(Some(2), core.get_out_edges("bar"));
+
+        assert!(pr.filter_by_score(f64::INFINITY).is_empty());
+    }
+}
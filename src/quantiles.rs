@@ -0,0 +1,208 @@
+//! Quantile lookup tables to turn scores into percentiles
+#[cfg(not(feature = "std"))]
+use crate::compat::prelude::*;
+use crate::graph::cmp_scores;
+use crate::Pagerank;
+use core::hash::{BuildHasher, Hash};
+#[cfg(feature = "std")]
+use std::io::{self, BufRead, Write};
+
+/// Scores at evenly spaced quantiles of a ranking, a compact stand-in
+/// for the whole score set when scores only need to be turned into
+/// percentiles, as returned by `Pagerank::score_quantiles`
+#[derive(Debug, Clone, PartialEq)]
+pub struct ScoreQuantiles {
+    /// Score at quantile `i / (len - 1)`, non decreasing
+    values: Vec<f64>,
+}
+
+impl<T, S> Pagerank<T, S>
+where
+    T: Eq + Hash + Clone,
+    S: BuildHasher,
+{
+    /// Builds a lookup table with the score at `points` evenly spaced
+    /// quantiles, from the lowest score to the highest one, so services
+    /// can turn raw scores into percentiles without the full score set.
+    /// A thousand points are usually plenty.
+    ///
+    /// Scores between two nodes are interpolated. The table is empty if
+    /// the graph is, and has at least two points otherwise.
+    pub fn score_quantiles(&self, points: usize) -> ScoreQuantiles {
+        let mut scores = self
+            .nodes
+            .iter()
+            .map(|n| self.transformed(n.score))
+            .collect::<Vec<f64>>();
+        if scores.is_empty() {
+            return ScoreQuantiles { values: Vec::new() };
+        }
+        scores.sort_by(|a, b| cmp_scores(*a, *b));
+
+        let points = points.max(2);
+        let last = (scores.len() - 1) as f64;
+        let values = (0..points)
+            .map(|i| {
+                let at = i as f64 * last / (points - 1) as f64;
+                let low = at.floor() as usize;
+                let high = at.ceil() as usize;
+                let fraction = at - low as f64;
+                scores[low] + (scores[high] - scores[low]) * fraction
+            })
+            .collect();
+
+        ScoreQuantiles { values }
+    }
+}
+
+impl ScoreQuantiles {
+    /// Builds a table from the scores at evenly spaced quantiles, as
+    /// given by `values`. Returns `None` unless there are at least two
+    /// of them, all finite and in non decreasing order.
+    pub fn from_values(values: Vec<f64>) -> Option<ScoreQuantiles> {
+        if values.len() < 2
+            || values.iter().any(|v| !v.is_finite())
+            || values.windows(2).any(|w| w[0] > w[1])
+        {
+            return None;
+        }
+        Some(ScoreQuantiles { values })
+    }
+
+    /// Scores at each quantile, lowest first
+    pub fn values(&self) -> &[f64] {
+        &self.values
+    }
+
+    /// Returns the fraction of the ranking, from 0 to 1, scoring at
+    /// most `score`. Scores out of the range of the table are clamped,
+    /// and an empty table always returns 0.
+    pub fn percentile(&self, score: f64) -> f64 {
+        let len = self.values.len();
+        if len < 2 || score.is_nan() || score < self.values[0] {
+            return 0f64;
+        }
+
+        let above = self.values.partition_point(|v| *v <= score);
+        if above == len {
+            return 1f64;
+        }
+
+        let low = above - 1;
+        let (from, to) = (self.values[low], self.values[above]);
+        (low as f64 + (score - from) / (to - from)) / (len - 1) as f64
+    }
+
+    /// Returns the score at the given quantile, from 0 to 1, which is
+    /// clamped to that range. Returns `None` if the table is empty.
+    pub fn score_at(&self, quantile: f64) -> Option<f64> {
+        let len = self.values.len();
+        if len == 0 {
+            return None;
+        }
+
+        let quantile = if quantile.is_nan() {
+            0f64
+        } else {
+            quantile.clamp(0f64, 1f64)
+        };
+        let at = quantile * (len - 1) as f64;
+        let low = at.floor() as usize;
+        let high = at.ceil() as usize;
+        let fraction = at - low as f64;
+        Some(
+            self.values[low]
+                + (self.values[high] - self.values[low]) * fraction,
+        )
+    }
+
+    /// Writes the table as text, one score per line with full
+    /// precision, which `read_from` loads back
+    #[cfg(feature = "std")]
+    pub fn write_to<W: Write>(
+        &self,
+        mut writer: W,
+    ) -> io::Result<()> {
+        for value in self.values.iter() {
+            writeln!(writer, "{:?}", value)?;
+        }
+        Ok(())
+    }
+
+    /// Reads a table written by `write_to`, skipping blank lines
+    #[cfg(feature = "std")]
+    pub fn read_from<R: BufRead>(
+        reader: R,
+    ) -> io::Result<ScoreQuantiles> {
+        let mut values = Vec::new();
+        for line in reader.lines() {
+            let line = line?;
+            let line = line.trim();
+            if line.is_empty() {
+                continue;
+            }
+            values.push(line.parse::<f64>().map_err(|err| {
+                io::Error::new(io::ErrorKind::InvalidData, err)
+            })?);
+        }
+
+        ScoreQuantiles::from_values(values).ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                "quantiles must be at least two finite, sorted scores",
+            )
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Pagerank, ScoreQuantiles};
+
+    #[test]
+    fn test_score_quantiles() {
+        assert!(Pagerank::<u32>::new()
+            .score_quantiles(10)
+            .values()
+            .is_empty());
+
+        let mut pr = Pagerank::<u32>::new();
+        for source in 1..=100 {
+            for target in 0..source % 10 {
+                pr.add_edge(source, target);
+            }
+        }
+        pr.calculate();
+
+        let quantiles = pr.score_quantiles(1000);
+        assert_eq!(1000, quantiles.values().len());
+        let scores = pr.nodes();
+        let lowest = scores.last().unwrap().1;
+        let highest = scores[0].1;
+        assert_eq!(Some(lowest), quantiles.score_at(0.0));
+        assert_eq!(Some(highest), quantiles.score_at(1.0));
+        assert_eq!(0.0, quantiles.percentile(lowest - 1.0));
+        assert_eq!(1.0, quantiles.percentile(highest));
+
+        let percentile = quantiles.percentile(scores[5].1);
+        assert!(percentile > 0.9 && percentile < 1.0);
+
+        let table = ScoreQuantiles::from_values(vec![
+            0.0, 1.0, 2.0, 3.0, 4.0,
+        ])
+        .unwrap();
+        assert_eq!(0.625, table.percentile(2.5));
+        assert_eq!(Some(2.5), table.score_at(0.625));
+
+        let mut text = Vec::new();
+        quantiles.write_to(&mut text).unwrap();
+        assert_eq!(
+            quantiles,
+            ScoreQuantiles::read_from(&text[..]).unwrap()
+        );
+        assert!(
+            ScoreQuantiles::read_from(&b"2.0\n1.0\n"[..]).is_err()
+        );
+        assert_eq!(None, ScoreQuantiles::from_values(vec![1.0]));
+    }
+}
@@ -0,0 +1,152 @@
+//! Lazy iteration over the nodes and their scores
+#[cfg(not(feature = "std"))]
+use crate::compat::prelude::*;
+use crate::graph::{cmp_scores, Node};
+use crate::transform::{transform, ScoreTransform};
+use crate::Pagerank;
+use core::cmp::Ordering;
+use core::hash::{BuildHasher, Hash};
+
+impl<T, S> Pagerank<T, S>
+where
+    T: Eq + Hash + Clone,
+    S: BuildHasher,
+{
+    /// Iterates over every node and its score in insertion order,
+    /// without allocating. Use `iter_ranked` to get them sorted.
+    pub fn iter_scores(
+        &self,
+    ) -> impl ExactSizeIterator<Item = (&T, f64)> {
+        self.nodes
+            .iter()
+            .map(move |n| (&n.node, self.transformed(n.score)))
+    }
+
+    /// Iterates over every node and its score, highest first, in the
+    /// same order as `nodes`.
+    ///
+    /// Nodes are sorted lazily: building the iterator takes linear time
+    /// and one id per node, and each step takes logarithmic time, so
+    /// stopping after a prefix is much cheaper than `nodes` on a large
+    /// graph, and no tuple is materialized until it is yielded.
+    pub fn iter_ranked(&self) -> Ranked<'_, T> {
+        let mut ranked = Ranked {
+            nodes: &self.nodes,
+            transform: self.score_transform.as_ref(),
+            heap: (0..self.nodes.len()).collect(),
+        };
+        for at in (0..ranked.heap.len() / 2).rev() {
+            ranked.sift_down(at);
+        }
+        ranked
+    }
+}
+
+/// Iterator over the nodes of a graph sorted by score, highest first,
+/// returned by `Pagerank::iter_ranked`
+pub struct Ranked<'a, T>
+where
+    T: Eq + Hash + Clone,
+{
+    nodes: &'a [Node<T>],
+    /// Transform applied to the scores yielded
+    transform: Option<&'a ScoreTransform>,
+    /// Ids of the nodes not yielded yet, as a binary heap with the next
+    /// node at the root
+    heap: Vec<usize>,
+}
+
+impl<'a, T> Ranked<'a, T>
+where
+    T: Eq + Hash + Clone,
+{
+    /// If node `a` is ranked before node `b`. Ties keep insertion
+    /// order.
+    fn before(&self, a: usize, b: usize) -> bool {
+        cmp_scores(self.nodes[b].score, self.nodes[a].score)
+            .then(a.cmp(&b))
+            == Ordering::Less
+    }
+
+    /// Moves the id at `at` down until both its children rank after it
+    fn sift_down(&mut self, mut at: usize) {
+        let len = self.heap.len();
+        loop {
+            let mut best = at;
+            for child in [2 * at + 1, 2 * at + 2].iter() {
+                if *child < len
+                    && self.before(self.heap[*child], self.heap[best])
+                {
+                    best = *child;
+                }
+            }
+            if best == at {
+                return;
+            }
+            self.heap.swap(at, best);
+            at = best;
+        }
+    }
+}
+
+impl<'a, T> Iterator for Ranked<'a, T>
+where
+    T: Eq + Hash + Clone,
+{
+    type Item = (&'a T, f64);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.heap.is_empty() {
+            return None;
+        }
+
+        let id = self.heap.swap_remove(0);
+        self.sift_down(0);
+        let n = &self.nodes[id];
+        Some((&n.node, transform(self.transform, n.score)))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.heap.len(), Some(self.heap.len()))
+    }
+}
+
+impl<'a, T> ExactSizeIterator for Ranked<'a, T> where
+    T: Eq + Hash + Clone
+{
+}
+
+#[cfg(all(test, feature = "generators"))]
+mod tests {
+    use crate::{generators, Pagerank};
+
+    #[test]
+    fn test_iter_ranked() {
+        let mut pr = generators::watts_strogatz(300, 4, 0.3, 5);
+        pr.add_edge(0, 1000);
+        pr.add_edge(1, 1000);
+        pr.calculate();
+
+        let ranked = pr.iter_ranked();
+        assert_eq!(301, ranked.len());
+        assert_eq!(pr.nodes(), ranked.collect::<Vec<_>>());
+        assert_eq!(
+            pr.top_k(10),
+            pr.iter_ranked().take(10).collect::<Vec<_>>()
+        );
+
+        let unsorted = pr.iter_scores().collect::<Vec<_>>();
+        assert_eq!(301, unsorted.len());
+        assert_eq!((&0, pr.get_score(0).unwrap()), unsorted[0]);
+
+        // Ties keep insertion order
+        let mut ties = Pagerank::<&str>::new();
+        ties.insert_node("b");
+        ties.insert_node("a");
+        ties.insert_node("c");
+        let order =
+            ties.iter_ranked().map(|(n, _)| *n).collect::<Vec<_>>();
+        assert_eq!(vec!["b", "a", "c"], order);
+        assert_eq!(None, Pagerank::<u8>::new().iter_ranked().next());
+    }
+}
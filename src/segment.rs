@@ -0,0 +1,226 @@
+//! Read-only score segments which can be shared between processes
+use crate::io::{checksum, SnapshotKey};
+use crate::Pagerank;
+use std::hash::{BuildHasher, Hash};
+use std::io::{self, Write};
+
+/// Magic bytes at the beginning of every score segment
+const SEGMENT_MAGIC: &[u8; 4] = b"PRSG";
+
+/// Current version of the score segment layout
+const SEGMENT_VERSION: u8 = 1;
+
+/// Size of the segment header: magic, version (padded to 8 bytes),
+/// number of nodes and number of hash slots
+const HEADER_LEN: usize = 24;
+
+/// Read-only view over a score segment written by
+/// `Pagerank::write_score_segment`.
+///
+/// The view never copies the underlying bytes: scores and keys are
+/// decoded on each lookup, so the segment can live in a memory-mapped
+/// file opened by many query processes at once, each of them sharing a
+/// single copy of the ranking.
+pub struct ScoreSegment<'a> {
+    /// Number of nodes
+    len: usize,
+    /// Number of hash slots, always a power of two
+    slots: usize,
+    /// Score of each node, as little-endian `f64`
+    scores: &'a [u8],
+    /// Start of each key in `keys`, as little-endian `u64`, plus the end
+    /// of the last key
+    offsets: &'a [u8],
+    /// Open addressing hash table of `node id + 1`, zero when empty
+    table: &'a [u8],
+    /// Encoded keys of all nodes
+    keys: &'a [u8],
+}
+
+impl<'a> ScoreSegment<'a> {
+    /// Opens a segment from its bytes, validating its layout
+    pub fn from_bytes(buf: &'a [u8]) -> io::Result<Self> {
+        if buf.len() < HEADER_LEN
+            || &buf[..SEGMENT_MAGIC.len()] != SEGMENT_MAGIC
+        {
+            return Err(invalid_segment("bad magic bytes"));
+        }
+
+        if buf[SEGMENT_MAGIC.len()] != SEGMENT_VERSION {
+            return Err(invalid_segment("unsupported version"));
+        }
+
+        let len = read_u64(buf, 8) as usize;
+        let slots = read_u64(buf, 16) as usize;
+        if !slots.is_power_of_two() || slots <= len {
+            return Err(invalid_segment("bad hash table size"));
+        }
+
+        let sizes = [len * 8, (len + 1) * 8, slots * 8];
+        let mut sections = Vec::new();
+        let mut pos = HEADER_LEN;
+        for size in sizes.iter() {
+            let end = pos
+                .checked_add(*size)
+                .filter(|end| *end <= buf.len())
+                .ok_or_else(|| invalid_segment("truncated"))?;
+            sections.push(&buf[pos..end]);
+            pos = end;
+        }
+
+        let segment = ScoreSegment {
+            len,
+            slots,
+            scores: sections[0],
+            offsets: sections[1],
+            table: sections[2],
+            keys: &buf[pos..],
+        };
+
+        if segment.offset(len) != segment.keys.len() {
+            return Err(invalid_segment("bad key index"));
+        }
+
+        Ok(segment)
+    }
+
+    /// Returns the score of a given node
+    pub fn get<T: SnapshotKey>(&self, node: &T) -> Option<f64> {
+        let mut key = Vec::new();
+        node.write_key(&mut key);
+
+        let mask = self.slots - 1;
+        let mut slot = checksum(&key) as usize & mask;
+        for _ in 0..self.slots {
+            let id = read_u64(self.table, slot * 8) as usize;
+            if id == 0 || id > self.len {
+                return None;
+            }
+
+            let (start, end) = (self.offset(id - 1), self.offset(id));
+            if start <= end
+                && self.keys.get(start..end) == Some(&key[..])
+            {
+                return Some(self.score(id - 1));
+            }
+
+            slot = (slot + 1) & mask;
+        }
+
+        None
+    }
+
+    /// Returns the score of the node at position `id`
+    pub fn score(&self, id: usize) -> f64 {
+        f64::from_bits(read_u64(self.scores, id * 8))
+    }
+
+    /// Return the number of nodes in the segment
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// If the segment is empty
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    fn offset(&self, id: usize) -> usize {
+        read_u64(self.offsets, id * 8) as usize
+    }
+}
+
+impl<T, S> Pagerank<T, S>
+where
+    T: Eq + Hash + Clone + SnapshotKey,
+    S: BuildHasher,
+{
+    /// Writes the current scores and a key index as a read-only segment.
+    ///
+    /// The segment is meant to be written to a file which query workers
+    /// memory-map and open with `ScoreSegment::from_bytes`, looking up
+    /// scores without loading the graph.
+    pub fn write_score_segment<W: Write>(
+        &self,
+        mut writer: W,
+    ) -> io::Result<()> {
+        let slots = (self.nodes.len() * 2).next_power_of_two().max(2);
+        let mask = slots - 1;
+        let mut table = vec![0u64; slots];
+        let mut offsets = Vec::with_capacity(self.nodes.len() + 1);
+        let mut keys = Vec::new();
+
+        for (id, node) in self.nodes.iter().enumerate() {
+            let start = keys.len();
+            offsets.push(start as u64);
+            node.node.write_key(&mut keys);
+
+            let mut slot = checksum(&keys[start..]) as usize & mask;
+            while table[slot] != 0 {
+                slot = (slot + 1) & mask;
+            }
+            table[slot] = id as u64 + 1;
+        }
+        offsets.push(keys.len() as u64);
+
+        let mut header = [0u8; HEADER_LEN];
+        header[..SEGMENT_MAGIC.len()].copy_from_slice(SEGMENT_MAGIC);
+        header[SEGMENT_MAGIC.len()] = SEGMENT_VERSION;
+        header[8..16].copy_from_slice(
+            &(self.nodes.len() as u64).to_le_bytes(),
+        );
+        header[16..24].copy_from_slice(&(slots as u64).to_le_bytes());
+        writer.write_all(&header)?;
+
+        for node in self.nodes.iter() {
+            writer.write_all(
+                &self.transformed(node.score).to_le_bytes(),
+            )?;
+        }
+        for value in offsets.iter().chain(table.iter()) {
+            writer.write_all(&value.to_le_bytes())?;
+        }
+        writer.write_all(&keys)
+    }
+}
+
+fn read_u64(buf: &[u8], pos: usize) -> u64 {
+    let mut bytes = [0u8; 8];
+    bytes.copy_from_slice(&buf[pos..pos + 8]);
+    u64::from_le_bytes(bytes)
+}
+
+fn invalid_segment(reason: &str) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!("invalid score segment: {}", reason),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_score_segment() {
+        let mut pr = Pagerank::<String>::new();
+        pr.add_edge("foo".to_string(), "bar".to_string());
+        pr.add_edge("bar".to_string(), "foo".to_string());
+        pr.add_edge("xxx".to_string(), "bar".to_string());
+        pr.calculate();
+
+        let mut buf = Vec::new();
+        pr.write_score_segment(&mut buf).expect("write");
+        let segment = ScoreSegment::from_bytes(&buf).expect("open");
+
+        assert_eq!(3, segment.len());
+        for node in ["foo", "bar", "xxx"].iter() {
+            assert_eq!(
+                pr.get_score(node.to_string()),
+                segment.get(&node.to_string())
+            );
+        }
+        assert_eq!(None, segment.get(&"unknown".to_string()));
+        assert!(ScoreSegment::from_bytes(&buf[..30]).is_err());
+    }
+}
@@ -0,0 +1,448 @@
+//! What-if analyses over a calculated graph
+#[cfg(not(feature = "std"))]
+use crate::compat::prelude::*;
+use crate::graph::cmp_scores;
+use crate::DanglingPolicy;
+use crate::Pagerank;
+use alloc::collections::VecDeque;
+use core::hash::{BuildHasher, Hash};
+
+/// Score change below which a node is not propagated any further
+const SIMULATION_TOLERANCE: f64 = 1e-10;
+
+impl<T, S> Pagerank<T, S>
+where
+    T: Eq + Hash + Clone,
+    S: BuildHasher,
+{
+    /// Estimates the scores every other node would have if the given
+    /// nodes, and all their edges, were removed. The graph itself is not
+    /// modified, other than computing the edge weights if needed. Unknown
+    /// nodes are ignored.
+    ///
+    /// Starting from the current scores, only the neighborhood affected
+    /// by the removal is recomputed: the targets of the removed nodes and
+    /// of every source whose out edges carry a different share of its
+    /// score, such as the sources of the removed nodes, and from there on
+    /// any node whose score changes. Edges carry the share they would
+    /// have in a calculation after the removal, with the weight and
+    /// affinity closures and the group discount. Pinned nodes keep their
+    /// pinned score, and with a dangling policy the score of the nodes
+    /// left without out edges is spread as a calculation would, which
+    /// reaches every node. Teleporting is over the remaining nodes, with
+    /// the personalization renormalized over the remaining seeds and the
+    /// node type shares split among the remaining nodes of each type, and
+    /// the nodes whose teleport score changes are recomputed too. Scores
+    /// should be calculated beforehand.
+    ///
+    /// Once the current scores have converged, the estimate matches, up
+    /// to the convergence and a propagation tolerance of `1e-10`, what
+    /// `remove_node` and a new calculation would give.
+    ///
+    /// Returns the remaining nodes sorted by their estimated score.
+    pub fn simulate_removal(&mut self, nodes: &[T]) -> Vec<(T, f64)> {
+        self.finalize_weights();
+        let len = self.nodes.len();
+        let mut removed = vec![false; len];
+        for node in nodes {
+            if let Some(id) = self.node_positions.get(node) {
+                removed[*id] = true;
+            }
+        }
+
+        // Share of the score of its source carried by each stored in
+        // edge after the removal, and the sources whose shares change
+        let mut shares = Vec::with_capacity(len);
+        let mut changed = vec![false; len];
+        match &self.edge_weights {
+            Some(before) => {
+                let after = self.compute_edge_weights(&removed);
+                for (source, total) in
+                    after.out_totals.iter().enumerate()
+                {
+                    changed[source] =
+                        *total != before.out_totals[source];
+                }
+                for (target, weights) in
+                    after.in_edges.iter().enumerate()
+                {
+                    let sources = self.in_edges(target);
+                    shares.push(
+                        weights
+                            .iter()
+                            .zip(sources.iter())
+                            .map(|(weight, source)| {
+                                let total = after.out_totals[*source];
+                                if total > 0f64 {
+                                    weight / total
+                                } else {
+                                    0f64
+                                }
+                            })
+                            .collect::<Vec<f64>>(),
+                    );
+                }
+            }
+            None => {
+                let mut out_weights = self
+                    .nodes
+                    .iter()
+                    .map(|n| n.out_weight)
+                    .collect::<Vec<f64>>();
+                for (target, n) in self.nodes.iter().enumerate() {
+                    if !removed[target] {
+                        continue;
+                    }
+                    let scale = self.in_edges_weight(target);
+                    for (slot, source) in
+                        self.in_edges(target).iter().enumerate()
+                    {
+                        out_weights[*source] -=
+                            scale * n.edge_weight(slot);
+                        changed[*source] = true;
+                    }
+                }
+                for (target, n) in self.nodes.iter().enumerate() {
+                    shares.push(
+                        self.in_edges(target)
+                            .iter()
+                            .enumerate()
+                            .map(|(slot, source)| {
+                                n.edge_weight(slot)
+                                    / out_weights[*source]
+                            })
+                            .collect::<Vec<f64>>(),
+                    );
+                }
+            }
+        }
+
+        // Out degree of each node after the removal, for the nodes left
+        // dangling by it
+        let mut out_edges = self
+            .nodes
+            .iter()
+            .map(|n| n.out_edges)
+            .collect::<Vec<_>>();
+        let mut targets = vec![Vec::new(); len];
+        for (target, is_removed) in removed.iter().enumerate() {
+            for source in self.in_edges(target).iter() {
+                targets[*source].push(target);
+                if *is_removed {
+                    out_edges[*source] -= 1;
+                }
+            }
+        }
+
+        // With a dangling policy, the score of the dangling nodes
+        // reaches every node, so all of them are recomputed whenever it
+        // changes
+        let remaining_len = removed.iter().filter(|r| !**r).count();
+        let before = self.teleport_scores();
+        let mut teleport = Vec::<f64>::new();
+        self.teleport_scores_without(
+            self.damping,
+            &removed,
+            &mut teleport,
+        );
+        let dangling = if self.dangling_policy
+            == DanglingPolicy::Ignore
+            || remaining_len == 0
+            || (0..len).all(|id| {
+                self.nodes[id].out_edges > 0
+                    && (removed[id] || out_edges[id] > 0)
+            }) {
+            None
+        } else {
+            let total = (0..len)
+                .filter(|id| !removed[*id])
+                .map(|id| teleport[id])
+                .sum::<f64>();
+            Some(
+                (0..len)
+                    .map(|id| match self.dangling_policy {
+                        _ if removed[id] => 0f64,
+                        DanglingPolicy::Teleport if total > 0f64 => {
+                            teleport[id] / total
+                        }
+                        _ => 1f64 / remaining_len as f64,
+                    })
+                    .collect::<Vec<f64>>(),
+            )
+        };
+        let dangling_score = |scores: &[f64]| {
+            (0..len)
+                .filter(|id| !removed[*id] && out_edges[*id] == 0)
+                .map(|id| scores[id])
+                .sum::<f64>()
+        };
+
+        // Pinned nodes keep their score and are never recomputed
+        let fixed = (0..len)
+            .map(|id| removed[id] || self.pinned_score(id).is_some())
+            .collect::<Vec<bool>>();
+        let mut pending = VecDeque::new();
+        let mut queued = vec![false; len];
+        for id in 0..len {
+            if (dangling.is_some() || teleport[id] != before[id])
+                && !fixed[id]
+            {
+                queued[id] = true;
+                pending.push_back(id);
+            } else if removed[id] || changed[id] {
+                for target in targets[id].iter() {
+                    if !queued[*target] && !fixed[*target] {
+                        queued[*target] = true;
+                        pending.push_back(*target);
+                    }
+                }
+            }
+        }
+
+        let mut scores =
+            self.nodes.iter().map(|n| n.score).collect::<Vec<f64>>();
+        for (id, is_removed) in removed.iter().enumerate() {
+            if *is_removed {
+                scores[id] = 0f64;
+            }
+        }
+
+        loop {
+            let spread =
+                dangling.as_ref().map(|_| dangling_score(&scores));
+            while let Some(id) = pending.pop_front() {
+                queued[id] = false;
+
+                let mut incoming = self
+                    .in_edges(id)
+                    .iter()
+                    .zip(shares[id].iter())
+                    .filter(|(source, _)| !removed[**source])
+                    .map(|(source, share)| scores[*source] * share)
+                    .sum::<f64>()
+                    * self.in_edges_weight_without(id, &removed);
+                if let (Some(shares), Some(spread)) =
+                    (&dangling, spread)
+                {
+                    incoming += spread * shares[id];
+                }
+                let score = teleport[id] + self.damping * incoming;
+
+                if (score - scores[id]).abs() > SIMULATION_TOLERANCE {
+                    scores[id] = score;
+                    for target in targets[id].iter() {
+                        if !queued[*target] && !fixed[*target] {
+                            queued[*target] = true;
+                            pending.push_back(*target);
+                        }
+                    }
+                }
+            }
+
+            // Goes over every node again while the score of the
+            // dangling nodes still changes
+            match spread {
+                Some(spread)
+                    if (dangling_score(&scores) - spread).abs()
+                        > SIMULATION_TOLERANCE =>
+                {
+                    for id in (0..len).filter(|id| !fixed[*id]) {
+                        queued[id] = true;
+                        pending.push_back(id);
+                    }
+                }
+                _ => break,
+            }
+        }
+
+        let mut remaining = self
+            .nodes
+            .iter()
+            .enumerate()
+            .filter(|(id, _)| !removed[*id])
+            .map(|(id, n)| (n.node.clone(), scores[id]))
+            .collect::<Vec<(T, f64)>>();
+
+        remaining.sort_by(|a, b| cmp_scores(b.1, a.1));
+
+        remaining
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{DanglingPolicy, Pagerank};
+
+    #[test]
+    fn test_simulate_removal() {
+        let edges = vec![
+            ("a", "b"),
+            ("b", "c"),
+            ("c", "a"),
+            ("a", "hub"),
+            ("hub", "c"),
+            ("d", "hub"),
+            ("d", "e"),
+        ];
+
+        let mut full = Pagerank::<&str>::new();
+        let mut without = Pagerank::<&str>::new();
+        for (source, target) in edges {
+            full.add_edge(source, target);
+            if source != "hub" && target != "hub" {
+                without.add_edge(source, target);
+            }
+        }
+        full.calculate_with_convergence(1e-12);
+        without.calculate_with_convergence(1e-12);
+
+        let simulated = full.simulate_removal(&["hub"]);
+        assert_eq!(without.len(), simulated.len());
+        for (node, score) in simulated {
+            let expected = without.get_score(node).unwrap();
+            assert!((expected - score).abs() < 1e-6);
+        }
+        assert_eq!(6, full.len());
+        assert!(full.get_score("hub").is_some());
+    }
+
+    #[test]
+    fn test_simulate_removal_weighted() {
+        let graph = || {
+            let mut pr = Pagerank::<&str>::new();
+            for (source, target) in [
+                ("a", "b"),
+                ("a", "c"),
+                ("a", "e"),
+                ("b", "c"),
+                ("c", "a"),
+                ("c", "e"),
+                ("d", "b"),
+                ("d", "c"),
+                ("d", "e"),
+                ("e", "a"),
+                ("e", "d"),
+            ] {
+                pr.add_edge(source, target);
+            }
+            pr.set_weight_fn(
+                |_, t| if *t == "b" { 9.0 } else { 1.0 },
+            );
+            pr.set_group_discount(|n| *n == "a" || *n == "d");
+            pr
+        };
+
+        let mut full = graph();
+        full.calculate_with_convergence(1e-12);
+        let simulated = full.simulate_removal(&["e"]);
+
+        let mut without = graph();
+        assert!(without.remove_node(&"e"));
+        without.calculate_with_convergence(1e-12);
+        assert_eq!(4, simulated.len());
+        for (node, score) in simulated {
+            let expected = without.get_score(node).unwrap();
+            assert!((expected - score).abs() < 1e-6);
+        }
+    }
+
+    #[test]
+    fn test_simulate_removal_teleport() {
+        let graph = |types: bool| {
+            let mut pr = Pagerank::<&str>::new();
+            for (source, target) in [
+                ("a", "b"),
+                ("b", "c"),
+                ("c", "a"),
+                ("a", "hub"),
+                ("hub", "c"),
+                ("d", "hub"),
+                ("d", "e"),
+                ("e", "d"),
+            ] {
+                pr.add_edge(source, target);
+            }
+            if types {
+                pr.set_node_type("hub", 1);
+                pr.set_node_type("d", 1);
+                pr.set_teleport_shares(vec![(0, 1.0), (1, 3.0)]);
+            } else {
+                pr.set_personalization(vec![
+                    ("hub", 1.0),
+                    ("d", 1.0),
+                ]);
+            }
+            pr
+        };
+
+        for types in [false, true] {
+            let mut full = graph(types);
+            full.calculate_with_convergence(1e-12);
+            let simulated = full.simulate_removal(&["hub"]);
+
+            let mut without = graph(types);
+            assert!(without.remove_node(&"hub"));
+            without.calculate_with_convergence(1e-12);
+            assert_eq!(5, simulated.len());
+            for (node, score) in simulated {
+                let expected = without.get_score(node).unwrap();
+                assert!((expected - score).abs() < 1e-6);
+            }
+        }
+    }
+
+    #[test]
+    fn test_simulate_removal_pins_and_dangling() {
+        let graph = |policy, teleport: usize| {
+            let mut pr = Pagerank::<&str>::new();
+            for (source, target) in [
+                ("a", "b"),
+                ("a", "d"),
+                ("b", "c"),
+                ("c", "a"),
+                ("c", "x"),
+                ("d", "b"),
+                ("y", "d"),
+            ] {
+                pr.add_edge(source, target);
+            }
+            pr.pin_score("a", 5.0);
+            pr.set_dangling_policy(policy);
+            // Uniform teleport, seeds including the removed node, or
+            // node types
+            if teleport == 1 {
+                pr.set_personalization(vec![("d", 2.0), ("x", 1.0)]);
+            } else if teleport == 2 {
+                pr.set_node_type("d", 1);
+                pr.set_node_type("x", 1);
+                pr.set_teleport_shares(vec![(0, 1.0), (1, 1.0)]);
+            }
+            pr
+        };
+
+        for policy in [
+            DanglingPolicy::Ignore,
+            DanglingPolicy::Uniform,
+            DanglingPolicy::Teleport,
+        ] {
+            for teleport in 0..3 {
+                // y is left dangling by the removal
+                let mut full = graph(policy, teleport);
+                full.calculate_with_convergence(1e-12);
+                let simulated = full.simulate_removal(&["d"]);
+
+                let mut without = graph(policy, teleport);
+                assert!(without.remove_node(&"d"));
+                without.calculate_with_convergence(1e-12);
+                assert_eq!(5, simulated.len());
+                for (node, score) in simulated {
+                    if node == "a" {
+                        assert_eq!(5.0, score);
+                    }
+                    let expected = without.get_score(node).unwrap();
+                    assert!((expected - score).abs() < 1e-6);
+                }
+            }
+        }
+    }
+}
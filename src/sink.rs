@@ -0,0 +1,105 @@
+//! Streaming scores into external stores
+use crate::Pagerank;
+use std::hash::{BuildHasher, Hash};
+use std::io;
+
+/// Destination of the scores emitted by `Pagerank::emit_scores`, for
+/// instance an embedding store or a remote endpoint.
+///
+/// Any `FnMut(&T, f64) -> io::Result<()>` closure is a sink, which makes
+/// it easy to forward scores through a callback, including one crossing
+/// an FFI boundary.
+pub trait ScoreSink<T> {
+    /// Receives the score of a single node
+    fn emit(&mut self, node: &T, score: f64) -> io::Result<()>;
+
+    /// Called once after the last score has been emitted
+    fn finish(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl<T, F> ScoreSink<T> for F
+where
+    F: FnMut(&T, f64) -> io::Result<()>,
+{
+    fn emit(&mut self, node: &T, score: f64) -> io::Result<()> {
+        self(node, score)
+    }
+}
+
+impl<T, S> Pagerank<T, S>
+where
+    T: Eq + Hash + Clone,
+    S: BuildHasher,
+{
+    /// Pushes the score of every node into `sink`, without collecting
+    /// or sorting them first. Nodes are emitted in insertion order.
+    ///
+    /// Stops at the first error returned by the sink.
+    pub fn emit_scores(
+        &self,
+        sink: &mut dyn ScoreSink<T>,
+    ) -> io::Result<()> {
+        for n in self.nodes.iter() {
+            sink.emit(&n.node, self.transformed(n.score))?;
+        }
+        sink.finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Collect(Vec<(String, f64)>, bool);
+
+    impl ScoreSink<&str> for Collect {
+        fn emit(
+            &mut self,
+            node: &&str,
+            score: f64,
+        ) -> io::Result<()> {
+            self.0.push((node.to_string(), score));
+            Ok(())
+        }
+
+        fn finish(&mut self) -> io::Result<()> {
+            self.1 = true;
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_emit_scores() {
+        let mut pr = Pagerank::<&str>::new();
+        pr.add_edge("foo", "bar");
+        pr.add_edge("bar", "foo");
+        pr.add_edge("xxx", "bar");
+        pr.calculate();
+
+        let mut sink = Collect(Vec::new(), false);
+        pr.emit_scores(&mut sink).expect("emit");
+        assert!(sink.1);
+        assert_eq!(
+            vec!["foo", "bar", "xxx"],
+            sink.0
+                .iter()
+                .map(|(n, _)| n.as_str())
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(pr.get_score("bar"), Some(sink.0[1].1));
+
+        let mut emitted = 0;
+        let mut callback = |_: &&str, _: f64| {
+            emitted += 1;
+            if emitted == 2 {
+                Err(io::Error::other("full"))
+            } else {
+                Ok(())
+            }
+        };
+        assert!(pr.emit_scores(&mut callback).is_err());
+        assert_eq!(2, emitted);
+    }
+}
@@ -0,0 +1,1376 @@
+//! Calculation of the PageRank scores
+#[cfg(not(feature = "std"))]
+use crate::compat::prelude::*;
+use crate::compat::Instant;
+use crate::{Pagerank, ScoreSet};
+#[cfg(feature = "std")]
+use alloc::collections::BTreeMap;
+use core::hash::{BuildHasher, Hash};
+use core::ops::ControlFlow;
+use core::time::Duration;
+#[cfg(feature = "std")]
+use std::io::{self, Write};
+
+/// Convergence used by `calculate`
+pub(crate) const DEFAULT_CONVERGENCE: f64 = 0.01;
+
+/// Floating point precision of the scores while iterating
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize)
+)]
+pub enum Precision {
+    /// 64 bit floats, the default
+    #[default]
+    Double,
+    /// 32 bit floats, halving the memory of the vectors used while
+    /// iterating. Scores are then accurate to about four or five
+    /// significant digits.
+    Single,
+}
+
+/// How the change of the scores between two iterations is measured to
+/// decide whether the calculation converged
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize)
+)]
+pub enum ConvergenceNorm {
+    /// L2 norm of the changes divided by the number of nodes with in
+    /// edges. This is the default.
+    #[default]
+    ScaledL2,
+    /// Sum of the absolute changes. NetworkX stops when this sum, over
+    /// scores normalized to add up to 1, is below `N * tol`; since these
+    /// scores add up to about `N`, that is a convergence of `N² * tol`.
+    L1,
+    /// L2 norm of the changes
+    L2,
+    /// Largest absolute change
+    LInf,
+    /// Largest change relative to the previous score of the node
+    MaxRelative,
+}
+
+impl ConvergenceNorm {
+    /// Adds the change from `previous` to `score` to `total`, the
+    /// running residual of an iteration before `Pagerank::residual_of`
+    #[inline]
+    pub(crate) fn accumulate(
+        self,
+        total: f64,
+        previous: f64,
+        score: f64,
+    ) -> f64 {
+        let diff = (previous - score).abs();
+        match self {
+            ConvergenceNorm::ScaledL2 | ConvergenceNorm::L2 => {
+                total + diff * diff
+            }
+            ConvergenceNorm::L1 => total + diff,
+            ConvergenceNorm::LInf => total.max(diff),
+            ConvergenceNorm::MaxRelative if diff == 0f64 => total,
+            ConvergenceNorm::MaxRelative => total
+                .max(diff / previous.abs().max(f64::MIN_POSITIVE)),
+        }
+    }
+
+    /// Turns the total accumulated over every node into the residual,
+    /// given the number of nodes with in edges
+    pub(crate) fn residual(
+        self,
+        total: f64,
+        nodes_with_in_edges: usize,
+    ) -> f64 {
+        match self {
+            ConvergenceNorm::ScaledL2 => {
+                total.sqrt() / nodes_with_in_edges.max(1) as f64
+            }
+            ConvergenceNorm::L2 => total.sqrt(),
+            _ => total,
+        }
+    }
+}
+
+/// Progress of a calculation, passed to the callback of
+/// `Pagerank::calculate_with_progress` after every iteration
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct IterationStats {
+    /// Number of the iteration, starting at 1
+    pub iteration: usize,
+    /// Residual of the iteration, compared against the tolerance
+    pub residual: f64,
+    /// Tolerance the residual is compared against
+    pub tolerance: f64,
+    /// Time spent on this iteration
+    pub duration: Duration,
+    /// Time spent since the calculation started
+    pub elapsed: Duration,
+}
+
+/// Score type the iterations can work with
+pub(crate) trait ScoreValue: Copy + Into<f64> {
+    /// Converts a score, rounding it if needed
+    fn from_f64(score: f64) -> Self;
+}
+
+impl ScoreValue for f64 {
+    fn from_f64(score: f64) -> Self {
+        score
+    }
+}
+
+impl ScoreValue for f32 {
+    fn from_f64(score: f64) -> Self {
+        score as f32
+    }
+}
+
+/// Score vectors used while iterating, which can be kept across
+/// calculations with `Pagerank::calculate_into` so a long running service
+/// doesn't allocate them again on every run. They grow to the size of
+/// the largest graph they are used with.
+#[derive(Debug, Clone, Default)]
+pub struct ScoreBuffers {
+    /// Score each node receives from teleporting, in double precision
+    teleport: Vec<f64>,
+    /// Scores of the previous iteration, in double precision
+    previous: Vec<f64>,
+    /// Score each node receives from teleporting, in single precision
+    teleport_single: Vec<f32>,
+    /// Scores of the previous iteration, in single precision
+    previous_single: Vec<f32>,
+}
+
+impl ScoreBuffers {
+    /// Creates empty buffers
+    pub fn new() -> ScoreBuffers {
+        ScoreBuffers::default()
+    }
+
+    /// Creates buffers with room for a graph of `nodes` nodes, in the
+    /// given precision
+    pub fn with_capacity(
+        nodes: usize,
+        precision: Precision,
+    ) -> ScoreBuffers {
+        let mut buffers = ScoreBuffers::new();
+        match precision {
+            Precision::Double => {
+                buffers.teleport.reserve(nodes);
+                buffers.previous.reserve(nodes);
+            }
+            Precision::Single => {
+                buffers.teleport_single.reserve(nodes);
+                buffers.previous_single.reserve(nodes);
+            }
+        }
+        buffers
+    }
+
+    /// Bytes held by the buffers
+    pub fn allocated(&self) -> usize {
+        (self.teleport.capacity() + self.previous.capacity())
+            * core::mem::size_of::<f64>()
+            + (self.teleport_single.capacity()
+                + self.previous_single.capacity())
+                * core::mem::size_of::<f32>()
+    }
+}
+
+/// Outcome of a PageRank calculation.
+///
+/// The scores themselves stay in the graph, so the result doesn't keep
+/// it borrowed; its accessors take the graph the result comes from, and
+/// return the scores it holds at the time.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize)
+)]
+pub struct RankResult {
+    /// Number of iterations run, the last one included
+    pub iterations: usize,
+    /// Whether the residual fell below the tolerance
+    pub converged: bool,
+    /// Residual of the last iteration
+    pub residual: f64,
+    /// Time spent calculating
+    pub elapsed: Duration,
+    /// Damping factor used
+    pub damping: f64,
+    /// Tolerance the residual was compared against
+    pub tolerance: f64,
+}
+
+impl RankResult {
+    /// Returns the score of a node of `graph`, see
+    /// `Pagerank::get_score`
+    pub fn get<T, S>(
+        &self,
+        graph: &Pagerank<T, S>,
+        node: T,
+    ) -> Option<f64>
+    where
+        T: Eq + Hash + Clone,
+        S: BuildHasher,
+    {
+        graph.get_score(node)
+    }
+
+    /// Returns the `k` highest scored nodes of `graph`, highest first,
+    /// see `Pagerank::top_k`
+    pub fn top_k<'a, T, S>(
+        &self,
+        graph: &'a Pagerank<T, S>,
+        k: usize,
+    ) -> Vec<(&'a T, f64)>
+    where
+        T: Eq + Hash + Clone,
+        S: BuildHasher,
+    {
+        graph.top_k(k)
+    }
+
+    /// Returns a copy of the scores of `graph`, which outlives any
+    /// later change to it, see `Pagerank::score_set`
+    pub fn scores<T, S>(&self, graph: &Pagerank<T, S>) -> ScoreSet<T>
+    where
+        T: Eq + Hash + Clone,
+        S: BuildHasher,
+    {
+        graph.score_set()
+    }
+}
+
+impl<T, S> Pagerank<T, S>
+where
+    T: Eq + Hash + Clone,
+    S: BuildHasher,
+{
+    /// Sets the precision of the scores while iterating. Single
+    /// precision holds the teleport scores, the scores of the previous
+    /// iteration and the shares of the dangling score as `f32`, halving
+    /// their memory, for graphs so large these vectors dominate it.
+    ///
+    /// Scores are still stored and returned as `f64`, rounded to the
+    /// chosen precision. In single precision, convergences below
+    /// `f32::EPSILON` are raised to it, since rounding keeps the scores
+    /// from settling any further. `calculate_linear` always works in
+    /// double precision.
+    pub fn set_precision(&mut self, precision: Precision) {
+        self.precision = precision;
+    }
+
+    /// Returns the precision of the scores while iterating
+    pub fn precision(&self) -> Precision {
+        self.precision
+    }
+
+    /// Lowest convergence reachable with the current precision
+    fn reachable(&self, convergence: f64) -> f64 {
+        match self.precision {
+            Precision::Double => convergence,
+            Precision::Single => convergence.max(f32::EPSILON as f64),
+        }
+    }
+
+    /// Calculates PageRank with custom convergence.
+    ///
+    /// It gives up, reporting that it didn't converge, once the residual
+    /// is not a finite number, since it would never get below the
+    /// convergence, or after the iteration limit given to
+    /// `PagerankBuilder`, if any.
+    pub fn calculate_with_convergence(
+        &mut self,
+        convergence: f64,
+    ) -> RankResult {
+        let mut scratch = ScoreBuffers::new();
+        self.calculate_with_convergence_into(
+            convergence,
+            &mut scratch,
+        )
+    }
+
+    /// Calculates PageRank like `calculate`, iterating over the score
+    /// vectors of `scratch` instead of allocating them, so the buffers
+    /// can be reused by the next calculation
+    pub fn calculate_into(
+        &mut self,
+        scratch: &mut ScoreBuffers,
+    ) -> RankResult {
+        self.calculate_with_convergence_into(
+            self.convergence,
+            scratch,
+        )
+    }
+
+    /// Calculates PageRank like `calculate_with_convergence`, iterating
+    /// over the score vectors of `scratch`. See `calculate_into`.
+    pub fn calculate_with_convergence_into(
+        &mut self,
+        convergence: f64,
+        scratch: &mut ScoreBuffers,
+    ) -> RankResult {
+        let started = Instant::now();
+        let mut iterations = 0;
+        let convergence = self.reachable(convergence);
+
+        loop {
+            let residual = self.step_into(scratch);
+            iterations += 1;
+            if self.finished(residual, convergence, iterations) {
+                return self.rank_result(
+                    iterations,
+                    residual,
+                    convergence,
+                    started,
+                );
+            }
+        }
+    }
+
+    /// Calculates PageRank with custom convergence, giving up after
+    /// `max_iterations` iterations so pathological graphs can't keep the
+    /// caller waiting. Whether it converged is reported in the result,
+    /// along with the residual of the last iteration.
+    pub fn calculate_with_limits(
+        &mut self,
+        convergence: f64,
+        max_iterations: usize,
+    ) -> RankResult {
+        let started = Instant::now();
+        let convergence = self.reachable(convergence);
+        let mut residual = f64::INFINITY;
+
+        for iterations in 1..=max_iterations {
+            residual = self.calculate_step();
+            if residual < convergence || !residual.is_finite() {
+                return self.rank_result(
+                    iterations,
+                    residual,
+                    convergence,
+                    started,
+                );
+            }
+        }
+
+        self.rank_result(
+            max_iterations,
+            residual,
+            convergence,
+            started,
+        )
+    }
+
+    /// Calculates PageRank with custom convergence, calling `progress`
+    /// after every iteration with its residual and timing, so long
+    /// calculations can be reported in a UI.
+    ///
+    /// Returning `ControlFlow::Break` from `progress` cancels the
+    /// calculation, keeping the scores of the last iteration. It also
+    /// stops as `calculate_with_convergence` does. Whether it converged
+    /// is reported in the result.
+    pub fn calculate_with_progress<F>(
+        &mut self,
+        convergence: f64,
+        mut progress: F,
+    ) -> RankResult
+    where
+        F: FnMut(IterationStats) -> ControlFlow<()>,
+    {
+        let started = Instant::now();
+        let convergence = self.reachable(convergence);
+        let mut iteration = 0;
+
+        loop {
+            iteration += 1;
+            let step = Instant::now();
+            let residual = self.calculate_step();
+            let flow = progress(IterationStats {
+                iteration,
+                residual,
+                tolerance: convergence,
+                duration: step.elapsed(),
+                elapsed: started.elapsed(),
+            });
+
+            if self.finished(residual, convergence, iteration) {
+                return self.rank_result(
+                    iteration,
+                    residual,
+                    convergence,
+                    started,
+                );
+            }
+            if flow.is_break() {
+                return self.rank_result(
+                    iteration,
+                    residual,
+                    convergence,
+                    started,
+                );
+            }
+        }
+    }
+
+    /// Calculates PageRank until it converges, by default once the
+    /// residual is below 0.01, or with the convergence and the
+    /// iteration limit given to `PagerankBuilder`
+    pub fn calculate(&mut self) -> RankResult {
+        match self.max_iterations {
+            Some(max_iterations) => self.calculate_with_limits(
+                self.convergence,
+                max_iterations,
+            ),
+            None => self.calculate_with_convergence(self.convergence),
+        }
+    }
+
+    /// Rescales the scores so they add up to 1, a probability
+    /// distribution as computed by most other PageRank implementations,
+    /// which makes comparing outputs easier. Scores are otherwise about
+    /// `1` per node, since every node gets `1 - d` from teleporting.
+    ///
+    /// Returns the sum of the scores before rescaling, to go back to the
+    /// raw scale if needed. Nothing changes if it is not positive, as in
+    /// an empty graph. The next calculation starts from the rescaled
+    /// scores and gives raw scores again.
+    pub fn normalize(&mut self) -> f64 {
+        let total = self.nodes.iter().map(|n| n.score).sum::<f64>();
+        if total > 0f64 {
+            for n in self.nodes.iter_mut() {
+                n.score /= total;
+            }
+        }
+        total
+    }
+
+    /// Calculates PageRank until it converges or the time budget expires,
+    /// whatever happens first.
+    ///
+    /// The budget is checked between iterations, so the call may take up
+    /// to one iteration longer than `budget`. It also stops as
+    /// `calculate_with_convergence` does. Whether it converged is
+    /// reported in the result. It needs the `std` feature, for the
+    /// clock.
+    #[cfg(feature = "std")]
+    pub fn calculate_for(&mut self, budget: Duration) -> RankResult {
+        let started = Instant::now();
+        let mut iterations = 0;
+        let convergence = self.reachable(self.convergence);
+
+        loop {
+            let residual = self.calculate_step();
+            iterations += 1;
+            if self.finished(residual, convergence, iterations)
+                || started.elapsed() >= budget
+            {
+                return self.rank_result(
+                    iterations,
+                    residual,
+                    convergence,
+                    started,
+                );
+            }
+        }
+    }
+
+    /// Whether an iterative calculation stops after `iterations`
+    /// iterations with the given residual: once it converged, once the
+    /// residual is not a finite number, or at the iteration limit given
+    /// to `PagerankBuilder`, if any
+    pub(crate) fn finished(
+        &self,
+        residual: f64,
+        convergence: f64,
+        iterations: usize,
+    ) -> bool {
+        residual < convergence
+            || !residual.is_finite()
+            || self
+                .max_iterations
+                .is_some_and(|max| iterations >= max)
+    }
+
+    /// Finishes a calculation which started at `started`, decaying
+    /// stale nodes if enabled, and builds its result
+    pub(crate) fn rank_result(
+        &mut self,
+        iterations: usize,
+        residual: f64,
+        tolerance: f64,
+        started: Instant,
+    ) -> RankResult {
+        self.apply_stale_decay();
+        let result = RankResult {
+            iterations,
+            converged: residual < tolerance,
+            residual,
+            elapsed: started.elapsed(),
+            damping: self.damping,
+            tolerance,
+        };
+        self.last_result = Some(result);
+        result
+    }
+
+    /// Calculates PageRank by solving the linear system
+    /// `(I - dM)x = t` with the BiCGSTAB Krylov method instead of
+    /// power iteration, where `M` is the transition matrix of the graph
+    /// and `t` the teleport scores.
+    ///
+    /// `tolerance` is the residual relative to the right-hand side at
+    /// which the solver stops. Each iteration costs two matrix-vector
+    /// products.
+    #[cfg(feature = "linalg")]
+    pub fn calculate_linear(
+        &mut self,
+        tolerance: f64,
+        max_iterations: usize,
+    ) -> RankResult {
+        let started = Instant::now();
+        self.finalize_weights();
+        let len = self.nodes.len();
+        let b = self.teleport_scores();
+        let dangling = self.dangling_shares(&b);
+        let dangling = dangling.as_deref();
+        let mut x =
+            self.nodes.iter().map(|n| n.score).collect::<Vec<f64>>();
+
+        let dot = |a: &[f64], b: &[f64]| {
+            a.iter().zip(b.iter()).map(|(a, b)| a * b).sum::<f64>()
+        };
+
+        let ax = self.linear_operator(&x, dangling);
+        let mut r = b
+            .iter()
+            .zip(ax.iter())
+            .map(|(b, ax)| b - ax)
+            .collect::<Vec<f64>>();
+        let r_hat = r.clone();
+        let threshold = tolerance * dot(&b, &b).sqrt();
+
+        let mut rho = 1f64;
+        let mut alpha = 1f64;
+        let mut omega = 1f64;
+        let mut v = vec![0f64; len];
+        let mut p = vec![0f64; len];
+        let mut iterations = 0;
+
+        while iterations < max_iterations
+            && dot(&r, &r).sqrt() > threshold
+        {
+            iterations += 1;
+
+            let rho_next = dot(&r_hat, &r);
+            let beta = (rho_next / rho) * (alpha / omega);
+            rho = rho_next;
+
+            for i in 0..len {
+                p[i] = r[i] + beta * (p[i] - omega * v[i]);
+            }
+
+            v = self.linear_operator(&p, dangling);
+            let r_hat_v = dot(&r_hat, &v);
+            if r_hat_v == 0f64 {
+                break;
+            }
+            alpha = rho / r_hat_v;
+
+            let s = r
+                .iter()
+                .zip(v.iter())
+                .map(|(r, v)| r - alpha * v)
+                .collect::<Vec<f64>>();
+
+            if dot(&s, &s).sqrt() <= threshold {
+                for i in 0..len {
+                    x[i] += alpha * p[i];
+                }
+                r = s;
+                break;
+            }
+
+            let t = self.linear_operator(&s, dangling);
+            let t_t = dot(&t, &t);
+            if t_t == 0f64 {
+                break;
+            }
+            omega = dot(&t, &s) / t_t;
+
+            for i in 0..len {
+                x[i] += alpha * p[i] + omega * s[i];
+                r[i] = s[i] - omega * t[i];
+            }
+        }
+
+        for (node, score) in self.nodes.iter_mut().zip(x) {
+            node.score = score;
+        }
+
+        let norm = dot(&b, &b).sqrt();
+        let residual =
+            dot(&r, &r).sqrt() / norm.max(f64::MIN_POSITIVE);
+        let mut result = self
+            .rank_result(iterations, residual, tolerance, started);
+        result.converged = residual <= tolerance;
+        self.last_result = Some(result);
+        result
+    }
+
+    /// Multiplies `x` by `(I - dM)`, where `M` includes the links from
+    /// dangling nodes given by their shares, if any
+    #[cfg(feature = "linalg")]
+    fn linear_operator(
+        &self,
+        x: &[f64],
+        dangling: Option<&[f64]>,
+    ) -> Vec<f64> {
+        let dangling_score = match dangling {
+            Some(_) => self.dangling_score(x),
+            None => 0f64,
+        };
+        (0..self.nodes.len())
+            .map(|id| {
+                let mut incoming = self.incoming(id, x);
+                if let Some(shares) = dangling {
+                    incoming += dangling_score * shares[id];
+                }
+                x[id] - self.damping * incoming
+            })
+            .collect()
+    }
+
+    /// Writes the transition matrix `M` used by the calculation, where
+    /// each iteration computes `teleport + d * M * scores`, in coordinate
+    /// form: one `row col value` line per non zero entry, sorted by row
+    /// then column.
+    ///
+    /// Rows and columns are node ids, as given by `iter_nodes`: entry
+    /// `(t, s)` is the share of the score of `s` passed on to `t`. Values
+    /// are written with full precision, so the matrix can be loaded into
+    /// an external eigen-solver to cross-validate the scores. Edge
+    /// weights are computed first if needed. Returns the number of
+    /// entries written.
+    #[cfg(feature = "std")]
+    pub fn export_transition_matrix<W: Write>(
+        &mut self,
+        mut writer: W,
+    ) -> io::Result<usize> {
+        self.finalize_weights();
+
+        let mut entries = 0;
+        let mut row = BTreeMap::new();
+        for target in 0..self.nodes.len() {
+            row.clear();
+            let scale = self.in_edges_weight(target);
+            for (slot, source) in
+                self.in_edges(target).iter().enumerate()
+            {
+                *row.entry(*source).or_insert(0f64) +=
+                    self.edge_share(*source, target, slot) * scale;
+            }
+
+            for (source, value) in row.iter() {
+                writeln!(
+                    writer,
+                    "{} {} {:?}",
+                    target, source, value
+                )?;
+                entries += 1;
+            }
+        }
+
+        Ok(entries)
+    }
+
+    /// Returns the score each node receives from teleporting: `1 - d`
+    /// for every node, or the same total mass split among the nodes of
+    /// the personalization vector, or else by node type.
+    pub(crate) fn teleport_scores(&self) -> Vec<f64> {
+        let mut scores = Vec::new();
+        self.teleport_scores_into(self.damping, &mut scores);
+        scores
+    }
+
+    /// Writes the teleport scores with the damping factor `damping` into
+    /// `scores`, see `teleport_scores`
+    pub(crate) fn teleport_scores_into<F: ScoreValue>(
+        &self,
+        damping: f64,
+        scores: &mut Vec<F>,
+    ) {
+        self.teleport_scores_without(damping, &[], scores);
+    }
+
+    /// Writes the teleport scores into `scores` as if the nodes flagged
+    /// in `removed`, if any, were not in the graph, as they would be
+    /// after `remove_node`: they get nothing, the total mass is that of
+    /// the remaining nodes, and the personalization is renormalized over
+    /// the remaining seeds, or uniform if none is left.
+    pub(crate) fn teleport_scores_without<F: ScoreValue>(
+        &self,
+        damping: f64,
+        removed: &[bool],
+        scores: &mut Vec<F>,
+    ) {
+        let is_removed = |id: usize| removed.get(id) == Some(&true);
+        let remaining =
+            self.nodes.len() - removed.iter().filter(|r| **r).count();
+        let teleport = 1f64 - damping;
+        let total = teleport * remaining as f64;
+        let seeds = if removed.is_empty() {
+            1f64
+        } else {
+            self.personalization
+                .iter()
+                .filter(|(id, _)| !is_removed(*id))
+                .map(|(_, weight)| weight)
+                .sum::<f64>()
+        };
+        scores.clear();
+        if self.personalization.is_empty() || seeds == 0f64 {
+            if !self.teleport_shares.is_empty() {
+                scores.extend(
+                    self.teleport_by_type::<F>(total, removed),
+                );
+            } else {
+                scores.extend((0..self.nodes.len()).map(|id| {
+                    F::from_f64(if is_removed(id) {
+                        0f64
+                    } else {
+                        teleport
+                    })
+                }));
+            }
+            return;
+        }
+
+        scores.resize(self.nodes.len(), F::from_f64(0f64));
+        for (id, weight) in self.personalization.iter() {
+            if is_removed(*id) {
+                continue;
+            }
+            scores[*id] = F::from_f64(if removed.is_empty() {
+                total * weight
+            } else {
+                total * (weight / seeds)
+            });
+        }
+    }
+
+    /// Calculates a single iteration of the PageRank
+    ///
+    /// Returns the convergence of the iteration. Graphs without any edge
+    /// and a zero damping factor always converge on the first iteration,
+    /// since every node gets the teleport score.
+    pub fn calculate_step(&mut self) -> f64 {
+        self.step_into(&mut ScoreBuffers::new())
+    }
+
+    /// Calculates a single iteration over the score vectors of
+    /// `scratch`, see `calculate_step`
+    fn step_into(&mut self, scratch: &mut ScoreBuffers) -> f64 {
+        self.finalize_weights();
+        match self.precision {
+            Precision::Double => self.step_with(
+                &mut scratch.teleport,
+                &mut scratch.previous,
+            ),
+            Precision::Single => self.step_with(
+                &mut scratch.teleport_single,
+                &mut scratch.previous_single,
+            ),
+        }
+    }
+
+    /// Calculates a single iteration with the teleport scores and the
+    /// previous scores held as `F`, in the given vectors
+    fn step_with<F: ScoreValue>(
+        &mut self,
+        teleport: &mut Vec<F>,
+        previous: &mut Vec<F>,
+    ) -> f64 {
+        self.teleport_scores_into(self.damping, teleport);
+        let teleport = &*teleport;
+        let dangling = self.dangling_shares(teleport);
+
+        if self.damping == 0f64
+            || (self.len_nodes_with_in_edges() == 0
+                && dangling.is_none())
+        {
+            let changes = self
+                .nodes
+                .iter()
+                .zip(teleport.iter())
+                .enumerate()
+                .map(|(id, (n, teleport))| {
+                    let score = self
+                        .pinned_score(id)
+                        .unwrap_or((*teleport).into());
+                    (n.score, score)
+                })
+                .collect::<Vec<(f64, f64)>>();
+            let convergence = match self.convergence_norm {
+                // There may be no node with in edges to scale by
+                ConvergenceNorm::ScaledL2 => changes
+                    .iter()
+                    .map(|(previous, score)| (previous - score).abs())
+                    .fold(0f64, f64::max),
+                _ => self.residual(changes.into_iter()),
+            };
+
+            for (id, teleport) in teleport.iter().enumerate() {
+                self.nodes[id].score = self
+                    .pinned_score(id)
+                    .unwrap_or((*teleport).into());
+            }
+
+            return convergence;
+        }
+
+        self.power_step(teleport, dangling.as_deref(), previous)
+    }
+
+    /// Sets how the change of the scores between two iterations is
+    /// measured, to match the stopping criterion of other tools
+    pub fn set_convergence_norm(&mut self, norm: ConvergenceNorm) {
+        self.convergence_norm = norm;
+    }
+
+    /// Returns how the change of the scores between two iterations is
+    /// measured
+    pub fn convergence_norm(&self) -> ConvergenceNorm {
+        self.convergence_norm
+    }
+
+    /// Measures the change from each previous score to the new one with
+    /// the convergence norm
+    pub(crate) fn residual<I>(&mut self, changes: I) -> f64
+    where
+        I: Iterator<Item = (f64, f64)>,
+    {
+        let norm = self.convergence_norm;
+        let total = changes.fold(0f64, |total, (previous, score)| {
+            norm.accumulate(total, previous, score)
+        });
+        self.residual_of(total)
+    }
+
+    /// Turns the total accumulated by `ConvergenceNorm::accumulate` over
+    /// every node into the residual
+    fn residual_of(&mut self, total: f64) -> f64 {
+        let scale = self.len_nodes_with_in_edges();
+        self.convergence_norm.residual(total, scale)
+    }
+
+    /// Updates every score with the teleport scores and the scores of
+    /// the previous iteration held as `F`, spreading the score of dangling nodes by the given
+    /// shares, if any. `scores` is overwritten with the previous scores.
+    /// Returns the residual of the iteration.
+    ///
+    /// The residual is accumulated while the scores are updated, so each
+    /// iteration goes over the nodes once after copying their scores.
+    fn power_step<F: ScoreValue>(
+        &mut self,
+        teleport: &[F],
+        dangling: Option<&[F]>,
+        scores: &mut Vec<F>,
+    ) -> f64 {
+        scores.clear();
+        scores
+            .extend(self.nodes.iter().map(|n| F::from_f64(n.score)));
+        let dangling_score = match dangling {
+            Some(_) => self.dangling_score(scores),
+            None => 0f64,
+        };
+        let norm = self.convergence_norm;
+        let mut total = 0f64;
+        for id in 0..self.nodes.len() {
+            let score = match self.pinned_score(id) {
+                Some(score) => score,
+                None => {
+                    let mut incoming = self.incoming(id, scores);
+                    if let Some(shares) = dangling {
+                        incoming +=
+                            dangling_score * shares[id].into();
+                    }
+                    F::from_f64(
+                        teleport[id].into() + self.damping * incoming,
+                    )
+                    .into()
+                }
+            };
+            total = norm.accumulate(total, scores[id].into(), score);
+            self.nodes[id].score = score;
+        }
+
+        self.residual_of(total)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #[cfg(feature = "generators")]
+    use crate::{ConvergenceNorm, Precision, ScoreBuffers};
+    use crate::{DampingError, Pagerank};
+    use std::collections::HashMap;
+    use std::ops::ControlFlow;
+    use std::time::Duration;
+
+    #[test]
+    fn test_iteration() {
+        let mut pr = Pagerank::<&str>::new();
+        pr.add_edge("foo", "bar");
+        pr.add_edge("bar", "foo");
+        pr.add_edge("xxx", "bar");
+        pr.add_edge("yyy", "xxx");
+
+        pr.calculate_step();
+
+        assert_eq!(
+            vec!["bar", "foo", "xxx", "yyy"],
+            pr.nodes()
+                .iter()
+                .map(|(node, _)| **node)
+                .collect::<Vec<&str>>()
+        );
+    }
+
+    #[test]
+    fn test_iterations() {
+        let mut pr = Pagerank::<&str>::new();
+        pr.add_edge("foo", "bar");
+        pr.add_edge("bar", "foo");
+        pr.add_edge("xxx", "bar");
+        pr.add_edge("yyy", "xxx");
+
+        assert!(pr.calculate_step() > pr.calculate_step());
+        pr.calculate_step();
+
+        assert_eq!(
+            vec!["bar", "foo", "xxx", "yyy"],
+            pr.nodes()
+                .iter()
+                .map(|(node, _)| **node)
+                .collect::<Vec<&str>>()
+        );
+    }
+
+    #[test]
+    fn test_full_run() {
+        let mut pr = Pagerank::<&str>::new();
+        pr.add_edge("foo", "bar");
+        pr.add_edge("bar", "foo");
+        pr.add_edge("xxx", "bar");
+        pr.add_edge("yyy", "xxx");
+
+        let result = pr.calculate();
+        assert_eq!(17, result.iterations);
+        assert!(result.converged);
+        assert_eq!(0.85, result.damping);
+        assert!(result.residual < result.tolerance);
+        assert_eq!(pr.get_score("bar"), result.get(&pr, "bar"));
+        assert_eq!(
+            vec!["bar", "foo"],
+            result
+                .top_k(&pr, 2)
+                .iter()
+                .map(|(node, _)| **node)
+                .collect::<Vec<&str>>()
+        );
+        let scores = result.scores(&pr);
+        pr.add_edge("yyy", "bar");
+        pr.calculate();
+        assert_eq!(4, scores.len());
+        assert!(scores.get(&"bar") < pr.get_score("bar"));
+
+        assert_eq!(
+            vec!["bar", "foo", "xxx", "yyy"],
+            pr.nodes()
+                .iter()
+                .map(|(node, _)| **node)
+                .collect::<Vec<&str>>()
+        );
+    }
+
+    #[test]
+    /// https://en.wikipedia.org/wiki/PageRank#/media/File:PageRanks-Example.svg
+    fn test_pagerank_example() {
+        let mut pr = Pagerank::new();
+        let edges = vec![
+            ("D", "A"),
+            ("D", "B"),
+            ("B", "C"),
+            ("C", "B"),
+            ("E", "B"),
+            ("E", "F"),
+            ("F", "B"),
+            ("F", "E"),
+            ("G", "B"),
+            ("G", "E"),
+            ("H", "B"),
+            ("H", "E"),
+            ("I", "B"),
+            ("I", "E"),
+            ("J", "E"),
+            ("K", "E"),
+        ];
+
+        edges
+            .iter()
+            .map(|(l1, l2)| pr.add_edge(*l1, *l2))
+            .for_each(drop);
+
+        pr.calculate();
+
+        assert_eq!(
+            vec![
+                "B", "C", "E", "F", "A", "D", "G", "H", "I", "J", "K"
+            ],
+            pr.nodes()
+                .iter()
+                .map(|(node, _)| **node)
+                .collect::<Vec<&str>>()
+        );
+    }
+
+    #[cfg(feature = "linalg")]
+    #[test]
+    fn test_linear_solver() {
+        let edges =
+            vec![("foo", "bar"), ("bar", "foo"), ("xxx", "bar")];
+
+        let mut power = Pagerank::<&str>::new();
+        let mut linear = Pagerank::<&str>::new();
+        for (source, target) in edges {
+            power.add_edge(source, target);
+            linear.add_edge(source, target);
+        }
+
+        power.calculate_with_convergence(1e-12);
+        let result = linear.calculate_linear(1e-12, 100);
+        assert!(result.iterations <= 3);
+        assert!(result.converged);
+
+        for node in ["foo", "bar", "xxx"].iter() {
+            let diff = power.get_score(*node).unwrap()
+                - linear.get_score(*node).unwrap();
+            assert!(diff.abs() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn test_damping_edge_cases() {
+        let mut pr = Pagerank::<&str>::new();
+        assert_eq!(
+            Err(DampingError::OutOfRange(100)),
+            pr.set_damping_factor(100)
+        );
+
+        pr.add_edge("foo", "bar");
+        pr.add_edge("xxx", "bar");
+        pr.set_damping_factor(0).expect("valid damping");
+        pr.calculate();
+        assert_eq!(Some(1f64), pr.get_score("foo"));
+        assert_eq!(Some(1f64), pr.get_score("bar"));
+
+        let mut pr = Pagerank::<&str>::new();
+        pr.insert_node("foo");
+        assert_eq!(1, pr.calculate().iterations);
+    }
+
+    #[test]
+    fn test_non_finite_residual() {
+        let mut pr = Pagerank::<&str>::new();
+        pr.add_edge("foo", "bar");
+        pr.add_edge("bar", "foo");
+        pr.add_edge("xxx", "bar");
+
+        // A NaN score never converges, but the calculation returns
+        let id = pr.node_positions[&"foo"];
+        pr.nodes[id].score = f64::NAN;
+        let result = pr.calculate_with_convergence(1e-9);
+        assert!(!result.converged);
+        assert!(result.residual.is_nan());
+        assert_eq!(1, result.iterations);
+
+        pr.nodes[id].score = f64::NAN;
+        let result = pr.calculate_with_progress(1e-9, |_| {
+            ControlFlow::Continue(())
+        });
+        assert!(!result.converged);
+        pr.nodes[id].score = f64::NAN;
+        assert!(
+            !pr.calculate_for(Duration::from_secs(3600)).converged
+        );
+        pr.nodes[id].score = f64::NAN;
+        let result = loop {
+            if let Some(result) = pr.calculate_bounded(1e-9, 1) {
+                break result;
+            }
+        };
+        assert!(!result.converged);
+
+        // The iteration limit of the builder applies too
+        let mut pr = crate::PagerankBuilder::new()
+            .max_iterations(3)
+            .build()
+            .unwrap();
+        pr.add_edge("foo", "bar");
+        pr.add_edge("bar", "foo");
+        pr.add_edge("xxx", "bar");
+        let result = pr.calculate_with_convergence(1e-15);
+        assert_eq!(3, result.iterations);
+        assert!(!result.converged);
+    }
+
+    #[test]
+    fn test_calculate_for() {
+        let mut pr = Pagerank::<&str>::new();
+        pr.add_edge("foo", "bar");
+        pr.add_edge("bar", "foo");
+        pr.add_edge("xxx", "bar");
+        pr.add_edge("yyy", "xxx");
+
+        let result = pr.calculate_for(Duration::from_secs(0));
+        assert_eq!(1, result.iterations);
+        assert!(!result.converged);
+        assert!(result.residual >= 0.01);
+
+        let result = pr.calculate_for(Duration::from_secs(60));
+        assert!(result.iterations > 0);
+        assert!(result.converged);
+    }
+
+    #[test]
+    fn test_personalization() {
+        let mut pr = Pagerank::<&str>::new();
+        pr.add_edge("foo", "bar");
+        pr.add_edge("bar", "foo");
+        pr.add_edge("xxx", "yyy");
+        pr.set_personalization(vec![("xxx", 3.0), ("yyy", 1.0)]);
+        pr.calculate_with_convergence(1e-12);
+
+        assert!(pr.get_score("foo").unwrap() < 1e-9);
+        assert!(pr.get_score("bar").unwrap() < 1e-9);
+        let xxx = pr.get_score("xxx").unwrap();
+        assert!((xxx - 0.15 * 4.0 * 0.75).abs() < 1e-9);
+        assert_eq!(
+            vec!["yyy", "xxx", "foo", "bar"],
+            pr.nodes()
+                .iter()
+                .map(|(node, _)| **node)
+                .collect::<Vec<&str>>()
+        );
+    }
+
+    #[test]
+    fn test_export_transition_matrix() {
+        let mut pr = Pagerank::<&str>::new();
+        pr.add_edge("foo", "bar");
+        pr.add_edge("bar", "foo");
+        pr.add_edge("xxx", "bar");
+        pr.add_edge("xxx", "bar");
+        pr.add_edge("xxx", "foo");
+
+        let mut buf = Vec::new();
+        assert_eq!(4, pr.export_transition_matrix(&mut buf).unwrap());
+        let matrix = String::from_utf8(buf).unwrap();
+        assert_eq!(
+            "0 1 1.0\n0 2 0.3333333333333333\n\
+             1 0 1.0\n1 2 0.6666666666666666\n",
+            matrix
+        );
+
+        pr.calculate_with_convergence(1e-12);
+        let score = |id: usize| pr.nodes[id].score;
+        let bar = 0.15 + 0.85 * (score(0) + 2.0 / 3.0 * score(2));
+        assert!((score(1) - bar).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_personalization_from_map() {
+        let mut pr = Pagerank::<&str>::new();
+        pr.add_edge("user", "a");
+        pr.add_edge("a", "b");
+        pr.add_edge("b", "a");
+        pr.add_edge("c", "b");
+
+        let mut seeds = HashMap::new();
+        seeds.insert("user", 2.0);
+        seeds.insert("c", 0.0);
+        pr.set_personalization(seeds);
+        assert_eq!(vec![(&"user", 1.0)], pr.personalization());
+
+        pr.calculate_with_convergence(1e-12);
+        assert!(pr.get_score("c").unwrap() < 1e-9);
+        assert!(
+            pr.get_score("a").unwrap() > pr.get_score("b").unwrap()
+        );
+
+        pr.set_personalization(HashMap::new());
+        assert!(pr.personalization().is_empty());
+        pr.calculate_with_convergence(1e-12);
+        assert!(pr.get_score("c").unwrap() > 0.1);
+    }
+
+    #[cfg(feature = "generators")]
+    #[test]
+    fn test_single_precision() {
+        let mut pr =
+            crate::generators::watts_strogatz(300, 6, 0.3, 3);
+        let mut single =
+            crate::generators::watts_strogatz(300, 6, 0.3, 3);
+        single.set_precision(Precision::Single);
+        assert_eq!(Precision::Single, single.precision());
+
+        pr.calculate_with_convergence(1e-12);
+        let result = single.calculate_with_convergence(1e-12);
+        assert_eq!(f32::EPSILON as f64, result.tolerance);
+        for node in 0..300 {
+            let a = pr.get_score(node).unwrap();
+            let b = single.get_score(node).unwrap();
+            assert!((a - b).abs() < 1e-4 * a);
+        }
+    }
+
+    #[cfg(feature = "generators")]
+    #[test]
+    fn test_convergence_norm() {
+        let norms = [
+            ConvergenceNorm::ScaledL2,
+            ConvergenceNorm::L1,
+            ConvergenceNorm::L2,
+            ConvergenceNorm::LInf,
+            ConvergenceNorm::MaxRelative,
+        ];
+        let mut reference =
+            crate::generators::watts_strogatz(50, 4, 0.2, 1);
+        reference.calculate_with_convergence(1e-14);
+
+        for norm in norms.iter() {
+            let mut pr =
+                crate::generators::watts_strogatz(50, 4, 0.2, 1);
+            pr.set_convergence_norm(*norm);
+            assert_eq!(*norm, pr.convergence_norm());
+
+            let first = pr.calculate_step();
+            let second = pr.calculate_step();
+            assert!(second < first);
+            pr.calculate_with_convergence(1e-10);
+            for node in 0..50 {
+                let a = reference.get_score(node).unwrap();
+                let b = pr.get_score(node).unwrap();
+                assert!((a - b).abs() < 1e-8);
+            }
+        }
+
+        let mut pr = Pagerank::<u32>::new();
+        pr.insert_node(1);
+        pr.set_convergence_norm(ConvergenceNorm::L1);
+        pr.insert_node(2);
+        assert!((pr.calculate_step()).abs() < 1e-12);
+    }
+
+    #[cfg(feature = "generators")]
+    #[test]
+    fn test_calculate_with_limits() {
+        let mut pr =
+            crate::generators::watts_strogatz(100, 4, 0.3, 5);
+        let result = pr.calculate_with_limits(1e-12, 3);
+        assert!(!result.converged);
+        assert_eq!(3, result.iterations);
+        assert!(result.residual >= 1e-12);
+
+        let mut expected =
+            crate::generators::watts_strogatz(100, 4, 0.3, 5);
+        let full = expected.calculate_with_convergence(1e-12);
+        let result = pr.calculate_with_limits(1e-12, 1000);
+        assert!(result.converged);
+        assert!(result.iterations < full.iterations);
+
+        let result = pr.calculate_with_limits(1e-12, 0);
+        assert!(!result.converged);
+        assert_eq!(f64::INFINITY, result.residual);
+    }
+
+    #[cfg(feature = "generators")]
+    #[test]
+    fn test_calculate_with_progress() {
+        let mut pr =
+            crate::generators::watts_strogatz(100, 4, 0.3, 5);
+        let mut seen = Vec::new();
+        let result = pr.calculate_with_progress(1e-9, |stats| {
+            seen.push(stats.residual);
+            ControlFlow::Continue(())
+        });
+        assert!(result.converged);
+        assert_eq!(result.iterations, seen.len());
+        assert_eq!(Some(&result.residual), seen.last());
+
+        let mut pr =
+            crate::generators::watts_strogatz(100, 4, 0.3, 5);
+        let result = pr.calculate_with_progress(1e-9, |stats| {
+            if stats.iteration == 3 {
+                ControlFlow::Break(())
+            } else {
+                ControlFlow::Continue(())
+            }
+        });
+        assert!(!result.converged);
+        assert_eq!(3, result.iterations);
+    }
+
+    #[cfg(feature = "generators")]
+    #[test]
+    fn test_calculate_into() {
+        let mut scratch =
+            ScoreBuffers::with_capacity(10, Precision::Double);
+        let allocated = scratch.allocated();
+        assert!(allocated >= 20 * 8);
+        let single =
+            ScoreBuffers::with_capacity(10, Precision::Single);
+        assert_eq!(allocated / 2, single.allocated());
+
+        let mut expected = crate::generators::ring(10);
+        expected.add_edge(0, 5);
+        let expected_result = expected.calculate();
+        for _ in 0..2 {
+            let mut pr = crate::generators::ring(10);
+            pr.add_edge(0, 5);
+            let result = pr.calculate_into(&mut scratch);
+            assert_eq!(expected_result.iterations, result.iterations);
+            assert_eq!(expected.nodes(), pr.nodes());
+            assert_eq!(allocated, scratch.allocated());
+        }
+
+        let mut pr = crate::generators::ring(10);
+        pr.set_precision(Precision::Single);
+        pr.calculate_with_convergence_into(1e-4, &mut scratch);
+        assert!(scratch.allocated() > allocated);
+    }
+
+    #[cfg(feature = "generators")]
+    #[test]
+    fn test_normalize() {
+        assert_eq!(0.0, Pagerank::<u32>::new().normalize());
+
+        let mut pr = crate::generators::ring(4);
+        pr.add_edge(0, 2);
+        pr.calculate_with_convergence(1e-12);
+        let raw =
+            pr.nodes().iter().map(|(_, s)| *s).collect::<Vec<_>>();
+        let total = pr.normalize();
+        assert!((total - 4.0).abs() < 1e-9);
+        let sum = pr.nodes().iter().map(|(_, s)| s).sum::<f64>();
+        assert!((sum - 1.0).abs() < 1e-12);
+        assert_eq!(raw[0] / total, pr.nodes()[0].1);
+
+        pr.calculate_with_convergence(1e-12);
+        assert!((pr.nodes()[0].1 - raw[0]).abs() < 1e-9);
+    }
+}
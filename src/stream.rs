@@ -0,0 +1,76 @@
+//! Ingestion from async streams of edges
+use crate::Pagerank;
+use core::future::poll_fn;
+use core::hash::{BuildHasher, Hash};
+use core::pin::pin;
+use futures_core::Stream;
+
+impl<T, S> Pagerank<T, S>
+where
+    T: Eq + Hash + Clone,
+    S: BuildHasher,
+{
+    /// Adds every `(source, target)` edge of an async stream as it
+    /// arrives, as `add_edge` does, so a consumer of a message queue or
+    /// an async file reader feeds the graph without collecting the edges
+    /// first. Completes when the stream ends.
+    ///
+    /// The future is not tied to any runtime.
+    pub async fn extend_from_stream<E>(&mut self, edges: E)
+    where
+        E: Stream<Item = (T, T)>,
+    {
+        let mut edges = pin!(edges);
+        if let Some(log) = self.edge_log.as_mut() {
+            log.reserve(edges.size_hint().0);
+        }
+        while let Some((source, target)) =
+            poll_fn(|cx| edges.as_mut().poll_next(cx)).await
+        {
+            self.add_edge(source, target);
+        }
+    }
+}
+
+impl<T, S> Pagerank<T, S>
+where
+    T: Eq + Hash + Clone,
+    S: BuildHasher + Default,
+{
+    /// Builds a graph from an async stream of `(source, target)` edges,
+    /// see `extend_from_stream`
+    pub async fn from_edge_stream<E>(edges: E) -> Self
+    where
+        E: Stream<Item = (T, T)>,
+    {
+        let mut pr = Pagerank::with_hasher(S::default());
+        pr.extend_from_stream(edges).await;
+        pr
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Pagerank;
+    use futures::executor::block_on;
+    use futures::stream;
+
+    #[test]
+    fn test_from_edge_stream() {
+        let edges = vec![(1, 2), (2, 1), (3, 2), (3, 4)];
+        let mut expected =
+            edges.iter().cloned().collect::<Pagerank<u32>>();
+        let mut pr: Pagerank<u32> =
+            block_on(Pagerank::from_edge_stream(stream::iter(edges)));
+        assert_eq!(expected.len_node(), pr.len_node());
+
+        pr.record_edge_order();
+        block_on(pr.extend_from_stream(stream::iter(vec![(4, 1)])));
+        expected.add_edge(4, 1);
+        assert_eq!(Some(vec![(&4, &1, 0)]), pr.edge_sequence());
+
+        expected.calculate();
+        pr.calculate();
+        assert_eq!(expected.nodes(), pr.nodes());
+    }
+}
@@ -0,0 +1,185 @@
+//! Human readable summaries of a graph, for logs and notebooks
+#[cfg(not(feature = "std"))]
+use crate::compat::prelude::*;
+use crate::{Pagerank, RankResult};
+use core::fmt;
+use core::hash::{BuildHasher, Hash};
+
+/// Number of nodes listed by the summaries
+const SUMMARY_NODES: usize = 10;
+
+impl<T, S> Pagerank<T, S>
+where
+    T: Eq + Hash + Clone,
+    S: BuildHasher,
+{
+    /// Returns the outcome of the last full calculation, if any
+    pub fn last_result(&self) -> Option<RankResult> {
+        self.last_result
+    }
+
+    /// Writes the counts, the outcome of the last calculation and the
+    /// top nodes, formatting each key with `key`
+    fn write_summary<F>(
+        &self,
+        f: &mut fmt::Formatter,
+        key: F,
+    ) -> fmt::Result
+    where
+        F: Fn(&T) -> String,
+    {
+        writeln!(
+            f,
+            "Pagerank: {} nodes, {} edges, damping {}",
+            self.nodes.len(),
+            self.edges,
+            self.damping
+        )?;
+        match self.last_result {
+            Some(result) => writeln!(
+                f,
+                "Last run: {} after {} iterations (residual {:.3e}, tolerance {:.3e})",
+                if result.converged {
+                    "converged"
+                } else {
+                    "not converged"
+                },
+                result.iterations,
+                result.residual,
+                result.tolerance
+            )?,
+            None => writeln!(f, "Last run: not calculated yet")?,
+        }
+
+        let top = self.top_k(SUMMARY_NODES);
+        if !top.is_empty() {
+            writeln!(f, "Top nodes:")?;
+        }
+        for (i, (node, score)) in top.into_iter().enumerate() {
+            writeln!(f, "{:>4}. {} {:.6}", i + 1, key(node), score)?;
+        }
+        Ok(())
+    }
+}
+
+impl<T, S> fmt::Display for Pagerank<T, S>
+where
+    T: Eq + Hash + Clone + fmt::Display,
+    S: BuildHasher,
+{
+    /// Summary of the graph: its size, the outcome of the last
+    /// calculation and the ten best ranked nodes
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.write_summary(f, |node| node.to_string())
+    }
+}
+
+impl<T, S> fmt::Debug for Pagerank<T, S>
+where
+    T: Eq + Hash + Clone + fmt::Debug,
+    S: BuildHasher,
+{
+    /// Same summary as `Display`, with the keys debug formatted
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.write_summary(f, |node| format!("{:?}", node))
+    }
+}
+
+#[cfg(feature = "evcxr")]
+impl<T, S> Pagerank<T, S>
+where
+    T: Eq + Hash + Clone + fmt::Display,
+    S: BuildHasher,
+{
+    /// Displays the summary as an HTML table in an evcxr Jupyter
+    /// notebook, which calls this method to render values
+    pub fn evcxr_display(&self) {
+        println!(
+            "EVCXR_BEGIN_CONTENT text/html\n{}\nEVCXR_END_CONTENT",
+            self.to_html()
+        );
+    }
+
+    /// Summary of the graph as an HTML fragment
+    pub fn to_html(&self) -> String {
+        let mut html = format!(
+            "<p><b>Pagerank</b>: {} nodes, {} edges, damping {}",
+            self.nodes.len(),
+            self.edges,
+            self.damping
+        );
+        if let Some(result) = self.last_result {
+            html += &format!(
+                "<br>Last run: {} after {} iterations (residual {:.3e})",
+                if result.converged {
+                    "converged"
+                } else {
+                    "not converged"
+                },
+                result.iterations,
+                result.residual
+            );
+        }
+        html += "</p>\n<table>\n<tr><th>#</th><th>Node</th><th>Score</th></tr>\n";
+        for (i, (node, score)) in
+            self.top_k(SUMMARY_NODES).into_iter().enumerate()
+        {
+            html += &format!(
+                "<tr><td>{}</td><td>{}</td><td>{:.6}</td></tr>\n",
+                i + 1,
+                escape_html(&node.to_string()),
+                score
+            );
+        }
+        html + "</table>"
+    }
+}
+
+/// Escapes the characters with a meaning in HTML
+#[cfg(feature = "evcxr")]
+fn escape_html(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '&' => escaped.push_str("&amp;"),
+            '"' => escaped.push_str("&quot;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Pagerank;
+
+    #[test]
+    fn test_summary() {
+        let mut pr = Pagerank::<&str>::new();
+        pr.add_edge("foo", "bar");
+        pr.add_edge("bar", "foo");
+        pr.add_edge("xxx", "bar");
+        assert!(pr.to_string().contains("not calculated yet"));
+
+        let result = pr.calculate();
+        assert_eq!(Some(result), pr.last_result());
+        let summary = pr.to_string();
+        assert!(summary.starts_with("Pagerank: 3 nodes, 3 edges"));
+        assert!(summary.contains("converged after"));
+        assert!(summary.contains("   1. bar "));
+        assert!(format!("{:?}", pr).contains("   1. \"bar\" "));
+    }
+
+    #[cfg(feature = "evcxr")]
+    #[test]
+    fn test_to_html() {
+        let mut pr = Pagerank::<&str>::new();
+        pr.add_edge("<a>", "b&c");
+        pr.calculate();
+        let html = pr.to_html();
+        assert!(html.contains("<td>b&amp;c</td>"));
+        assert!(html.contains("<td>&lt;a&gt;</td>"));
+    }
+}
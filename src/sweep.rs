@@ -0,0 +1,183 @@
+//! Iterations split into partial sweeps of bounded work
+#[cfg(not(feature = "std"))]
+use crate::compat::prelude::*;
+use crate::compat::Instant;
+use crate::{Pagerank, RankResult};
+use core::hash::{BuildHasher, Hash};
+use core::mem;
+
+/// State of an iteration interrupted mid-sweep by
+/// `Pagerank::calculate_bounded`
+pub(crate) struct Sweep {
+    /// Scores at the start of the iteration
+    previous: Vec<f64>,
+    /// Scores computed so far in this iteration
+    next: Vec<f64>,
+    /// Score each node receives from teleporting
+    teleport: Vec<f64>,
+    /// Share of the dangling score each node receives, if spread
+    dangling: Option<Vec<f64>>,
+    /// Score of the dangling nodes at the start of the iteration
+    dangling_score: f64,
+    /// Iterations completed without converging
+    iterations: usize,
+    /// When the first call of the calculation started
+    started: Instant,
+}
+
+impl<T, S> Pagerank<T, S>
+where
+    T: Eq + Hash + Clone,
+    S: BuildHasher,
+{
+    /// Calculates PageRank cooperatively: each call processes at most
+    /// about `max_edges` in edges and returns, resuming mid-sweep on the
+    /// next call, so the calculation fits within strict latency budgets
+    /// inside a single threaded runtime. At least one node is processed
+    /// per call.
+    ///
+    /// Returns `None` until the calculation converges, or gives up as
+    /// `calculate_with_convergence` does, and then its result, whose
+    /// `elapsed` is the time since the first call. The
+    /// scores of the graph are only updated when an iteration completes,
+    /// so they can be read between calls. Changing the graph or its
+    /// weights between calls restarts the interrupted iteration, other
+    /// settings apply from the next one. Iterations are run in double
+    /// precision.
+    pub fn calculate_bounded(
+        &mut self,
+        convergence: f64,
+        max_edges: usize,
+    ) -> Option<RankResult> {
+        let mut budget = max_edges.max(1);
+        loop {
+            if self.sweep.is_none() {
+                self.sweep = Some(self.start_sweep());
+            }
+            let mut sweep = self.sweep.take().unwrap();
+
+            let len = self.nodes.len();
+            while sweep.next.len() < len && budget > 0 {
+                let id = sweep.next.len();
+                if let Some(score) = self.pinned_score(id) {
+                    sweep.next.push(score);
+                    continue;
+                }
+                let mut incoming = self.incoming(id, &sweep.previous);
+                if let Some(shares) = sweep.dangling.as_ref() {
+                    incoming += sweep.dangling_score * shares[id];
+                }
+                sweep.next.push(
+                    sweep.teleport[id] + self.damping * incoming,
+                );
+                budget = budget
+                    .saturating_sub(self.len_in_edges(id).max(1));
+            }
+
+            if sweep.next.len() < len {
+                self.sweep = Some(sweep);
+                return None;
+            }
+
+            let residual = self.residual(
+                sweep
+                    .previous
+                    .iter()
+                    .cloned()
+                    .zip(sweep.next.iter().cloned()),
+            );
+            for (n, score) in
+                self.nodes.iter_mut().zip(sweep.next.iter())
+            {
+                n.score = *score;
+            }
+            if self.finished(
+                residual,
+                convergence,
+                sweep.iterations + 1,
+            ) {
+                return Some(self.rank_result(
+                    sweep.iterations + 1,
+                    residual,
+                    convergence,
+                    sweep.started,
+                ));
+            }
+
+            // The next iteration reuses the vectors of this one
+            sweep.iterations += 1;
+            mem::swap(&mut sweep.previous, &mut sweep.next);
+            sweep.next.clear();
+            self.fill_sweep(&mut sweep);
+            self.sweep = Some(sweep);
+            if budget == 0 {
+                return None;
+            }
+        }
+    }
+
+    /// Starts the first iteration of a bounded calculation
+    fn start_sweep(&mut self) -> Sweep {
+        let mut sweep = Sweep {
+            previous: self.nodes.iter().map(|n| n.score).collect(),
+            next: Vec::with_capacity(self.nodes.len()),
+            teleport: Vec::new(),
+            dangling: None,
+            dangling_score: 0f64,
+            iterations: 0,
+            started: Instant::now(),
+        };
+        self.fill_sweep(&mut sweep);
+        sweep
+    }
+
+    /// Computes what an iteration needs besides the previous scores
+    fn fill_sweep(&mut self, sweep: &mut Sweep) {
+        self.finalize_weights();
+        sweep.teleport = self.teleport_scores();
+        sweep.dangling = self.dangling_shares(&sweep.teleport);
+        sweep.dangling_score = match sweep.dangling {
+            Some(_) => self.dangling_score(&sweep.previous),
+            None => 0f64,
+        };
+    }
+}
+
+#[cfg(all(test, feature = "generators"))]
+mod tests {
+    use crate::{generators, DanglingPolicy};
+
+    #[test]
+    fn test_calculate_bounded() {
+        let mut expected = generators::watts_strogatz(200, 4, 0.3, 9);
+        expected.add_edge(0, 500);
+        expected.set_dangling_policy(DanglingPolicy::Uniform);
+        let expected_result =
+            expected.calculate_with_convergence(1e-9);
+
+        let mut pr = generators::watts_strogatz(200, 4, 0.3, 9);
+        pr.add_edge(0, 500);
+        pr.set_dangling_policy(DanglingPolicy::Uniform);
+        let mut calls = 0;
+        let result = loop {
+            calls += 1;
+            if let Some(result) = pr.calculate_bounded(1e-9, 50) {
+                break result;
+            }
+        };
+        assert!(result.converged);
+        assert_eq!(expected_result.iterations, result.iterations);
+        assert!(calls > result.iterations * 10);
+        for node in 0..200 {
+            let diff = pr.get_score(node).unwrap()
+                - expected.get_score(node).unwrap();
+            assert!(diff.abs() < 1e-12);
+        }
+
+        // Changing the graph restarts the interrupted iteration
+        assert_eq!(None, pr.calculate_bounded(1e-12, 50));
+        pr.add_edge(1, 2);
+        assert!(pr.sweep.is_none());
+        while pr.calculate_bounded(1e-9, 10_000).is_none() {}
+    }
+}
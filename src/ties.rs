@@ -0,0 +1,124 @@
+//! Rankings with near-identical scores grouped as ties
+#[cfg(not(feature = "std"))]
+use crate::compat::prelude::*;
+use crate::Pagerank;
+#[cfg(feature = "std")]
+use core::fmt::Display;
+use core::hash::{BuildHasher, Hash};
+#[cfg(feature = "std")]
+use std::io::{self, Write};
+
+/// Nodes whose scores are equal within the epsilon given to
+/// `Pagerank::tie_groups`, sharing a dense rank
+#[derive(Debug, Clone, PartialEq)]
+pub struct TieGroup<'a, T> {
+    /// Dense rank of the group: 1 for the highest scores, and one more
+    /// for each following group, without gaps
+    pub rank: usize,
+    /// Nodes of the group and their score, highest first
+    pub nodes: Vec<(&'a T, f64)>,
+}
+
+impl<T, S> Pagerank<T, S>
+where
+    T: Eq + Hash + Clone,
+    S: BuildHasher,
+{
+    /// Returns the ranking with the nodes whose scores are within
+    /// `epsilon` of each other grouped as ties, each group with its
+    /// dense rank, so consumers don't need to derive the grouping from
+    /// floating point scores themselves.
+    ///
+    /// Groups are anchored at their highest score: a node joins the
+    /// group when its score is at most `epsilon` below the first score
+    /// of the group, so long runs of close scores don't chain into a
+    /// single group. Scores are the ones `nodes` returns, with the score
+    /// transform applied, and NaN scores are tied with each other at the
+    /// end. An `epsilon` of 0 groups exactly equal scores only.
+    pub fn tie_groups(&self, epsilon: f64) -> Vec<TieGroup<'_, T>> {
+        let mut groups: Vec<TieGroup<T>> = Vec::new();
+        for (node, score) in self.iter_ranked() {
+            let tied = groups.last().is_some_and(|group| {
+                let head = group.nodes[0].1;
+                head - score <= epsilon
+                    || (head.is_nan() && score.is_nan())
+            });
+            if tied {
+                groups.last_mut().unwrap().nodes.push((node, score));
+            } else {
+                groups.push(TieGroup {
+                    rank: groups.len() + 1,
+                    nodes: vec![(node, score)],
+                });
+            }
+        }
+        groups
+    }
+
+    /// Writes the ranking grouped as in `tie_groups`, one
+    /// `rank<TAB>node<TAB>score` line per node, highest first. Nodes of
+    /// the same group share their rank.
+    #[cfg(feature = "std")]
+    pub fn write_dense_ranks<W: Write>(
+        &self,
+        mut writer: W,
+        epsilon: f64,
+    ) -> io::Result<()>
+    where
+        T: Display,
+    {
+        for group in self.tie_groups(epsilon) {
+            for (node, score) in group.nodes {
+                writeln!(
+                    writer,
+                    "{}\t{}\t{:?}",
+                    group.rank, node, score
+                )?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Pagerank;
+
+    #[test]
+    fn test_tie_groups() {
+        let mut pr = Pagerank::<&str>::new();
+        pr.add_edge("a", "hub");
+        pr.add_edge("b", "hub");
+        pr.add_edge_weighted("hub", "x", 1.0 + 1e-9).unwrap();
+        pr.add_edge("hub", "y");
+        pr.calculate_with_convergence(1e-9);
+
+        let groups = pr.tie_groups(1e-6);
+        let ranks = groups
+            .iter()
+            .map(|g| {
+                (g.rank, g.nodes.iter().map(|(n, _)| **n).collect())
+            })
+            .collect::<Vec<(usize, Vec<&str>)>>();
+        assert_eq!(
+            vec![
+                (1, vec!["hub"]),
+                (2, vec!["x", "y"]),
+                (3, vec!["a", "b"])
+            ],
+            ranks
+        );
+        assert_eq!(4, pr.tie_groups(0.0).len());
+        assert_eq!(1, pr.tie_groups(f64::INFINITY).len());
+
+        let mut tsv = Vec::new();
+        pr.write_dense_ranks(&mut tsv, 1e-6).unwrap();
+        let tsv = String::from_utf8(tsv).unwrap();
+        let ranks = tsv
+            .lines()
+            .map(|line| line.split('\t').next().unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(vec!["1", "2", "2", "3", "3"], ranks);
+        assert!(tsv.starts_with("1\thub\t"));
+    }
+}
@@ -0,0 +1,363 @@
+//! Edge insertion order and replay
+#[cfg(not(feature = "std"))]
+use crate::compat::prelude::*;
+use crate::compat::HashMap;
+use crate::{Pagerank, ScoreSet};
+use core::hash::{BuildHasher, Hash};
+
+impl<T, S> Pagerank<T, S>
+where
+    T: Eq + Hash + Clone,
+    S: BuildHasher,
+{
+    /// Starts recording the order in which edges are added, so the graph
+    /// can later be replayed up to a given point with `replay`. Only
+    /// edges added afterwards are recorded.
+    pub fn record_edge_order(&mut self) {
+        if self.edge_log.is_none() {
+            self.edge_log = Some(Vec::new());
+        }
+    }
+
+    /// Adds an edge with an explicit sequence number, such as a
+    /// timestamp, used instead of the insertion position by `replay`.
+    /// Recording is enabled if it wasn't already.
+    pub fn add_edge_at(
+        &mut self,
+        source: T,
+        target: T,
+        sequence: u64,
+    ) {
+        self.record_edge_order();
+        let _ =
+            self.insert_edge(source, target, 1f64, Some(sequence));
+    }
+
+    /// Returns the sequence number of every recorded edge, in insertion
+    /// order, or `None` if edges are not being recorded
+    pub fn edge_sequence(&self) -> Option<Vec<(&T, &T, u64)>> {
+        self.edge_log.as_ref().map(|log| {
+            log.iter()
+                .map(|(source, target, sequence, _)| {
+                    (
+                        &self.nodes[*source].node,
+                        &self.nodes[*target].node,
+                        *sequence,
+                    )
+                })
+                .collect()
+        })
+    }
+
+    /// Builds a new graph with the recorded edges whose sequence number
+    /// is up to `until` (inclusive), added in their original order. The
+    /// new graph has the same settings, see `calculate_windowed`.
+    ///
+    /// Returns `None` if edges are not being recorded.
+    pub fn replay(&self, until: u64) -> Option<Pagerank<T>> {
+        self.replay_range(0, until)
+    }
+
+    /// Calculates the PageRank over sliding windows of the recorded edges.
+    ///
+    /// Windows cover `window` consecutive sequence numbers, the first one
+    /// starting at the lowest recorded sequence, and each following one
+    /// `step` later, until the highest recorded sequence is covered. A
+    /// single graph is kept: the edges leaving the window are removed,
+    /// the ones entering it added, and the scores updated with
+    /// `update_after_changes`, so consecutive, mostly overlapping windows
+    /// only cost the changed edges. The scores of a window hold the
+    /// nodes with edges in it.
+    ///
+    /// Windows are calculated with the settings of this graph: damping
+    /// factor, convergence and iteration limit, precision, convergence
+    /// norm, dangling policy, in edges cap, edge weights, score
+    /// transform, personalization and pinned scores. Seeds and pinned
+    /// nodes are part of every window. Node types are not kept. When in
+    /// edges are capped, sampled edges can't be removed reliably, so each
+    /// window is built again, warm started from the previous one.
+    ///
+    /// Returns the first sequence number of each window with its scores.
+    pub fn calculate_windowed(
+        &self,
+        window: u64,
+        step: u64,
+    ) -> Vec<(u64, ScoreSet<T>)> {
+        let log = match self.edge_log.as_ref() {
+            Some(log) if !log.is_empty() && window > 0 => log,
+            _ => return Vec::new(),
+        };
+
+        // Edges by sequence number, ties in insertion order, so each
+        // window holds `order[leaving..entering]`
+        let mut order = log.iter().collect::<Vec<_>>();
+        order.sort_by_key(|(_, _, sequence, _)| *sequence);
+        let (mut leaving, mut entering) = (0, 0);
+        let first = order[0].2;
+        let last = order[order.len() - 1].2;
+
+        let mut pr = self.empty_copy();
+        let mut rankings: Vec<(u64, ScoreSet<T>)> = Vec::new();
+        let mut start = first;
+        loop {
+            let end = start.saturating_add(window - 1);
+            if self.in_edges_cap.is_some() {
+                let mut next = match self.replay_range(start, end) {
+                    Some(pr) => pr,
+                    None => break,
+                };
+                for node in next.nodes.iter_mut() {
+                    if let Some(id) =
+                        pr.node_positions.get(&node.node)
+                    {
+                        node.score = pr.nodes[*id].score;
+                    }
+                }
+                next.calculate();
+                pr = next;
+            } else {
+                while leaving < entering && order[leaving].2 < start {
+                    let (source, target, _, _) = order[leaving];
+                    pr.remove_edge_copy(
+                        &self.nodes[*source].node,
+                        &self.nodes[*target].node,
+                        true,
+                    );
+                    leaving += 1;
+                }
+                while entering < order.len()
+                    && order[entering].2 <= end
+                {
+                    let (source, target, sequence, weight) =
+                        order[entering];
+                    entering += 1;
+                    if *sequence < start {
+                        leaving = entering;
+                        continue;
+                    }
+                    let _ = pr.insert_edge(
+                        self.nodes[*source].node.clone(),
+                        self.nodes[*target].node.clone(),
+                        *weight,
+                        None,
+                    );
+                }
+
+                // Nodes left without edges are dropped, as they count
+                // in the teleport mass and receive dangling scores
+                let isolated = (0..pr.nodes.len())
+                    .map(|id| pr.is_isolated(id))
+                    .collect::<Vec<bool>>();
+                if isolated.contains(&true) {
+                    pr.remove_ids(&isolated);
+                }
+
+                if rankings.is_empty() {
+                    pr.calculate();
+                } else {
+                    pr.update_after_changes();
+                }
+            }
+
+            rankings.push((start, pr.window_score_set()));
+
+            if end >= last {
+                break;
+            }
+            start = start.saturating_add(step.max(1));
+        }
+
+        rankings
+    }
+
+    /// Builds a new graph with the recorded edges whose sequence number
+    /// is within `from..=until`
+    pub(crate) fn replay_range(
+        &self,
+        from: u64,
+        until: u64,
+    ) -> Option<Pagerank<T>> {
+        let log = self.edge_log.as_ref()?;
+
+        let mut pr = self.empty_copy();
+        pr.record_edge_order();
+
+        for (source, target, sequence, weight) in log.iter() {
+            if *sequence >= from && *sequence <= until {
+                let _ = pr.insert_edge(
+                    self.nodes[*source].node.clone(),
+                    self.nodes[*target].node.clone(),
+                    *weight,
+                    Some(*sequence),
+                );
+            }
+        }
+
+        Some(pr)
+    }
+
+    /// Builds a graph without edges with the settings listed in
+    /// `calculate_windowed`
+    fn empty_copy(&self) -> Pagerank<T> {
+        let mut pr = Pagerank::new();
+        pr.damping = self.damping;
+        pr.convergence = self.convergence;
+        pr.max_iterations = self.max_iterations;
+        pr.precision = self.precision;
+        pr.convergence_norm = self.convergence_norm;
+        pr.dangling_policy = self.dangling_policy;
+        pr.in_edges_cap = self.in_edges_cap;
+        pr.weight_fn = self.weight_fn.clone();
+        pr.affinity_fn = self.affinity_fn.clone();
+        pr.group_fn = self.group_fn.clone();
+        pr.score_transform = self.score_transform.clone();
+        pr.set_personalization(self.personalization.iter().map(
+            |(id, weight)| (self.nodes[*id].node.clone(), *weight),
+        ));
+        for (id, score) in self.pinned.iter() {
+            pr.pin_score(self.nodes[*id].node.clone(), *score);
+        }
+        pr
+    }
+
+    /// If a node has no edges, and is neither pinned nor a seed
+    fn is_isolated(&self, id: usize) -> bool {
+        let n = &self.nodes[id];
+        n.in_degree == 0
+            && n.out_edges == 0
+            && self.pinned_score(id).is_none()
+            && self
+                .personalization
+                .binary_search_by_key(&id, |(id, _)| *id)
+                .is_err()
+    }
+
+    /// Returns the scores of the nodes which are not isolated
+    fn window_score_set(&self) -> ScoreSet<T> {
+        let mut positions = HashMap::new();
+        let mut scores = Vec::new();
+        for (id, n) in self.nodes.iter().enumerate() {
+            if !self.is_isolated(id) {
+                positions.insert(n.node.clone(), scores.len());
+                scores.push(self.transformed(n.score));
+            }
+        }
+        ScoreSet { positions, scores }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{DanglingPolicy, Pagerank, PagerankBuilder};
+
+    #[test]
+    fn test_replay() {
+        let mut pr = Pagerank::<&str>::new();
+        pr.add_edge("before", "recording");
+        assert!(pr.replay(10).is_none());
+
+        pr.record_edge_order();
+        pr.add_edge("foo", "bar");
+        pr.add_edge("bar", "foo");
+        pr.add_edge("xxx", "bar");
+
+        let replayed = pr.replay(1).expect("recording");
+        assert_eq!(2, replayed.len());
+        assert_eq!(2, replayed.len_node());
+        assert_eq!(None, replayed.get_score("xxx"));
+        assert_eq!(3, pr.replay(2).unwrap().len_node());
+    }
+
+    #[test]
+    fn test_add_edge_at() {
+        let mut pr = Pagerank::<&str>::new();
+        pr.add_edge_at("foo", "bar", 100);
+        pr.add_edge_at("bar", "foo", 50);
+        pr.add_edge("xxx", "bar");
+
+        assert_eq!(
+            Some(vec![
+                (&"foo", &"bar", 100),
+                (&"bar", &"foo", 50),
+                (&"xxx", &"bar", 51)
+            ]),
+            pr.edge_sequence()
+        );
+
+        let replayed = pr.replay(75).expect("recording");
+        assert_eq!(Some(1), replayed.get_in_edges("foo"));
+        assert_eq!(Some(0), replayed.get_out_edges("foo"));
+        assert_eq!(2, replayed.len_node());
+    }
+
+    #[test]
+    fn test_calculate_windowed() {
+        let mut pr = Pagerank::<&str>::new();
+        pr.add_edge_at("foo", "bar", 10);
+        pr.add_edge_at("xxx", "bar", 11);
+        pr.add_edge_at("bar", "foo", 20);
+        pr.add_edge_at("xxx", "foo", 21);
+        pr.add_edge_at("yyy", "foo", 22);
+
+        let windows = pr.calculate_windowed(10, 5);
+        assert_eq!(
+            vec![10, 15],
+            windows
+                .iter()
+                .map(|(start, _)| *start)
+                .collect::<Vec<u64>>()
+        );
+        assert_eq!("bar", *windows[0].1.nodes()[0].0);
+        assert_eq!(None, windows[0].1.get(&"yyy"));
+        assert_eq!("foo", *windows[1].1.nodes()[0].0);
+        assert_eq!(4, windows[1].1.len());
+    }
+
+    #[test]
+    fn test_calculate_windowed_incremental() {
+        for policy in [
+            DanglingPolicy::Ignore,
+            DanglingPolicy::Uniform,
+            DanglingPolicy::Teleport,
+        ] {
+            let mut pr = PagerankBuilder::<u32>::new()
+                .convergence(1e-12)
+                .dangling_policy(policy)
+                .build()
+                .unwrap();
+            let mut rng = 7u64;
+            let mut next = |range: u64| {
+                rng = rng
+                    .wrapping_mul(6364136223846793005)
+                    .wrapping_add(1442695040888963407);
+                (rng >> 33) % range
+            };
+            for _ in 0..300 {
+                let (source, target) = (next(40), next(40));
+                let sequence = next(100);
+                pr.add_edge_at(
+                    source as u32,
+                    target as u32,
+                    sequence,
+                );
+            }
+            pr.add_edge_weighted(3, 4, 2.5).unwrap();
+            pr.pin_score(0, 2.0);
+            pr.set_personalization(vec![(1, 3.0), (5, 1.0)]);
+
+            let windows = pr.calculate_windowed(20, 7);
+            assert_eq!(13, windows.len());
+            for (start, scores) in windows {
+                let mut fresh =
+                    pr.replay_range(start, start + 19).unwrap();
+                fresh.calculate();
+                assert_eq!(fresh.len(), scores.len());
+                assert_eq!(Some(2.0), scores.get(&0));
+                for (node, score) in fresh.nodes() {
+                    let windowed = scores.get(node).unwrap();
+                    assert!((windowed - score).abs() < 1e-6);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,108 @@
+//! Transforming scores when they are queried
+use crate::Pagerank;
+use alloc::sync::Arc;
+use core::hash::{BuildHasher, Hash};
+
+/// Closure applied to every score handed out by queries and exports
+pub(crate) type ScoreTransform =
+    Arc<dyn Fn(f64) -> f64 + Send + Sync>;
+
+impl<T, S> Pagerank<T, S>
+where
+    T: Eq + Hash + Clone,
+    S: BuildHasher,
+{
+    /// Sets a transform applied to the raw scores wherever they are
+    /// handed out, for instance `f64::ln_1p` to compress their range or
+    /// a temperature scaling, so every consumer sees the same values
+    /// without transforming them itself.
+    ///
+    /// It applies to `get_score`, `get_scores`, `nodes`, `iter_nodes`,
+    /// `iter_scores`, `iter_ranked`, `top_k` and its variants,
+    /// `frontier`, `score_set`, `group_scores`, `score_quantiles`,
+    /// `emit_scores`, `write_scores_into` and the npy, segment and DOT
+    /// exports. Calculations, snapshots, golden files and `view` keep
+    /// using the raw scores. The transform must be increasing, so it
+    /// doesn't change the ranking.
+    pub fn set_score_transform<F>(&mut self, transform: F)
+    where
+        F: Fn(f64) -> f64 + Send + Sync + 'static,
+    {
+        self.score_transform = Some(Arc::new(transform));
+    }
+
+    /// Goes back to handing out raw scores
+    pub fn clear_score_transform(&mut self) {
+        self.score_transform = None;
+    }
+
+    /// Returns the score of a node as calculated, before the score
+    /// transform
+    pub fn raw_score(&self, node: &T) -> Option<f64> {
+        self.node_positions
+            .get(node)
+            .map(|id| self.nodes[*id].score)
+    }
+
+    /// Applies the score transform, if any, to a raw score
+    pub(crate) fn transformed(&self, score: f64) -> f64 {
+        transform(self.score_transform.as_ref(), score)
+    }
+}
+
+/// Applies `transform`, if any, to a raw score
+pub(crate) fn transform(
+    transform: Option<&ScoreTransform>,
+    score: f64,
+) -> f64 {
+    match transform {
+        Some(transform) => transform(score),
+        None => score,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Pagerank;
+
+    #[test]
+    fn test_score_transform() {
+        let mut pr = Pagerank::<&str>::new();
+        pr.add_edge("a", "b");
+        pr.add_edge("c", "b");
+        pr.add_edge("b", "a");
+        pr.calculate();
+        let raw = pr
+            .nodes()
+            .iter()
+            .map(|(n, s)| (**n, *s))
+            .collect::<Vec<_>>();
+
+        pr.set_score_transform(|score| 10.0 * score);
+        let scaled = pr.nodes();
+        for ((node, raw), (scaled_node, scaled)) in
+            raw.iter().zip(scaled.iter())
+        {
+            assert_eq!(node, *scaled_node);
+            assert_eq!(10.0 * raw, *scaled);
+            assert_eq!(Some(*scaled), pr.get_score(node));
+            assert_eq!(Some(*raw), pr.raw_score(node));
+        }
+        assert_eq!(scaled[..2], pr.top_k(2)[..]);
+        assert_eq!(scaled, pr.iter_ranked().collect::<Vec<_>>());
+        assert_eq!(Some(scaled[0].1), pr.score_set().get(&"b"));
+        assert_eq!(vec![Some(scaled[0].1)], pr.get_scores(&["b"]));
+        let mut top = Vec::new();
+        pr.top_k_into(1, &mut top);
+        assert_eq!(scaled[..1], top[..]);
+
+        // Calculations keep using the raw scores
+        pr.calculate();
+        let b = pr.raw_score(&"b").unwrap();
+        assert!(b < 2.0);
+        assert_eq!(Some(10.0 * b), pr.get_score("b"));
+
+        pr.clear_score_transform();
+        assert_eq!(Some(b), pr.get_score("b"));
+    }
+}
@@ -0,0 +1,115 @@
+//! Ingestion ignoring edges which are already in the graph
+#[cfg(not(feature = "std"))]
+use crate::compat::prelude::*;
+use crate::compat::HashSet;
+use crate::Pagerank;
+use core::hash::{BuildHasher, Hash};
+
+impl<T, S> Pagerank<T, S>
+where
+    T: Eq + Hash + Clone,
+    S: BuildHasher,
+{
+    /// Makes adding an edge idempotent: while enabled, adding an edge
+    /// from `source` to `target` when there is already one does nothing,
+    /// so ingesting the same edge file, or chunk of it, again doesn't
+    /// double the weight of its edges. Retryable batch jobs which may
+    /// deliver a chunk twice can then add every edge they get.
+    ///
+    /// Edges are keyed by their pair of node ids, 16 bytes per edge.
+    /// When enabled, the edges already stored are keyed too; duplicates
+    /// added before are kept. With an in edges cap, edges which were not
+    /// sampled are not known, so adding them again is not ignored.
+    /// Disabling it drops the keys.
+    pub fn set_unique_edges(&mut self, enabled: bool) {
+        if !enabled {
+            self.unique_edges = None;
+            return;
+        }
+        if self.unique_edges.is_none() {
+            self.unique_edges = Some(self.stored_edges());
+        }
+    }
+
+    /// If adding an edge which is already in the graph is ignored
+    pub fn unique_edges(&self) -> bool {
+        self.unique_edges.is_some()
+    }
+
+    /// If adding an edge between the given nodes would be ignored
+    pub(crate) fn is_duplicate_edge(
+        &self,
+        source: &T,
+        target: &T,
+    ) -> bool {
+        let unique = match self.unique_edges.as_ref() {
+            Some(unique) => unique,
+            None => return false,
+        };
+        match (self.node_id(source), self.node_id(target)) {
+            (Some(source), Some(target)) => {
+                unique.contains(&(source, target))
+            }
+            _ => false,
+        }
+    }
+
+    /// Keys of the stored edges, as pairs of source and target ids
+    pub(crate) fn stored_edges(&self) -> HashSet<(usize, usize)> {
+        (0..self.nodes.len())
+            .flat_map(|target| {
+                self.in_edges(target)
+                    .iter()
+                    .map(|source| (*source, target))
+                    .collect::<Vec<_>>()
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{ImportOptions, Pagerank};
+
+    #[test]
+    fn test_unique_edges() {
+        let chunk = "a\tb\nb\tc\nc\ta\n";
+        let mut pr = Pagerank::<String>::new();
+        pr.add_edge("a".to_owned(), "b".to_owned());
+        pr.add_edge("a".to_owned(), "b".to_owned());
+        pr.set_unique_edges(true);
+        assert!(pr.unique_edges());
+
+        let options = ImportOptions::default();
+        let report =
+            pr.import_tsv(chunk.as_bytes(), &options).unwrap();
+        assert_eq!(2, report.edges);
+        assert_eq!(1, report.duplicates);
+        let report =
+            pr.import_tsv(chunk.as_bytes(), &options).unwrap();
+        assert_eq!(0, report.edges);
+        assert_eq!(3, report.duplicates);
+        assert_eq!(4, pr.len_node());
+
+        // Removed edges can be added again, also after a rollback
+        assert!(pr.remove_edge(&"b".to_owned(), &"c".to_owned()));
+        pr.begin_batch();
+        pr.add_edge("b".to_owned(), "c".to_owned());
+        pr.add_edge("b".to_owned(), "c".to_owned());
+        assert_eq!(4, pr.len_node());
+        pr.rollback_batch();
+        assert_eq!(3, pr.len_node());
+        pr.add_edge("b".to_owned(), "c".to_owned());
+        assert_eq!(4, pr.len_node());
+
+        // Ids are kept right when nodes are removed
+        assert!(pr.remove_node(&"a".to_owned()));
+        pr.add_edge("b".to_owned(), "c".to_owned());
+        pr.add_edge("c".to_owned(), "b".to_owned());
+        assert_eq!(2, pr.len_node());
+
+        pr.set_unique_edges(false);
+        pr.add_edge("b".to_owned(), "c".to_owned());
+        assert_eq!(3, pr.len_node());
+    }
+}
@@ -0,0 +1,126 @@
+//! Scores of nodes which are not in the graph
+#[cfg(not(feature = "std"))]
+use crate::compat::prelude::*;
+use crate::Pagerank;
+use core::hash::{BuildHasher, Hash};
+
+/// What `get_score` returns for nodes which are not in the graph
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize)
+)]
+pub enum UnknownScore {
+    /// `None`, the default
+    #[default]
+    Missing,
+    /// The baseline score, see `Pagerank::baseline_score`
+    Baseline,
+}
+
+impl<T, S> Pagerank<T, S>
+where
+    T: Eq + Hash + Clone,
+    S: BuildHasher,
+{
+    /// Sets what `get_score` and `get_scores` return for nodes which are
+    /// not in the graph, so serving layers which must always answer with
+    /// a number don't need their own fallback.
+    pub fn set_unknown_score(&mut self, unknown: UnknownScore) {
+        self.unknown_score = unknown;
+    }
+
+    /// Returns what is returned for nodes which are not in the graph
+    pub fn unknown_score(&self) -> UnknownScore {
+        self.unknown_score
+    }
+
+    /// Score a node without in edges gets from teleporting alone, the
+    /// one an unseen node would have if it was added to the graph:
+    /// `1 - d` with uniform teleporting.
+    ///
+    /// Unseen nodes are not personalized, so it is 0 when a
+    /// personalization vector is set. With teleport shares by node type
+    /// they are untagged, and get the share of a type 0 node.
+    pub fn baseline_score(&self) -> f64 {
+        let teleport = 1f64 - self.damping;
+        if !self.personalization.is_empty() {
+            return 0f64;
+        }
+        if self.teleport_shares.is_empty() {
+            return teleport;
+        }
+
+        let untagged =
+            (0..self.nodes.len()).filter(|id| self.type_of(*id) == 0);
+        let total = teleport * (self.nodes.len() + 1) as f64;
+        self.teleport_shares
+            .iter()
+            .find(|(node_type, _)| *node_type == 0)
+            .map_or(0f64, |(_, share)| {
+                total * share / (untagged.count() + 1) as f64
+            })
+    }
+
+    /// Returns the score of each of `nodes`, in the same order, following
+    /// the same rules as `get_score`
+    pub fn get_scores(&self, nodes: &[T]) -> Vec<Option<f64>> {
+        nodes
+            .iter()
+            .map(|node| match self.node_positions.get(node) {
+                Some(id) => {
+                    Some(self.transformed(self.nodes[*id].score))
+                }
+                None => self.unknown_node_score(),
+            })
+            .collect()
+    }
+
+    /// Score returned for a node which is not in the graph
+    pub(crate) fn unknown_node_score(&self) -> Option<f64> {
+        match self.unknown_score {
+            UnknownScore::Missing => None,
+            UnknownScore::Baseline => {
+                Some(self.transformed(self.baseline_score()))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Pagerank, UnknownScore};
+
+    #[test]
+    fn test_unknown_score() {
+        let mut pr = Pagerank::<&str>::new();
+        pr.add_edge("a", "b");
+        pr.add_edge("b", "a");
+        pr.calculate();
+        assert_eq!(None, pr.get_score("x"));
+
+        pr.set_unknown_score(UnknownScore::Baseline);
+        assert!((pr.get_score("x").unwrap() - 0.15).abs() < 1e-12);
+        let scores = pr.get_scores(&["a", "x"]);
+        assert_eq!(pr.get_score("a"), scores[0]);
+        assert_eq!(pr.get_score("x"), scores[1]);
+
+        // The baseline is the score the node gets once added
+        pr.add_edge("x", "a");
+        pr.calculate();
+        assert!(
+            (pr.get_score("x").unwrap() - pr.baseline_score()).abs()
+                < 1e-12
+        );
+
+        pr.set_teleport_shares(vec![(0, 0.5), (1, 0.5)]);
+        pr.set_node_type("b", 1);
+        assert!(
+            (pr.baseline_score() - 0.15 * 4.0 * 0.5 / 3.0).abs()
+                < 1e-12
+        );
+
+        pr.set_personalization(vec![("a", 1.0)]);
+        assert_eq!(Some(0.0), pr.get_score("y"));
+    }
+}
@@ -0,0 +1,142 @@
+//! Read-only views of a graph
+use crate::compat::HashMap;
+use crate::compat::RandomState;
+use crate::frozen::Frozen;
+use crate::graph::Node;
+use crate::Pagerank;
+use alloc::borrow::Cow;
+use core::hash::{BuildHasher, Hash};
+
+/// Read-only view of a graph, borrowing its internal structures.
+///
+/// Nodes are addressed by their id, from 0 to `len() - 1`, so analytics
+/// can use plain vectors indexed by id. A view is `Copy`, and `Send` and
+/// `Sync` whenever `T` is, so it can be handed to many scoped threads
+/// working over the same graph.
+pub struct GraphView<'a, T, S = RandomState>
+where
+    T: Eq + Hash + Clone,
+{
+    nodes: &'a [Node<T>],
+    positions: &'a HashMap<T, usize, S>,
+    frozen: Option<&'a Frozen>,
+}
+
+impl<'a, T, S> Clone for GraphView<'a, T, S>
+where
+    T: Eq + Hash + Clone,
+{
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<'a, T, S> Copy for GraphView<'a, T, S> where T: Eq + Hash + Clone {}
+
+impl<'a, T, S> GraphView<'a, T, S>
+where
+    T: Eq + Hash + Clone,
+    S: BuildHasher,
+{
+    /// Returns the number of nodes
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    /// If there are no nodes
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    /// Returns the id of a node
+    pub fn id(&self, node: &T) -> Option<usize> {
+        self.positions.get(node).cloned()
+    }
+
+    /// Returns the node with the given id. Panics if it is out of range.
+    pub fn node(&self, id: usize) -> &'a T {
+        &self.nodes[id].node
+    }
+
+    /// Returns the current score of a node
+    pub fn score(&self, id: usize) -> f64 {
+        self.nodes[id].score
+    }
+
+    /// Returns the number of in edges of a node
+    pub fn in_degree(&self, id: usize) -> usize {
+        self.nodes[id].in_degree
+    }
+
+    /// Returns the number of out edges of a node
+    pub fn out_degree(&self, id: usize) -> usize {
+        self.nodes[id].out_edges
+    }
+
+    /// Returns the ids of the nodes linking to a node, once per stored
+    /// edge. With an in edges cap this is a sample of `in_degree` edges.
+    ///
+    /// They are borrowed unless the graph was frozen with
+    /// `freeze_tiered` and the node is in its cold tier, whose in edges
+    /// are decoded.
+    pub fn in_neighbors(&self, id: usize) -> Cow<'a, [usize]> {
+        match self.frozen {
+            Some(frozen) => frozen.in_edges(id),
+            None => Cow::Borrowed(&self.nodes[id].in_edges),
+        }
+    }
+}
+
+impl<T, S> Pagerank<T, S>
+where
+    T: Eq + Hash + Clone,
+    S: BuildHasher,
+{
+    /// Returns a read-only view of the graph, for custom analytics
+    pub fn view(&self) -> GraphView<'_, T, S> {
+        GraphView {
+            nodes: &self.nodes,
+            positions: &self.node_positions,
+            frozen: self.frozen.as_ref(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Pagerank;
+    use std::thread;
+
+    #[test]
+    fn test_view_in_threads() {
+        let mut pr = Pagerank::<&str>::new();
+        pr.add_edge("foo", "bar");
+        pr.add_edge("bar", "foo");
+        pr.add_edge("xxx", "bar");
+        pr.calculate();
+
+        let view = pr.view();
+        let bar = view.id(&"bar").unwrap();
+        assert_eq!("bar", *view.node(bar));
+        assert_eq!(2, view.in_neighbors(bar).len());
+
+        let totals = thread::scope(|s| {
+            let workers = (0..2)
+                .map(|half| {
+                    s.spawn(move || {
+                        (0..view.len())
+                            .filter(|id| id % 2 == half)
+                            .map(|id| view.in_degree(id))
+                            .sum::<usize>()
+                    })
+                })
+                .collect::<Vec<_>>();
+            workers
+                .into_iter()
+                .map(|w| w.join().unwrap())
+                .sum::<usize>()
+        });
+        assert_eq!(3, totals);
+        assert_eq!(pr.get_score("bar"), Some(view.score(bar)));
+    }
+}
@@ -0,0 +1,88 @@
+//! Starting the calculation from known scores
+use crate::Pagerank;
+use core::hash::{BuildHasher, Hash};
+
+impl<T, S> Pagerank<T, S>
+where
+    T: Eq + Hash + Clone,
+    S: BuildHasher,
+{
+    /// Sets the scores the next calculation starts from, for instance
+    /// the scores of the previous nightly run, loaded from wherever they
+    /// were stored. On a slowly changing graph the calculation then
+    /// starts near the solution and takes far fewer iterations.
+    ///
+    /// Any collection of pairs works, such as a `HashMap<T, f64>`. Nodes
+    /// which are not in the graph and scores which are not finite,
+    /// non negative numbers are ignored; nodes left out keep their
+    /// current score, which is `1 - d` for new nodes and the last
+    /// calculated one otherwise. Returns the number of scores set.
+    ///
+    /// The scores only change where the calculation starts, not the
+    /// result.
+    pub fn set_initial_scores<I>(&mut self, scores: I) -> usize
+    where
+        I: IntoIterator<Item = (T, f64)>,
+    {
+        let mut set = 0;
+        for (node, score) in scores {
+            if !(score >= 0f64 && score.is_finite()) {
+                continue;
+            }
+            if let Some(id) = self.node_positions.get(&node) {
+                self.nodes[*id].score = score;
+                set += 1;
+            }
+        }
+        set
+    }
+
+    /// Sets every score back to `1 - d`, so the next calculation starts
+    /// from scratch instead of from the last calculated scores. Pinned
+    /// scores are kept.
+    pub fn reset_scores(&mut self) {
+        let score = 1f64 - self.damping;
+        for n in self.nodes.iter_mut() {
+            n.score = score;
+        }
+        for (id, score) in self.pinned.iter() {
+            self.nodes[*id].score = *score;
+        }
+    }
+}
+
+#[cfg(all(test, feature = "generators"))]
+mod tests {
+    use crate::generators;
+    use std::collections::HashMap;
+
+    #[test]
+    fn test_set_initial_scores() {
+        let mut yesterday =
+            generators::watts_strogatz(500, 6, 0.2, 3);
+        yesterday.calculate_with_convergence(1e-6);
+        let stored = yesterday
+            .nodes()
+            .into_iter()
+            .map(|(node, score)| (*node, score))
+            .collect::<HashMap<usize, f64>>();
+
+        let mut today = generators::watts_strogatz(500, 6, 0.2, 3);
+        today.add_edge(1, 2);
+        today.add_edge(3, 400);
+        let cold = today.calculate_with_convergence(1e-6);
+        today.reset_scores();
+
+        let mut stored_with_noise = stored.clone();
+        stored_with_noise.insert(1000, 1.0);
+        stored_with_noise.insert(7, f64::INFINITY);
+        assert_eq!(499, today.set_initial_scores(stored_with_noise));
+        let warm = today.calculate_with_convergence(1e-6);
+        assert!(warm.iterations * 2 < cold.iterations);
+
+        today.reset_scores();
+        let again = today.calculate_with_convergence(1e-6);
+        assert_eq!(cold.iterations, again.iterations);
+        assert_eq!(0, today.set_initial_scores(vec![(9999, 1.0)]));
+    }
+}
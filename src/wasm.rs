@@ -0,0 +1,141 @@
+//! WebAssembly bindings, to rank graphs client-side in the browser.
+//!
+//! `Ranker` is exported with `wasm-bindgen`, over string keys. Build the
+//! module and its JavaScript glue with `wasm-pack`:
+//!
+//! ```text
+//! wasm-pack build --target web -- --features wasm
+//! ```
+//!
+//! And call it from JavaScript:
+//!
+//! ```text
+//! import init, { Ranker } from "./pkg/simple_pagerank.js";
+//!
+//! await init();
+//! const pr = new Ranker();
+//! pr.addEdge("a", "b");
+//! pr.calculate();
+//! console.log(JSON.parse(pr.topK(10))); // [["b", 0.2775], ["a", 0.15]]
+//! pr.free();
+//! ```
+use crate::explain::write_json_string;
+use crate::Pagerank;
+use std::fmt::Write;
+use wasm_bindgen::prelude::*;
+
+/// Graph with string keys
+#[wasm_bindgen]
+pub struct Ranker {
+    /// The graph
+    graph: Pagerank<String>,
+}
+
+#[wasm_bindgen]
+impl Ranker {
+    /// Creates an empty graph
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> Ranker {
+        Ranker {
+            graph: Pagerank::new(),
+        }
+    }
+
+    /// Adds an edge between two keys
+    #[wasm_bindgen(js_name = addEdge)]
+    pub fn add_edge(&mut self, source: &str, target: &str) {
+        self.graph.add_edge(source.to_string(), target.to_string());
+    }
+
+    /// Calculates the scores until they converge. Returns the number of
+    /// iterations.
+    pub fn calculate(&mut self) -> usize {
+        self.graph.calculate().iterations
+    }
+
+    /// Returns the score of a key, or `undefined` if it is not in the
+    /// graph
+    #[wasm_bindgen(js_name = getScore)]
+    pub fn get_score(&self, key: &str) -> Option<f64> {
+        self.graph.get_score(key.to_string())
+    }
+
+    /// Returns the `k` highest scored keys as a JSON array of
+    /// `[key, score]` pairs, highest first. Scores which are not finite
+    /// are written as `null`.
+    #[wasm_bindgen(js_name = topK)]
+    pub fn top_k(&self, k: usize) -> String {
+        scores_json(self.graph.top_k(k))
+    }
+
+    /// Returns the number of nodes
+    pub fn len(&self) -> usize {
+        self.graph.len()
+    }
+
+    /// If the graph is empty
+    #[wasm_bindgen(js_name = isEmpty)]
+    pub fn is_empty(&self) -> bool {
+        self.graph.is_empty()
+    }
+}
+
+impl Default for Ranker {
+    fn default() -> Self {
+        Ranker::new()
+    }
+}
+
+/// Writes keys and their scores as a JSON array of `[key, score]`
+/// pairs. JSON has no NaN nor infinities, so those are written as
+/// `null`.
+fn scores_json(scores: Vec<(&String, f64)>) -> String {
+    let mut json = String::from("[");
+    for (i, (node, score)) in scores.into_iter().enumerate() {
+        if i > 0 {
+            json.push(',');
+        }
+        json.push('[');
+        write_json_string(&mut json, node);
+        if score.is_finite() {
+            let _ = write!(json, ",{}]", score);
+        } else {
+            json.push_str(",null]");
+        }
+    }
+    json.push(']');
+    json
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ranker() {
+        let mut pr = Ranker::new();
+        assert!(pr.is_empty());
+        pr.add_edge("a", "b\"");
+        assert_eq!(2, pr.len());
+        assert!(pr.calculate() > 0);
+
+        let score = pr.get_score("b\"").unwrap();
+        assert!(score > 0.15);
+        assert_eq!(None, pr.get_score("c"));
+        assert_eq!(format!("[[\"b\\\"\",{}]]", score), pr.top_k(1));
+    }
+
+    #[test]
+    fn test_scores_json() {
+        let (a, b, c) =
+            ("a".to_string(), "b".to_string(), "c".to_string());
+        assert_eq!(
+            "[[\"a\",0.5],[\"b\",null],[\"c\",null]]",
+            scores_json(vec![
+                (&a, 0.5),
+                (&b, f64::NAN),
+                (&c, f64::INFINITY)
+            ])
+        );
+    }
+}
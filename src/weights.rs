@@ -0,0 +1,390 @@
+//! Edge weights computed from a closure
+#[cfg(not(feature = "std"))]
+use crate::compat::prelude::*;
+use crate::compat::HashMap;
+use crate::graph::Node;
+use crate::Pagerank;
+use alloc::sync::Arc;
+use core::hash::{BuildHasher, Hash};
+
+/// Closure giving the weight of an edge from its source and target
+pub(crate) type WeightFn<T> =
+    Arc<dyn Fn(&T, &T) -> f64 + Send + Sync>;
+
+/// Closure giving the group of every node, as dense ids
+pub(crate) type GroupFn<T> =
+    Arc<dyn Fn(&[Node<T>]) -> Vec<usize> + Send + Sync>;
+
+/// Weights of the stored edges, computed by `finalize_weights`
+pub(crate) struct EdgeWeights {
+    /// Weight of each stored in edge, parallel to `Node::in_edges`
+    pub(crate) in_edges: Vec<Vec<f64>>,
+    /// Sum of the weights of the out edges of each node
+    pub(crate) out_totals: Vec<f64>,
+}
+
+impl<T, S> Pagerank<T, S>
+where
+    T: Eq + Hash + Clone,
+    S: BuildHasher,
+{
+    /// Sets a closure giving the weight of each edge from its source and
+    /// target, for instance from node metadata such as domain authority.
+    ///
+    /// Weights are not stored while edges are added: they are computed
+    /// once when the next calculation starts, and again only if edges
+    /// change. A node then splits its score among its out edges in
+    /// proportion to their weight instead of evenly. The closure weight
+    /// multiplies the weight given to `add_edge_weighted`, if any.
+    /// Weights which are negative, infinite or NaN count as 0; a node
+    /// whose out edges all weigh 0 passes nothing on.
+    pub fn set_weight_fn<F>(&mut self, weight: F)
+    where
+        F: Fn(&T, &T) -> f64 + Send + Sync + 'static,
+    {
+        self.weight_fn = Some(Arc::new(weight));
+        self.edge_weights = None;
+        self.thaw();
+        self.sweep = None;
+    }
+
+    /// Goes back to unweighted edges. An affinity closure, if any, is
+    /// kept.
+    pub fn clear_weight_fn(&mut self) {
+        self.weight_fn = None;
+        self.edge_weights = None;
+        self.thaw();
+        self.sweep = None;
+    }
+
+    /// Sets an affinity between the endpoints of each edge, computed from
+    /// their metadata, which multiplies the weight of the edge. It makes
+    /// the ranking attribute aware, for instance links between pages in
+    /// the same language counting more, without weighting every edge by
+    /// hand.
+    ///
+    /// `metadata` gives the metadata of a node, usually a lookup in a
+    /// map moved into the closure, and `affinity` the factor for an edge
+    /// from the metadata of its source and its target. Both are called
+    /// for every stored edge when weights are computed, as with
+    /// `set_weight_fn`, whose weight is multiplied too. As with it,
+    /// weights which are negative, infinite or NaN count as 0.
+    pub fn set_affinity_fn<M, L, F>(
+        &mut self,
+        metadata: L,
+        affinity: F,
+    ) where
+        L: Fn(&T) -> M + Send + Sync + 'static,
+        F: Fn(&M, &M) -> f64 + Send + Sync + 'static,
+    {
+        self.affinity_fn = Some(Arc::new(move |source, target| {
+            affinity(&metadata(source), &metadata(target))
+        }));
+        self.edge_weights = None;
+        self.thaw();
+        self.sweep = None;
+    }
+
+    /// Removes the affinity closure
+    pub fn clear_affinity_fn(&mut self) {
+        self.affinity_fn = None;
+        self.edge_weights = None;
+        self.thaw();
+        self.sweep = None;
+    }
+
+    /// Discounts links from the same group of sources to the same
+    /// target, where `group` gives the group of a node, for instance the
+    /// domain of a URL. It keeps a link farm, many pages of one site
+    /// linking to the same target, from counting as many independent
+    /// votes.
+    ///
+    /// When `k` stored in edges of a target come from sources of the
+    /// same group, each of them weighs `1 / k`, so the group counts as
+    /// much as a single link. The edges themselves are not changed: the
+    /// discount is applied when weights are computed, as with
+    /// `set_weight_fn`, whose weight is multiplied too, so it can be
+    /// configured before or after the edges are ingested.
+    pub fn set_group_discount<G, F>(&mut self, group: F)
+    where
+        G: Eq + Hash,
+        F: Fn(&T) -> G + Send + Sync + 'static,
+    {
+        self.group_fn = Some(Arc::new(move |nodes: &[Node<T>]| {
+            let mut ids = HashMap::new();
+            nodes
+                .iter()
+                .map(|n| {
+                    let next = ids.len();
+                    *ids.entry(group(&n.node)).or_insert(next)
+                })
+                .collect()
+        }));
+        self.edge_weights = None;
+        self.thaw();
+        self.sweep = None;
+    }
+
+    /// Stops discounting links from the same group of sources
+    pub fn clear_group_discount(&mut self) {
+        self.group_fn = None;
+        self.edge_weights = None;
+        self.thaw();
+        self.sweep = None;
+    }
+
+    /// Computes the edge weights with the weight and affinity closures
+    /// and the group discount, if there is any and they are not computed
+    /// yet
+    pub(crate) fn finalize_weights(&mut self) {
+        if self.edge_weights.is_some() {
+            return;
+        }
+        if self.weight_fn.is_none()
+            && self.affinity_fn.is_none()
+            && self.group_fn.is_none()
+        {
+            return;
+        }
+        self.edge_weights = Some(self.compute_edge_weights(&[]));
+    }
+
+    /// Computes the edge weights with the closures and the group
+    /// discount as if the nodes flagged in `removed`, if any, were not in
+    /// the graph: their edges weigh 0 and don't count in the discount.
+    /// Weights which are not finite, non negative numbers are replaced
+    /// by 0, so they can't turn the scores into NaN.
+    pub(crate) fn compute_edge_weights(
+        &self,
+        removed: &[bool],
+    ) -> EdgeWeights {
+        let is_removed = |id: usize| removed.get(id) == Some(&true);
+        let closures = [&self.weight_fn, &self.affinity_fn];
+        let weight = |source: &T, target: &T| {
+            closures
+                .iter()
+                .filter_map(|f| f.as_ref())
+                .map(|f| f(source, target))
+                .product::<f64>()
+        };
+
+        let groups = self.group_fn.as_ref().map(|f| f(&self.nodes));
+        let mut group_links = HashMap::new();
+
+        let mut out_totals = vec![0f64; self.nodes.len()];
+        let in_edges = self
+            .nodes
+            .iter()
+            .enumerate()
+            .map(|(target, n)| {
+                let scale =
+                    self.in_edges_weight_without(target, removed);
+                let sources = self.in_edges(target);
+                if let Some(groups) = groups.as_ref() {
+                    group_links.clear();
+                    for source in
+                        sources.iter().filter(|s| !is_removed(**s))
+                    {
+                        *group_links
+                            .entry(groups[*source])
+                            .or_insert(0) += 1;
+                    }
+                }
+                sources
+                    .iter()
+                    .enumerate()
+                    .map(|(slot, source)| {
+                        if is_removed(target) || is_removed(*source) {
+                            return 0f64;
+                        }
+                        let mut w = n.edge_weight(slot)
+                            * weight(
+                                &self.nodes[*source].node,
+                                &n.node,
+                            );
+                        if let Some(groups) = groups.as_ref() {
+                            w /= group_links[&groups[*source]] as f64;
+                        }
+                        if !w.is_finite() || w < 0f64 {
+                            w = 0f64;
+                        }
+                        out_totals[*source] += w * scale;
+                        w
+                    })
+                    .collect()
+            })
+            .collect();
+
+        EdgeWeights {
+            in_edges,
+            out_totals,
+        }
+    }
+
+    /// Share of the score of `source` carried by the `slot`-th stored in
+    /// edge of `target`, which comes from it: the edge weight over the
+    /// total weight of the source out edges, which is one over its out
+    /// degree when edges are not weighted.
+    pub(crate) fn edge_share(
+        &self,
+        source: usize,
+        target: usize,
+        slot: usize,
+    ) -> f64 {
+        match &self.edge_weights {
+            Some(weights) if weights.out_totals[source] > 0f64 => {
+                weights.in_edges[target][slot]
+                    / weights.out_totals[source]
+            }
+            Some(_) => 0f64,
+            None => {
+                self.nodes[target].edge_weight(slot)
+                    / self.nodes[source].out_weight
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Pagerank;
+
+    #[test]
+    fn test_weight_fn() {
+        let mut pr = Pagerank::<&str>::new();
+        pr.add_edge("hub", "en");
+        pr.add_edge("hub", "es");
+        pr.add_edge("en", "hub");
+        pr.add_edge("es", "hub");
+        pr.calculate();
+        assert_eq!(pr.get_score("en"), pr.get_score("es"));
+
+        pr.set_weight_fn(
+            |_, target| if *target == "en" { 3.0 } else { 1.0 },
+        );
+        pr.calculate_with_convergence(1e-12);
+        let hub = pr.get_score("hub").unwrap();
+        let expected = 0.15 + 0.85 * hub * 0.75;
+        assert!(
+            (pr.get_score("en").unwrap() - expected).abs() < 1e-9
+        );
+
+        // New edges are weighted on the next calculation
+        pr.add_edge("hub", "fr");
+        pr.calculate_with_convergence(1e-12);
+        let hub = pr.get_score("hub").unwrap();
+        let expected = 0.15 + 0.85 * hub * 0.2;
+        assert!(
+            (pr.get_score("fr").unwrap() - expected).abs() < 1e-9
+        );
+
+        pr.clear_weight_fn();
+        pr.calculate_with_convergence(1e-12);
+        assert_eq!(pr.get_score("fr"), pr.get_score("es"));
+    }
+
+    #[test]
+    fn test_affinity_fn() {
+        let mut pr = Pagerank::<&str>::new();
+        pr.add_edge("en:home", "en:about");
+        pr.add_edge("en:home", "es:inicio");
+        pr.add_edge("en:about", "en:home");
+        pr.add_edge("es:inicio", "en:home");
+
+        pr.set_affinity_fn(
+            |node: &&str| node[..2].to_string(),
+            |source, target| if source == target { 4.0 } else { 1.0 },
+        );
+        pr.calculate_with_convergence(1e-12);
+        let home = pr.get_score("en:home").unwrap();
+        let expected = 0.15 + 0.85 * home * 0.8;
+        assert!(
+            (pr.get_score("en:about").unwrap() - expected).abs()
+                < 1e-9
+        );
+
+        // Combined with the weight closure
+        pr.set_weight_fn(|_, target| {
+            if *target == "es:inicio" {
+                4.0
+            } else {
+                1.0
+            }
+        });
+        pr.calculate_with_convergence(1e-12);
+        assert!(
+            (pr.get_score("en:about").unwrap()
+                - pr.get_score("es:inicio").unwrap())
+            .abs()
+                < 1e-9
+        );
+
+        pr.clear_affinity_fn();
+        pr.clear_weight_fn();
+        pr.calculate_with_convergence(1e-12);
+        assert_eq!(
+            pr.get_score("en:about"),
+            pr.get_score("es:inicio")
+        );
+    }
+
+    #[test]
+    fn test_group_discount() {
+        let mut pr = Pagerank::<&str>::new();
+        for page in ["farm.com/1", "farm.com/2", "farm.com/3"].iter()
+        {
+            pr.add_edge(page, "spam.com");
+            pr.add_edge(page, "news.com");
+        }
+        pr.add_edge("blog.org/a", "news.com");
+        pr.add_edge("farm.com/1", "blog.org/a");
+        pr.calculate_with_convergence(1e-12);
+        let before = pr.get_score("spam.com").unwrap();
+
+        pr.set_group_discount(|url: &&str| url.split('/').next());
+        pr.calculate_with_convergence(1e-12);
+        let spam = pr.get_score("spam.com").unwrap();
+        assert!(spam < before);
+
+        // Links to spam.com and news.com weigh 1/3, as both have three
+        // in edges from farm.com, so the first farm page passes most of
+        // its score to blog.org/a, and the others split it evenly
+        let farm = pr.get_score("farm.com/1").unwrap();
+        let expected = 0.15 + 0.85 * farm * (0.2 + 0.5 + 0.5);
+        assert!((spam - expected).abs() < 1e-9);
+
+        pr.clear_group_discount();
+        pr.calculate_with_convergence(1e-12);
+        assert!(
+            (pr.get_score("spam.com").unwrap() - before).abs() < 1e-9
+        );
+    }
+
+    #[test]
+    fn test_invalid_weights() {
+        let mut pr = Pagerank::<&str>::new();
+        for source in ["a", "b", "c"].iter() {
+            pr.add_edge(source, "x");
+            pr.add_edge(source, "y");
+            pr.add_edge("x", source);
+        }
+        pr.set_weight_fn(|source, target| match (*source, *target) {
+            ("a", "x") => f64::INFINITY,
+            ("b", "x") => f64::NAN,
+            ("c", "x") => -1.0,
+            _ => 2.0,
+        });
+
+        // The edges to x weigh 0, so a, b and c pass their whole score
+        // to y
+        let result = pr.calculate_with_convergence(1e-12);
+        assert!(result.converged);
+        assert!((pr.get_score("x").unwrap() - 0.15).abs() < 1e-9);
+        let sources = ["a", "b", "c"]
+            .iter()
+            .map(|n| pr.get_score(n).unwrap())
+            .sum::<f64>();
+        let expected = 0.15 + 0.85 * sources;
+        assert!((pr.get_score("y").unwrap() - expected).abs() < 1e-9);
+    }
+}